@@ -0,0 +1,132 @@
+use crate::prelude::*;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use sha2::{ Digest, Sha256 };
+
+// Image hrefs starting with this point into the document's asset store,
+// followed by the hash of the asset.
+pub const ASSET_HREF_PREFIX: &str = "asset:";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub mime_type: String,
+    #[serde(with = "asset_data")]
+    pub data: Vec<u8>,
+}
+
+impl Asset {
+    // Hex encoded SHA-256 of the bytes.
+    pub fn hash(data: &[u8]) -> String {
+        Sha256::digest(data).iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, BASE64.encode(&self.data))
+    }
+
+    // Only base64 encoded data URLs are recognised.
+    pub fn from_data_url(url: &str) -> Option<Self> {
+        let (mime_type, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+        let data = BASE64.decode(data.trim()).ok()?;
+        Some(Self { mime_type: mime_type.to_string(), data })
+    }
+}
+
+// Asset bytes keyed by the hash of their content. Since the key follows from
+// the content, replicas never disagree about an entry and merging is a union.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AssetStore {
+    assets: HashMap<String, Asset>,
+}
+
+impl AssetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Asset> {
+        self.assets.get(hash)
+    }
+
+    pub fn has(&self, hash: &str) -> bool {
+        self.assets.contains_key(hash)
+    }
+
+    pub fn hashes(&self) -> HashSet<String> {
+        self.assets.keys().cloned().collect()
+    }
+
+    pub fn insert(&mut self, asset: Asset) -> String {
+        let hash = Asset::hash(&asset.data);
+        self.assets.entry(hash.clone()).or_insert(asset);
+        hash
+    }
+
+    // Adds the assets missing here and returns their hashes. Entries whose
+    // bytes do not match their hash are dropped.
+    pub fn merge_from(&mut self, other: AssetStore) -> Vec<String> {
+        let mut added = Vec::new();
+        for (hash, asset) in other.assets {
+            if self.has(&hash) || Asset::hash(&asset.data) != hash { continue; }
+            self.assets.insert(hash.clone(), asset);
+            added.push(hash);
+        }
+        added
+    }
+
+    pub fn subset(&self, hashes: &HashSet<String>) -> AssetStore {
+        let assets = self.assets.iter()
+            .filter(|(hash, _)| hashes.contains(*hash))
+            .map(|(hash, asset)| (hash.clone(), asset.clone()))
+            .collect();
+        AssetStore { assets }
+    }
+
+    pub fn retain(&mut self, hashes: &HashSet<String>) {
+        self.assets.retain(|hash, _| hashes.contains(hash));
+    }
+}
+
+// Base64 in JSON, raw bytes in the binary encoding.
+mod asset_data {
+    use super::*;
+    use serde::de::{ Error, Visitor };
+    use serde::{ Deserializer, Serializer };
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&BASE64.encode(data))
+        } else {
+            serializer.serialize_bytes(data)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            BASE64.decode(encoded).map_err(D::Error::custom)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("asset bytes")
+        }
+
+        fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(v)
+        }
+    }
+}
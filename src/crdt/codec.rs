@@ -0,0 +1,665 @@
+// Compact binary encoding for saves and broadcasts.
+//
+// Layout: the magic bytes, the format version, a table of every distinct
+// string and then the value itself. Every value starts with a tag byte, so
+// the data describes itself the way JSON does: structs are maps from field
+// names to values, unit variants are their name and other variants a map
+// with the name as the only key. Integers are LEB128 varints, floats are
+// little endian and strings are indices into the table, so ids, replica ids
+// and field names repeated across registers are only stored once.
+use crate::prelude::*;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser;
+
+const MAGIC: &[u8; 4] = b"IKTR";
+// Changes only when the encoding itself does. Changes to the saved types
+// are handled by the save format version, see `format`.
+pub const BINARY_FORMAT_VERSION: u32 = 1;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_UINT: u8 = 3;
+// Negative integers, stored as `-1 - value`.
+const TAG_NINT: u8 = 4;
+const TAG_F32: u8 = 5;
+const TAG_F64: u8 = 6;
+const TAG_STR: u8 = 7;
+const TAG_BYTES: u8 = 8;
+const TAG_SEQ: u8 = 9;
+const TAG_MAP: u8 = 10;
+
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut body = BinarySerializer { out: Vec::new(), strings: Vec::new(), string_ids: HashMap::new() };
+    value.serialize(&mut body)?;
+    let mut out = MAGIC.to_vec();
+    write_varint(&mut out, BINARY_FORMAT_VERSION as u128);
+    write_varint(&mut out, body.strings.len() as u128);
+    for string in body.strings.iter() {
+        write_varint(&mut out, string.len() as u128);
+        out.extend_from_slice(string.as_bytes());
+    }
+    out.extend_from_slice(&body.out);
+    Ok(out)
+}
+
+pub fn from_bytes<T: DeserializeOwned>(data: &[u8]) -> Result<T, DocError> {
+    let mut reader = BinaryDeserializer { data, pos: 0, strings: Vec::new() };
+    if reader.read_bytes(MAGIC.len())? != MAGIC {
+        return Err(DocError::DecodeError("not a binary document".to_string()));
+    }
+    let version = reader.read_varint()?;
+    if version != BINARY_FORMAT_VERSION as u128 {
+        let found = u32::try_from(version).unwrap_or(u32::MAX);
+        return Err(DocError::VersionMismatch { expected: BINARY_FORMAT_VERSION, found });
+    }
+    let count = reader.read_len()?;
+    for _ in 0..count {
+        let len = reader.read_len()?;
+        let bytes = reader.read_bytes(len)?;
+        let string = std::str::from_utf8(bytes).map_err(|err| DocError::DecodeError(err.to_string()))?;
+        reader.strings.push(string.to_string());
+    }
+    let value = T::deserialize(&mut reader)?;
+    if reader.pos != data.len() {
+        return Err(DocError::DecodeError("trailing bytes after the document".to_string()));
+    }
+    Ok(value)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u128) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[derive(Debug)]
+pub struct EncodeError(String);
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncodeError: {}", self.0)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl ser::Error for EncodeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        EncodeError(msg.to_string())
+    }
+}
+
+impl From<EncodeError> for DocError {
+    fn from(EncodeError(msg): EncodeError) -> Self {
+        DocError::EncodeError(msg)
+    }
+}
+
+impl de::Error for DocError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        DocError::DecodeError(msg.to_string())
+    }
+}
+
+struct BinarySerializer {
+    out: Vec<u8>,
+    strings: Vec<String>,
+    string_ids: HashMap<String, usize>,
+}
+
+impl BinarySerializer {
+    fn write_uint(&mut self, value: u128) {
+        self.out.push(TAG_UINT);
+        write_varint(&mut self.out, value);
+    }
+
+    fn write_int(&mut self, value: i128) {
+        if value >= 0 {
+            return self.write_uint(value as u128);
+        }
+        self.out.push(TAG_NINT);
+        write_varint(&mut self.out, (-1 - value) as u128);
+    }
+
+    fn write_str(&mut self, value: &str) {
+        let id = match self.string_ids.get(value) {
+            Some(id) => *id,
+            None => {
+                let id = self.strings.len();
+                self.strings.push(value.to_string());
+                self.string_ids.insert(value.to_string(), id);
+                id
+            }
+        };
+        self.out.push(TAG_STR);
+        write_varint(&mut self.out, id as u128);
+    }
+
+    // Variants other than unit ones are a map from their name to their data.
+    fn write_variant(&mut self, variant: &str) {
+        self.out.push(TAG_MAP);
+        write_varint(&mut self.out, 1);
+        self.write_str(variant);
+    }
+
+    fn begin(&mut self, tag: u8, len: Option<usize>) -> Compound<'_> {
+        self.out.push(tag);
+        if let Some(len) = len {
+            write_varint(&mut self.out, len as u128);
+        }
+        let start = self.out.len();
+        Compound { ser: self, len, start, count: 0 }
+    }
+}
+
+// A sequence, map or struct being written. Without a known length the
+// count is put in front of the entries once they are all written.
+struct Compound<'a> {
+    ser: &'a mut BinarySerializer,
+    len: Option<usize>,
+    start: usize,
+    count: usize,
+}
+
+impl Compound<'_> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.count += 1;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), EncodeError> {
+        self.count += 1;
+        self.ser.write_str(key);
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        match self.len {
+            Some(len) if len != self.count => {
+                Err(EncodeError(format!("expected {} entries, found {}", len, self.count)))
+            },
+            Some(_) => Ok(()),
+            None => {
+                let mut len = Vec::new();
+                write_varint(&mut len, self.count as u128);
+                self.ser.out.splice(self.start..self.start, len);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut BinarySerializer {
+    type Ok = ();
+    type Error = EncodeError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), EncodeError> {
+        self.out.push(if v { TAG_TRUE } else { TAG_FALSE });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), EncodeError> { self.write_int(v as i128); Ok(()) }
+    fn serialize_i16(self, v: i16) -> Result<(), EncodeError> { self.write_int(v as i128); Ok(()) }
+    fn serialize_i32(self, v: i32) -> Result<(), EncodeError> { self.write_int(v as i128); Ok(()) }
+    fn serialize_i64(self, v: i64) -> Result<(), EncodeError> { self.write_int(v as i128); Ok(()) }
+    fn serialize_i128(self, v: i128) -> Result<(), EncodeError> { self.write_int(v); Ok(()) }
+    fn serialize_u8(self, v: u8) -> Result<(), EncodeError> { self.write_uint(v as u128); Ok(()) }
+    fn serialize_u16(self, v: u16) -> Result<(), EncodeError> { self.write_uint(v as u128); Ok(()) }
+    fn serialize_u32(self, v: u32) -> Result<(), EncodeError> { self.write_uint(v as u128); Ok(()) }
+    fn serialize_u64(self, v: u64) -> Result<(), EncodeError> { self.write_uint(v as u128); Ok(()) }
+    fn serialize_u128(self, v: u128) -> Result<(), EncodeError> { self.write_uint(v); Ok(()) }
+
+    fn serialize_f32(self, v: f32) -> Result<(), EncodeError> {
+        self.out.push(TAG_F32);
+        self.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), EncodeError> {
+        self.out.push(TAG_F64);
+        self.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), EncodeError> {
+        self.write_str(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), EncodeError> {
+        self.write_str(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), EncodeError> {
+        self.out.push(TAG_BYTES);
+        write_varint(&mut self.out, v.len() as u128);
+        self.out.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), EncodeError> {
+        self.out.push(TAG_NULL);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), EncodeError> {
+        self.out.push(TAG_NULL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), EncodeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<(), EncodeError> {
+        self.write_str(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T
+    ) -> Result<(), EncodeError> {
+        self.write_variant(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a>, EncodeError> {
+        Ok(self.begin(TAG_SEQ, len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, EncodeError> {
+        Ok(self.begin(TAG_SEQ, Some(len)))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, EncodeError> {
+        Ok(self.begin(TAG_SEQ, Some(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize
+    ) -> Result<Compound<'a>, EncodeError> {
+        self.write_variant(variant);
+        Ok(self.begin(TAG_SEQ, Some(len)))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a>, EncodeError> {
+        Ok(self.begin(TAG_MAP, len))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, EncodeError> {
+        Ok(self.begin(TAG_MAP, Some(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize
+    ) -> Result<Compound<'a>, EncodeError> {
+        self.write_variant(variant);
+        Ok(self.begin(TAG_MAP, Some(len)))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> { self.element(value) }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> { self.element(value) }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> { self.element(value) }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> { self.element(value) }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), EncodeError> { self.element(key) }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), EncodeError> { value.serialize(&mut *self.ser) }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), EncodeError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), EncodeError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), EncodeError> { Compound::end(self) }
+}
+
+struct BinaryDeserializer<'de> {
+    data: &'de [u8],
+    pos: usize,
+    strings: Vec<String>,
+}
+
+impl<'de> BinaryDeserializer<'de> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'de [u8], DocError> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| DocError::DecodeError("unexpected end of data".to_string()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, DocError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn peek_byte(&self) -> Result<u8, DocError> {
+        self.data.get(self.pos)
+            .copied()
+            .ok_or_else(|| DocError::DecodeError("unexpected end of data".to_string()))
+    }
+
+    fn read_varint(&mut self) -> Result<u128, DocError> {
+        let mut value = 0u128;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if shift >= 128 { return Err(DocError::DecodeError("varint is too long".to_string())); }
+            value |= ((byte & 0x7f) as u128) << shift;
+            if byte & 0x80 == 0 { return Ok(value); }
+            shift += 7;
+        }
+    }
+
+    fn read_uint<T: TryFrom<u128>>(&mut self) -> Result<T, DocError> {
+        let value = self.read_varint()?;
+        T::try_from(value).map_err(|_| DocError::DecodeError(format!("integer {} is out of range", value)))
+    }
+
+    fn read_len(&mut self) -> Result<usize, DocError> {
+        let len = self.read_uint::<usize>()?;
+        // Every element takes at least a byte, longer lengths are corrupt
+        // and would otherwise make visitors preallocate huge buffers.
+        if len > self.data.len() - self.pos {
+            return Err(DocError::DecodeError("length exceeds the data".to_string()));
+        }
+        Ok(len)
+    }
+
+    fn read_str(&mut self) -> Result<&str, DocError> {
+        let id = self.read_uint::<usize>()?;
+        self.strings.get(id)
+            .map(|string| string.as_str())
+            .ok_or_else(|| DocError::DecodeError(format!("string {} is not in the table", id)))
+    }
+}
+
+struct Elements<'a, 'de> {
+    de: &'a mut BinaryDeserializer<'de>,
+    remaining: usize,
+}
+
+impl Elements<'_, '_> {
+    // A type reading fewer entries than were written would otherwise take
+    // the rest for whatever follows.
+    fn finish(&self) -> Result<(), DocError> {
+        if self.remaining != 0 {
+            return Err(DocError::DecodeError(format!("{} unread entries", self.remaining)));
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Elements<'a, 'de> {
+    type Error = DocError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, DocError> {
+        if self.remaining == 0 { return Ok(None); }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Elements<'a, 'de> {
+    type Error = DocError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, DocError> {
+        if self.remaining == 0 { return Ok(None); }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DocError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+// Variants with data, read from the map holding their name.
+impl<'de> de::EnumAccess<'de> for &mut BinaryDeserializer<'de> {
+    type Error = DocError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), DocError> {
+        let variant = seed.deserialize(&mut *self)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut BinaryDeserializer<'de> {
+    type Error = DocError;
+
+    fn unit_variant(self) -> Result<(), DocError> {
+        <()>::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, DocError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DocError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, DocError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+// Every value says what it is, so apart from options, newtypes and enums
+// the type's hint is not needed.
+impl<'de> de::Deserializer<'de> for &mut BinaryDeserializer<'de> {
+    type Error = DocError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> {
+        match self.read_byte()? {
+            TAG_NULL => visitor.visit_unit(),
+            TAG_FALSE => visitor.visit_bool(false),
+            TAG_TRUE => visitor.visit_bool(true),
+            TAG_UINT => {
+                let value = self.read_varint()?;
+                match u64::try_from(value) {
+                    Ok(value) => visitor.visit_u64(value),
+                    Err(_) => visitor.visit_u128(value),
+                }
+            },
+            TAG_NINT => {
+                let value = self.read_uint::<i128>()
+                    .map_err(|_| DocError::DecodeError("negative integer is out of range".to_string()))?;
+                match i64::try_from(-1 - value) {
+                    Ok(value) => visitor.visit_i64(value),
+                    Err(_) => visitor.visit_i128(-1 - value),
+                }
+            },
+            TAG_F32 => {
+                let bytes = self.read_bytes(4)?;
+                visitor.visit_f32(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            },
+            TAG_F64 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(self.read_bytes(8)?);
+                visitor.visit_f64(f64::from_le_bytes(bytes))
+            },
+            TAG_STR => visitor.visit_str(self.read_str()?),
+            TAG_BYTES => {
+                let len = self.read_len()?;
+                visitor.visit_borrowed_bytes(self.read_bytes(len)?)
+            },
+            TAG_SEQ => {
+                let remaining = self.read_len()?;
+                let mut elements = Elements { de: self, remaining };
+                let value = visitor.visit_seq(&mut elements)?;
+                elements.finish()?;
+                Ok(value)
+            },
+            TAG_MAP => {
+                let remaining = self.read_len()?;
+                let mut entries = Elements { de: self, remaining };
+                let value = visitor.visit_map(&mut entries)?;
+                entries.finish()?;
+                Ok(value)
+            },
+            tag => Err(DocError::DecodeError(format!("invalid tag {}", tag))),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> { self.deserialize_any(visitor) }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DocError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DocError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, DocError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, DocError> {
+        self.deserialize_any(visitor)
+    }
+
+    // `None` is null, anything else is the value of a `Some`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DocError> {
+        if self.peek_byte()? == TAG_NULL {
+            self.read_byte()?;
+            return visitor.visit_none();
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DocError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, DocError> {
+        match self.read_byte()? {
+            TAG_STR => visitor.visit_enum(IntoDeserializer::<DocError>::into_deserializer(self.read_str()?)),
+            TAG_MAP if self.read_varint()? == 1 => visitor.visit_enum(self),
+            _ => Err(DocError::DecodeError("expected an enum variant".to_string())),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
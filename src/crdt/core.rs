@@ -297,12 +297,12 @@ impl SVGDocCrdt {
                 path.points.push(SVGPathCommand::Close { id: point_id });
             },
             SVGPathCommandType::BEZIER => {
-                let handle1 = Vec2 { x: pos.x + 20, y: pos.y + 20 };
-                let handle2 = Vec2 { x: pos.x + 20, y: pos.y - 20 };
+                let handle1 = Vec2 { x: pos.x + 20.0, y: pos.y + 20.0 };
+                let handle2 = Vec2 { x: pos.x + 20.0, y: pos.y - 20.0 };
                 path.points.push(SVGPathCommand::Bezier { id: point_id, handle1, handle2, pos });
             },
             SVGPathCommandType::BEZIER_QUAD => {
-                let handle = Vec2 { x: pos.x, y: pos.y + 20 };
+                let handle = Vec2 { x: pos.x, y: pos.y + 20.0 };
                 path.points.push(SVGPathCommand::BezierQuad { id: point_id, handle, pos });
             }
         };
@@ -343,18 +343,18 @@ impl SVGDocCrdt {
     ) {
         let Some(path) = self.tree.find_path_mut(&path_id) else { return; };
         let Some(point) = path.find_point_mut(&point_id) else { return; };
-        let pos = Vec2 { x: 0, y: 0 };
+        let pos = Vec2 { x: 0.0, y: 0.0 };
         let command = match command_type {
             SVGPathCommandType::START => SVGPathCommand::Start { id: gen_str_id(), pos },
             SVGPathCommandType::LINE => SVGPathCommand::Line { id: gen_str_id(), pos },
             SVGPathCommandType::CLOSE => SVGPathCommand::Close { id: gen_str_id() },
             SVGPathCommandType::BEZIER => {
-                let handle1 = Vec2 { x: pos.x + 20, y: pos.y + 20 };
-                let handle2 = Vec2 { x: pos.x + 20, y: pos.y - 20 };
+                let handle1 = Vec2 { x: pos.x + 20.0, y: pos.y + 20.0 };
+                let handle2 = Vec2 { x: pos.x + 20.0, y: pos.y - 20.0 };
                 SVGPathCommand::Bezier { id: gen_str_id(), handle1, handle2, pos }
             },
             SVGPathCommandType::BEZIER_QUAD => {
-                let handle = Vec2 { x: pos.x, y: pos.y + 20 };
+                let handle = Vec2 { x: pos.x, y: pos.y + 20.0 };
                 SVGPathCommand::BezierQuad { id: gen_str_id(), handle, pos }
             },
         };
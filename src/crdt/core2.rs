@@ -1,4 +1,6 @@
 use crate::prelude::*;
+use crate::crdt::codec;
+use crate::crdt::format::{self, SavedDoc};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveLog {
@@ -6,10 +8,47 @@ pub struct MoveLog {
     new_group_id: Option<NodeID>,
     object_id: NodeID,
     index: FractionalIndex,
-    timestamp: UnixEpochTimeNanos
+    timestamp: HLCTimestamp,
+    #[serde(default)]
+    replica_id: ReplicaId,
+    // Version of the moved object right after the move, used to tell
+    // whether a peer has received it.
+    #[serde(default = "VTime::zero")]
+    vtime: VTime
+}
+
+impl MoveLog {
+    // Moves are ordered by timestamp, with the replica id breaking ties
+    // between concurrent moves made at the same instant.
+    fn order(&self) -> (HLCTimestamp, &ReplicaId) {
+        (self.timestamp, &self.replica_id)
+    }
+}
+
+// What a replica has seen, sent to peers so they can tell which tombstones
+// and moves are stable.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ack {
+    replica_id: ReplicaId,
+    version: HashMap<NodeID, VTime>,
+    // No move made by the replica after the ack is older than this.
+    time: HLCTimestamp
 }
 
 static NEW_NODE_ROOT_ID: &'static str = "NEW_NODES_ROOT_ID";
+// Parent of the document's gradients and symbols, which are kept out of
+// the tree.
+static DEFS_ROOT_ID: &str = "DEFS_ROOT_ID";
+
+// What happens to the children of a removed group.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[wasm_bindgen]
+pub enum DeletePolicy {
+    // Remove the group together with everything inside it.
+    DELETE_SUBTREE = 0,
+    // Move the children to the group's parent, where the group used to be.
+    REPARENT_CHILDREN = 1,
+}
 
 // pub struct MoveOp {
 //     group_id: Option<NodeID>,
@@ -26,49 +65,145 @@ pub struct LWWNodeMapItem {
 }
 
 impl LWWNodeMapItem {
-    pub fn new(NodeMapItem { object, parent_id, index }: NodeMapItem) -> Self {
+    pub fn new(NodeMapItem { object, parent_id, index }: NodeMapItem, stamp: &Stamp) -> Self {
         Self { 
-            object: LWWReg::new(object.into()), 
-            parent_id: LWWReg::new(parent_id), 
-            index: LWWReg::new(index)
+            object: LWWReg::new_at(LWWSVGObject::new(object, stamp), stamp), 
+            parent_id: LWWReg::new_at(parent_id, stamp), 
+            index: LWWReg::new_at(index, stamp)
         }
     }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to the item or any of its object's fields.
+        [
+            self.object.time,
+            self.object.value().time(),
+            self.parent_id.time,
+            self.index.time
+        ].into_iter().max().unwrap_or_default()
+    }
     
-    pub fn update_object(&mut self, object: SVGObject) {
-        self.object.set(object.into());
+    pub fn update_object(&mut self, object: SVGObject, stamp: &Stamp) {
+        self.object.set(LWWSVGObject::new(object, stamp), stamp);
     }
 
-    pub fn update_circle(&mut self, edits: PartialSVGCircle) {
+    pub fn update_circle(&mut self, edits: PartialSVGCircle, stamp: &Stamp) {
         let LWWSVGObject::Circle(ref mut circle) = self.object.val.borrow_mut() else { return; };
-        circle.apply_some(edits);
+        circle.apply_some(edits, stamp);
     }
 
-    pub fn update_group(&mut self, edits: PartialSVGGroup) {
+    pub fn update_group(&mut self, edits: PartialSVGGroup, stamp: &Stamp) {
         let LWWSVGObject::Group(ref mut g) = self.object.val.borrow_mut() else { return; };
-        g.apply_some(edits);
+        g.apply_some(edits, stamp);
     }
 
-    pub fn update_rectangle(&mut self, edits: PartialSVGRectangle) {
+    pub fn update_rectangle(&mut self, edits: PartialSVGRectangle, stamp: &Stamp) {
         let LWWSVGObject::Rectangle(ref mut rect) = self.object.val.borrow_mut() else { return; };
-        rect.apply_some(edits);
+        rect.apply_some(edits, stamp);
     }
 
-    pub fn update_path(&mut self, edits: PartialSVGPath) {
+    pub fn update_path(&mut self, edits: PartialSVGPath, stamp: &Stamp) {
         let LWWSVGObject::Path(ref mut path) = self.object.val.borrow_mut() else { return; };
-        path.apply_some(edits);
+        path.apply_some(edits, stamp);
     }
 
-    pub fn update_path_points(&mut self, points: Vec<SVGPathCommand>) {
-        let LWWSVGObject::Path(ref mut path) = self.object.val.borrow_mut() else { return; };
-        path.set_points(points);
+    pub fn revert_object(&mut self, before: &LWWNodeMapItem, after: &LWWNodeMapItem, stamp: &Stamp) -> bool {
+        self.object.val.revert(before.object.value(), after.object.value(), stamp)
+    }
+
+    pub fn update_ellipse(&mut self, edits: PartialSVGEllipse, stamp: &Stamp) {
+        let LWWSVGObject::Ellipse(ref mut ellipse) = self.object.val.borrow_mut() else { return; };
+        ellipse.apply_some(edits, stamp);
+    }
+
+    pub fn update_line(&mut self, edits: PartialSVGLine, stamp: &Stamp) {
+        let LWWSVGObject::Line(ref mut line) = self.object.val.borrow_mut() else { return; };
+        line.apply_some(edits, stamp);
+    }
+
+    pub fn update_polyline(&mut self, edits: PartialSVGPolyline, stamp: &Stamp) {
+        let LWWSVGObject::Polyline(ref mut polyline) = self.object.val.borrow_mut() else { return; };
+        polyline.apply_some(edits, stamp);
+    }
+
+    pub fn update_polygon(&mut self, edits: PartialSVGPolygon, stamp: &Stamp) {
+        let LWWSVGObject::Polygon(ref mut polygon) = self.object.val.borrow_mut() else { return; };
+        polygon.apply_some(edits, stamp);
+    }
+
+    pub fn update_text(&mut self, edits: PartialSVGText, stamp: &Stamp) {
+        let LWWSVGObject::Text(ref mut text) = self.object.val.borrow_mut() else { return; };
+        text.apply_some(edits, stamp);
+    }
+
+    pub fn update_image(&mut self, edits: PartialSVGImage, stamp: &Stamp) {
+        let LWWSVGObject::Image(ref mut image) = self.object.val.borrow_mut() else { return; };
+        image.apply_some(edits, stamp);
+    }
+
+    pub fn update_instance(&mut self, edits: PartialSVGInstance, stamp: &Stamp) {
+        let LWWSVGObject::Instance(ref mut instance) = self.object.val.borrow_mut() else { return; };
+        instance.apply_some(edits, stamp);
+    }
+
+    pub fn update_gradient(&mut self, edits: PartialSVGGradient, stamp: &Stamp) {
+        let LWWSVGObject::Gradient(ref mut gradient) = self.object.val.borrow_mut() else { return; };
+        gradient.apply_some(edits, stamp);
+    }
+
+    pub fn gradient_stops_mut(&mut self) -> Option<&mut LWWGradientStops> {
+        let LWWSVGObject::Gradient(ref mut gradient) = self.object.val.borrow_mut() else { return None; };
+        Some(&mut gradient.stops)
+    }
+
+    pub fn text_mut(&mut self) -> Option<&mut TextSequence> {
+        let LWWSVGObject::Text(ref mut text) = self.object.val.borrow_mut() else { return None; };
+        Some(&mut text.content)
     }
 
-    pub fn update_parent_id(&mut self, parent_id: Option<NodeID>) {
-        self.parent_id.set(parent_id);
+    pub fn transform_mut(&mut self) -> &mut LWWTransform {
+        self.object.val.transform_mut()
     }
 
-    pub fn update_index(&mut self, index: FractionalIndex) {
-        self.index.set(index);
+    pub fn clip_path_mut(&mut self) -> Option<&mut LWWReg<Option<NodeID>>> {
+        self.object.val.clip_path_mut()
+    }
+
+    pub fn mask_mut(&mut self) -> Option<&mut LWWReg<Option<NodeID>>> {
+        self.object.val.mask_mut()
+    }
+
+    pub fn name_mut(&mut self) -> Option<&mut LWWReg<String>> {
+        self.object.val.name_mut()
+    }
+
+    pub fn hidden_mut(&mut self) -> Option<&mut LWWReg<bool>> {
+        self.object.val.hidden_mut()
+    }
+
+    pub fn locked_mut(&mut self) -> Option<&mut LWWReg<bool>> {
+        self.object.val.locked_mut()
+    }
+
+    pub fn vertices_mut(&mut self) -> Option<&mut LWWSVGVertices> {
+        match self.object.val.borrow_mut() {
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.points),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.points),
+            _ => None
+        }
+    }
+
+    pub fn path_mut(&mut self) -> Option<&mut LWWSVGPath> {
+        let LWWSVGObject::Path(ref mut path) = self.object.val.borrow_mut() else { return None; };
+        Some(path)
+    }
+
+    pub fn update_parent_id(&mut self, parent_id: Option<NodeID>, stamp: &Stamp) {
+        self.parent_id.set(parent_id, stamp);
+    }
+
+    pub fn update_index(&mut self, index: FractionalIndex, stamp: &Stamp) {
+        self.index.set(index, stamp);
     }
 
     pub fn value(&self) -> NodeMapItem {
@@ -80,12 +215,6 @@ impl LWWNodeMapItem {
     }
 }
 
-impl From<NodeMapItem> for LWWNodeMapItem {
-    fn from(value: NodeMapItem) -> Self {
-        Self::new(value)
-    }
-}
-
 pub struct NodeMapItem {
     object: SVGObject,
     parent_id: Option<NodeID>,
@@ -94,23 +223,50 @@ pub struct NodeMapItem {
 
 impl Mergeable for LWWNodeMapItem {
     fn merge(&self, other: &Self) -> Self {
+        let stamp = self.object.stamp().max(other.object.stamp());
         let object = match (self.object.value(), other.object.value()) {
             (LWWSVGObject::Group(g1), LWWSVGObject::Group(g2)) => {
-                LWWReg::new(LWWSVGObject::Group(g1.merge(g2)))
+                LWWReg::new_at(LWWSVGObject::Group(g1.merge(g2)), &stamp)
             },
             (LWWSVGObject::Circle(c1), LWWSVGObject::Circle(c2)) => {
-                LWWReg::new(LWWSVGObject::Circle(c1.merge(c2)))
+                LWWReg::new_at(LWWSVGObject::Circle(c1.merge(c2)), &stamp)
             },
             (LWWSVGObject::Path(p1), LWWSVGObject::Path(p2)) => {
-                LWWReg::new(LWWSVGObject::Path(p1.merge(p2)))
+                LWWReg::new_at(LWWSVGObject::Path(p1.merge(p2)), &stamp)
             },
             (LWWSVGObject::Rectangle(r1), LWWSVGObject::Rectangle(r2)) => {
-                LWWReg::new(LWWSVGObject::Rectangle(r1.merge(r2)))
+                LWWReg::new_at(LWWSVGObject::Rectangle(r1.merge(r2)), &stamp)
+            },
+            (LWWSVGObject::Ellipse(e1), LWWSVGObject::Ellipse(e2)) => {
+                LWWReg::new_at(LWWSVGObject::Ellipse(e1.merge(e2)), &stamp)
+            },
+            (LWWSVGObject::Line(l1), LWWSVGObject::Line(l2)) => {
+                LWWReg::new_at(LWWSVGObject::Line(l1.merge(l2)), &stamp)
+            },
+            (LWWSVGObject::Polyline(p1), LWWSVGObject::Polyline(p2)) => {
+                LWWReg::new_at(LWWSVGObject::Polyline(p1.merge(p2)), &stamp)
+            },
+            (LWWSVGObject::Polygon(p1), LWWSVGObject::Polygon(p2)) => {
+                LWWReg::new_at(LWWSVGObject::Polygon(p1.merge(p2)), &stamp)
+            },
+            (LWWSVGObject::Text(t1), LWWSVGObject::Text(t2)) => {
+                LWWReg::new_at(LWWSVGObject::Text(t1.merge(t2)), &stamp)
+            },
+            (LWWSVGObject::Image(i1), LWWSVGObject::Image(i2)) => {
+                LWWReg::new_at(LWWSVGObject::Image(i1.merge(i2)), &stamp)
+            },
+            (LWWSVGObject::Gradient(g1), LWWSVGObject::Gradient(g2)) => {
+                LWWReg::new_at(LWWSVGObject::Gradient(g1.merge(g2)), &stamp)
+            },
+            (LWWSVGObject::Instance(i1), LWWSVGObject::Instance(i2)) => {
+                LWWReg::new_at(LWWSVGObject::Instance(i1.merge(i2)), &stamp)
             },
             (_, _) => { 
                 self.object.merge(&other.object) 
             }
         };
+        // The parent is left to the move log, whose replay rejects moves
+        // that would form a cycle.
         Self {
             object,
             parent_id: self.parent_id.clone(),
@@ -124,6 +280,42 @@ pub struct SVGDocCrdt2 {
     node_map: UWMap<NodeID, LWWNodeMapItem>,
     move_history: Vec<MoveLog>,
     send_buffer: Vec<MoveLog>,
+    // Keys changed since the last broadcast, used for delta broadcasts.
+    dirty: HashSet<NodeID>,
+    clock: HybridLogicalClock,
+    // Latest acknowledgement received from each peer.
+    acks: HashMap<ReplicaId, Ack>,
+    // Order of the last move dropped by compaction. Moves up to here have
+    // all been applied, so copies that peers send again are ignored.
+    compacted_until: Option<Stamp>,
+    assets: AssetStore,
+    // Assets added or received since the last broadcast.
+    unsent_assets: HashSet<String>,
+    history: UndoManager,
+    // Set while a step runs, see `step`.
+    checkpoint: Option<Checkpoint>,
+}
+
+// What a failed step puts back. Nodes are saved as they were before the
+// step first wrote them.
+struct Checkpoint {
+    nodes: HashMap<NodeID, UWMapEntry<LWWNodeMapItem>>,
+    moves: usize,
+    sent_moves: usize,
+    dirty: HashSet<NodeID>,
+    changes: Vec<Change>,
+}
+
+// What broadcasts carry and `merge` accepts.
+type Update = (UWMap<NodeID, LWWNodeMapItem>, Vec<MoveLog>, AssetStore);
+
+// Data that is neither a broadcast nor a save. A save from a newer version
+// is reported as such, anything else as an unreadable broadcast.
+fn unreadable_update(err: impl std::fmt::Display, save_err: DocError) -> DocError {
+    match save_err {
+        DocError::VersionMismatch { .. } => save_err,
+        _ => DocError::DecodeError(format!("not a broadcast or a save: {}", err)),
+    }
 }
 
 impl SVGDocCrdt2 {
@@ -132,7 +324,15 @@ impl SVGDocCrdt2 {
             replica_id,
             node_map: UWMap::new(), 
             move_history: Vec::new(),
-            send_buffer: Vec::new()
+            send_buffer: Vec::new(),
+            dirty: HashSet::new(),
+            clock: HybridLogicalClock::new(),
+            acks: HashMap::new(),
+            compacted_until: None,
+            assets: AssetStore::new(),
+            unsent_assets: HashSet::new(),
+            history: UndoManager::new(),
+            checkpoint: None
         }
     }
 
@@ -140,8 +340,148 @@ impl SVGDocCrdt2 {
         self.node_map = UWMap::new();
         self.move_history = Vec::new();
         self.send_buffer = Vec::new();
+        self.dirty = HashSet::new();
+        self.acks = HashMap::new();
+        self.compacted_until = None;
+        self.assets = AssetStore::new();
+        self.unsent_assets = HashSet::new();
+        self.history.clear();
+    }
+
+    fn stamp(&mut self) -> Stamp {
+        Stamp::new(self.clock.now(), self.replica_id.clone())
+    }
+
+    fn insert_node(&mut self, node_id: NodeID, item: LWWNodeMapItem) {
+        self.save_to_checkpoint(&node_id);
+        let before = (self.node_map.get(&node_id).cloned(), self.node_map.vtime(&node_id));
+        self.dirty.insert(node_id.clone());
+        self.node_map.insert(self.replica_id.clone(), node_id.clone(), item.clone());
+        self.history.record(&node_id, before, (Some(item), self.node_map.vtime(&node_id)));
+    }
+
+    fn remove_node(&mut self, node_id: NodeID) {
+        let Some(before) = self.node_map.get(&node_id).cloned() else { return; };
+        let before = (Some(before), self.node_map.vtime(&node_id));
+        self.save_to_checkpoint(&node_id);
+        self.dirty.insert(node_id.clone());
+        self.node_map.remove(self.replica_id.clone(), node_id.clone());
+        self.history.record(&node_id, before, (None, self.node_map.vtime(&node_id)));
     }
     
+    fn save_to_checkpoint(&mut self, node_id: &NodeID) {
+        let Some(checkpoint) = self.checkpoint.as_mut() else { return; };
+        if checkpoint.nodes.contains_key(node_id) { return; }
+        checkpoint.nodes.insert(node_id.clone(), self.node_map.entry(node_id));
+    }
+
+    // Runs a local edit as one undo step, unless it is part of a larger one.
+    // An edit that fails is rolled back as a whole, so it neither leaves
+    // half of its changes behind nor ends up on the undo stack.
+    fn step<T, F>(&mut self, f: F) -> Result<T, DocError>
+        where F: FnOnce(&mut Self) -> Result<T, DocError>
+    {
+        let outermost = self.checkpoint.is_none();
+        if outermost {
+            self.checkpoint = Some(Checkpoint {
+                nodes: HashMap::new(),
+                moves: self.move_history.len(),
+                sent_moves: self.send_buffer.len(),
+                dirty: self.dirty.clone(),
+                changes: self.history.pending(),
+            });
+        }
+        self.history.begin_step();
+        let res = f(self);
+        let checkpoint = if outermost { self.checkpoint.take() } else { None };
+        match checkpoint {
+            Some(checkpoint) if res.is_err() => self.rollback(checkpoint),
+            _ => self.history.end_step(),
+        }
+        res
+    }
+
+    // Nothing of the step has been broadcast yet, so its writes can be
+    // taken back rather than reverted with new ones.
+    fn rollback(&mut self, Checkpoint { nodes, moves, sent_moves, dirty, changes }: Checkpoint) {
+        for (node_id, entry) in nodes {
+            self.node_map.restore_entry(node_id, entry);
+        }
+        self.move_history.truncate(moves);
+        self.send_buffer.truncate(sent_moves);
+        self.dirty = dirty;
+        self.history.abort_step(changes);
+    }
+
+    pub fn begin_step(&mut self) {
+        self.history.begin_step();
+    }
+
+    pub fn end_step(&mut self) {
+        self.history.end_step();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.replay(Recording::Undo)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.replay(Recording::Redo)
+    }
+
+    fn replay(&mut self, recording: Recording) -> bool {
+        let Some(mut step) = self.history.begin_replay(recording) else { return false; };
+        while let Some(change) = step.pop() {
+            let node_id = change.node_id.clone();
+            let before_vtime = change.before_vtime.clone();
+            // Only then does the node hold nothing but what the step and
+            // its replay wrote.
+            let untouched = self.node_map.vtime(&node_id) == change.after_vtime;
+            self.revert_change(change);
+            if !untouched { continue; }
+            let Some(item) = self.node_map.get(&node_id).cloned() else { continue; };
+            let vtime = self.node_map.vtime(&node_id);
+            self.history.rebase(&node_id, &before_vtime, item, vtime);
+        }
+        self.history.end_replay();
+        true
+    }
+
+    fn revert_change(&mut self, Change { node_id, before, after, .. }: Change) {
+        // Issues new operations that restore `before`, skipping anything
+        // other replicas changed after this replica's edit.
+        let current = self.node_map.get(&node_id).cloned();
+        match (before, after, current) {
+            (None, Some(_), Some(_)) => {
+                self.remove_node(node_id);
+            },
+            (Some(before), None, None) => {
+                self.insert_node(node_id, before);
+            },
+            (Some(before), Some(after), Some(mut current)) => {
+                let now = self.stamp();
+                if current.revert_object(&before, &after, &now) {
+                    self.insert_node(node_id.clone(), current.clone());
+                }
+                // Moves go through the move log like any other move.
+                let mut index = current.index.clone();
+                if !index.revert(&before.index, &after.index, &now) { return; }
+                let group_id = before.parent_id.value().clone();
+                let Ok(item) = self.check_move(&group_id, &node_id) else { return; };
+                self.place_object(item, group_id, node_id, index.value().clone());
+            },
+            _ => {}
+        }
+    }
+
     fn is_ancestor(&self, object1_id: &str, object2_id: &str) -> bool{
         // Is object1 an ancestor of object2
         let Some(NodeMapItem { parent_id: Some(parent), .. }) = self.node_map
@@ -152,6 +492,40 @@ impl SVGDocCrdt2 {
         return self.is_ancestor(object1_id, &parent);
     }
 
+    fn is_attached(&self, node_id: &NodeID) -> bool {
+        // A node is part of the document only while all of its ancestors
+        // are. Objects moved into a group that another replica removed at the
+        // same time are removed along with it.
+        let mut visited = HashSet::new();
+        let mut node_id = node_id.clone();
+        loop {
+            if !visited.insert(node_id.clone()) { return false; }
+            let Some(item) = self.node_map.get(&node_id) else { return false; };
+            match item.parent_id.value() {
+                None => return true,
+                // Freshly added objects sit here until they are placed.
+                Some(parent_id) if parent_id == NEW_NODE_ROOT_ID => return true,
+                Some(parent_id) if parent_id == DEFS_ROOT_ID => return true,
+                Some(parent_id) => node_id = parent_id.clone(),
+            }
+        }
+    }
+
+    fn get_live(&self, node_id: &NodeID) -> Option<&LWWNodeMapItem> {
+        if !self.is_attached(node_id) { return None; }
+        self.node_map.get(node_id)
+    }
+
+    fn descendants(&self, node_id: &NodeID) -> Vec<NodeID> {
+        let mut res = Vec::new();
+        let Some(children) = self.get_children(&Some(node_id.clone())) else { return res; };
+        for (_, child_id) in children {
+            res.extend(self.descendants(&child_id));
+            res.push(child_id);
+        }
+        res
+    }
+
     fn get_children(&self, object_id: &Option<NodeID>) -> Option<Vec<(FractionalIndex, NodeID)>> {
         // Returns the children of a group node or root node.
         if let Some(object_id) = object_id {
@@ -168,21 +542,20 @@ impl SVGDocCrdt2 {
                 acc.push((idx.clone(), node_id.clone()));
                 acc
             });
-        res.sort_by(|(idx_a, _), (idx_b, _)| idx_a.cmp(idx_b));
+        // Concurrent inserts can pick the same index, the node id keeps the
+        // order the same on every replica.
+        res.sort();
         Some(res)
     }
 
     fn get_fractional_index_insert_at(&self, parent_id: &Option<NodeID>, object_id: &NodeID, index: Option<usize>) -> Option<FractionalIndex> {
         // Returns the children of a group node or root node.
         console_log!("Target index: {:?}", index);
-        let children = match self.get_children(parent_id) {
-            Some(v) => v,
-            None => return None,
-        };
+        let children = self.get_children(parent_id)?;
         let children = children.iter()
             .filter(|(_, node_id)| node_id != object_id)
             .collect::<Vec<_>>();
-        if children.len() == 0 {
+        if children.is_empty() {
             // let val = Some(thread_rng().sample(Open01));
             return Some(FractionalIndex::default());
         }
@@ -211,444 +584,1171 @@ impl SVGDocCrdt2 {
         }
         let (lower, _) = children.get(index - 1).unwrap();
         let (upper, _) = children.get(index).unwrap();
-        return FractionalIndex::new_between(lower, upper);
+        // Siblings that share an index leave no room in between, the node
+        // id then decides the order.
+        return FractionalIndex::new_between(lower, upper).or_else(|| Some(lower.clone()));
     }
 
     pub fn get_group(&self, group_id: NodeID) -> Option<SVGGroup> {
-        self.node_map.get(&group_id).map(|r| 
+        self.get_live(&group_id).and_then(|r| 
             match r.value() {
                 NodeMapItem { object: SVGObject::Group(g), .. } => Some(g),
                 _ => None
             }
         )
-        .flatten()
     }
 
     pub fn get_circle(&self, circle_id: NodeID) -> Option<SVGCircle>{
-        self.node_map.get(&circle_id).map(|r| 
+        self.get_live(&circle_id).and_then(|r| 
             match r.value() {
                 NodeMapItem { object: SVGObject::Circle(circle), .. } => Some(circle),
                 _ => None
             }
         )
-        .flatten()
     }
     
     pub fn get_rectangle(&self, rectangle_id: NodeID) -> Option<SVGRectangle> {
-        self.node_map.get(&rectangle_id)
-            .map(|r| 
+        self.get_live(&rectangle_id)
+            .and_then(|r| 
                 match r.value() {
                     NodeMapItem { object: SVGObject::Rectangle(r), .. } => Some(r),
                     _ => None
                 }
             )
-            .flatten()
     }
 
     pub fn get_path(&self, path_id: NodeID) -> Option<SVGPath> {
-        self.node_map.get(&path_id)
-            .map(|r| match r.value() {
+        self.get_live(&path_id)
+            .and_then(|r| match r.value() {
                 NodeMapItem { object: SVGObject::Path(p), .. } => Some(p),
                 _ => None
             })
-            .flatten()
+    }
+
+    pub fn get_ellipse(&self, ellipse_id: NodeID) -> Option<SVGEllipse> {
+        self.get_live(&ellipse_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Ellipse(e), .. } => Some(e),
+                _ => None
+            })
+    }
+
+    pub fn get_line(&self, line_id: NodeID) -> Option<SVGLine> {
+        self.get_live(&line_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Line(l), .. } => Some(l),
+                _ => None
+            })
+    }
+
+    pub fn get_polyline(&self, polyline_id: NodeID) -> Option<SVGPolyline> {
+        self.get_live(&polyline_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Polyline(p), .. } => Some(p),
+                _ => None
+            })
+    }
+
+    pub fn get_polygon(&self, polygon_id: NodeID) -> Option<SVGPolygon> {
+        self.get_live(&polygon_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Polygon(p), .. } => Some(p),
+                _ => None
+            })
+    }
+
+    pub fn get_text(&self, text_id: NodeID) -> Option<SVGText> {
+        self.get_live(&text_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Text(t), .. } => Some(t),
+                _ => None
+            })
+    }
+
+    pub fn get_image(&self, image_id: NodeID) -> Option<SVGImage> {
+        self.get_live(&image_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Image(i), .. } => Some(i),
+                _ => None
+            })
+    }
+
+    pub fn get_gradient(&self, gradient_id: NodeID) -> Option<SVGGradient> {
+        self.get_live(&gradient_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Gradient(g), .. } => Some(g),
+                _ => None
+            })
+    }
+
+    pub fn get_instance(&self, instance_id: NodeID) -> Option<SVGInstance> {
+        self.get_live(&instance_id)
+            .and_then(|r| match r.value() {
+                NodeMapItem { object: SVGObject::Instance(i), .. } => Some(i),
+                _ => None
+            })
+    }
+
+    // With its content, like the groups in `tree`.
+    pub fn get_symbol(&self, symbol_id: NodeID) -> Option<SVGGroup> {
+        self.symbols().into_iter().find(|symbol| symbol.id == symbol_id)
+    }
+
+    // Ordered by id, like gradients.
+    pub fn symbols(&self) -> Vec<SVGGroup> {
+        self.tree().symbols
+    }
+
+    // Ordered by id, the order they are written to `<defs>` in.
+    pub fn gradients(&self) -> Vec<SVGGradient> {
+        let mut gradients = self.node_map.value()
+            .into_values()
+            .filter(|item| item.parent_id.value().as_deref() == Some(DEFS_ROOT_ID))
+            .filter_map(|item| match item.object.value().value() {
+                SVGObject::Gradient(gradient) => Some(gradient),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        gradients.sort_by(|a, b| a.id.cmp(&b.id));
+        gradients
+    }
+
+    // Maps the object's own coordinates to document coordinates, composing
+    // the transforms of its enclosing groups.
+    pub fn world_transform(&self, object_id: NodeID) -> Result<Matrix, DocError> {
+        let Some(item) = self.get_live(&object_id) else { return Err(DocError::NotFound(object_id)); };
+        let mut matrix = item.object.value().transform().value().matrix();
+        let mut parent_id = item.parent_id.value().clone();
+        while let Some(group_id) = parent_id {
+            let Some(group) = self.node_map.get(&group_id) else { break; };
+            matrix = group.object.value().transform().value().matrix().multiply(&matrix);
+            parent_id = group.parent_id.value().clone();
+        }
+        Ok(matrix)
+    }
+
+    fn get_item(&self, node_id: &NodeID) -> Result<LWWNodeMapItem, DocError> {
+        let Some(item) = self.get_live(node_id) else { return Err(DocError::NotFound(node_id.clone())); };
+        Ok(item.clone())
+    }
+
+    fn get_item_of_kind(&self, node_id: &NodeID, kind: &'static str) -> Result<LWWNodeMapItem, DocError> {
+        let item = self.get_item(node_id)?;
+        if item.object.value().kind() != kind {
+            return Err(DocError::WrongKind { id: node_id.clone(), expected: kind });
+        }
+        Ok(item)
+    }
+
+    fn add_object(
+        &mut self,
+        group_id: Option<NodeID>,
+        object_id: NodeID,
+        object: SVGObject
+    ) -> Result<NodeID, DocError> {
+        self.step(|doc| {
+            if let Some(group_id) = group_id.as_ref() {
+                doc.get_item_of_kind(group_id, "GROUP")?;
+                doc.check_unlocked(group_id)?;
+            }
+            // Caller supplied ids must not clash with a live object.
+            if doc.node_map.get(&object_id).is_some() { return Err(DocError::DuplicateId(object_id)); }
+            let item = NodeMapItem {
+                object,
+                parent_id: Some(NEW_NODE_ROOT_ID.to_string()),
+                index: FractionalIndex::default()
+            };
+            let now = doc.stamp();
+            doc.insert_node(object_id.clone(), LWWNodeMapItem::new(item, &now));
+            doc.move_object(group_id, object_id.clone(), None)?;
+            Ok(object_id)
+        })
+    }
+
+    // Adds the objects described by SVG markup as regular operations, so
+    // the import syncs like any other edit and is undone in one step.
+    // Returns the ids of the top level objects.
+    pub fn import_svg(&mut self, svg_text: &str, parent_group_id: Option<NodeID>) -> Result<Vec<NodeID>, DocError> {
+        let objects = parse_svg(svg_text)?;
+        self.step(|doc| {
+            if let Some(group_id) = parent_group_id.as_ref() {
+                doc.get_item_of_kind(group_id, "GROUP")?;
+            }
+            objects.into_iter()
+                .map(|object| doc.import_object(parent_group_id.clone(), object))
+                .collect()
+        })
+    }
+
+    pub(crate) fn import_object(&mut self, group_id: Option<NodeID>, mut object: SVGObject) -> Result<NodeID, DocError> {
+        // Ids from the markup are kept unless they are already taken.
+        let mut object_id = object.get_id().to_string();
+        if self.node_map.get(&object_id).is_some() {
+            object_id = gen_str_id();
+            object.set_id(object_id.clone());
+        }
+        // Embedded images move into the asset store.
+        if let SVGObject::Image(image) = &mut object {
+            if let Some(asset) = Asset::from_data_url(&image.href) {
+                image.href = format!("{}{}", ASSET_HREF_PREFIX, self.add_asset(asset.data, asset.mime_type));
+            }
+        }
+        let children = match &mut object {
+            SVGObject::Group(group) => std::mem::take(&mut group.children),
+            _ => Vec::new(),
+        };
+        self.add_object(group_id, object_id.clone(), object)?;
+        for child in children {
+            self.import_object(Some(object_id.clone()), child)?;
+        }
+        Ok(object_id)
     }
 
     pub fn add_group(
         &mut self, 
         group_id: Option<String>, 
         partial_group: PartialSVGGroup
-    ) {
-        if let Some(group_id) = group_id.clone() {
-            match self.node_map.get(&group_id).map(|v| v.value()) {
-                Some(NodeMapItem { object: SVGObject::Group(_), .. }) => {},
-                _ => return,
-            }
-        }
+    ) -> Result<NodeID, DocError> {
+        self.add_group_with_id(group_id, gen_str_id(), partial_group)
+    }
+
+    pub fn add_group_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        new_group_id: NodeID,
+        partial_group: PartialSVGGroup
+    ) -> Result<NodeID, DocError> {
         let mut group = SVGGroup::default();
         group.apply_some(partial_group);
-        let new_group_id = gen_str_id();
         group.id = new_group_id.clone();
-        let item = NodeMapItem {
-            object: SVGObject::Group(group),
-            parent_id: Some(NEW_NODE_ROOT_ID.to_string()),
-            index: FractionalIndex::default()
-        };
-        self.node_map.insert(self.replica_id.clone(), new_group_id.clone(), item.into());
-        // self.parent.insert(new_group_id.clone(), (Some(NEW_NODE_ROOT_ID.to_string()), 0.5));
-        self.move_object(group_id, new_group_id, None);
+        self.add_object(group_id, new_group_id, SVGObject::Group(group))
     }
 
     pub fn add_circle(
         &mut self, 
         group_id: Option<String>, 
         partial_circle: PartialSVGCircle
-    ) {
-        if let Some(group_id) = group_id.clone() {
-            match self.node_map.get(&group_id).map(|v| v.value()) {
-                Some(NodeMapItem { object: SVGObject::Group(_), .. }) => {},
-                _ => return,
-            }
-        }
+    ) -> Result<NodeID, DocError> {
+        self.add_circle_with_id(group_id, gen_str_id(), partial_circle)
+    }
+
+    pub fn add_circle_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        circle_id: NodeID,
+        partial_circle: PartialSVGCircle
+    ) -> Result<NodeID, DocError> {
         let mut circle = SVGCircle::default();
         circle.apply_some(partial_circle);
-        let circle_id = gen_str_id();
         circle.id = circle_id.clone();
-        let item = NodeMapItem {
-            object: SVGObject::Circle(circle),
-            parent_id: Some(NEW_NODE_ROOT_ID.to_string()),
-            index: FractionalIndex::default(),
-        };
-        self.node_map.insert(self.replica_id.clone(), circle_id.clone(), item.into());
-        self.move_object(group_id, circle_id, None);
+        self.add_object(group_id, circle_id, SVGObject::Circle(circle))
     }
 
     pub fn add_rectangle(
         &mut self,
         group_id: Option<String>,
         partial_rectangle: PartialSVGRectangle
-    ) {
-        if let Some(group_id) = group_id.clone() {
-            match self.node_map.get(&group_id).map(|v| v.value()) {
-                Some(NodeMapItem { object: SVGObject::Group(_), .. }) => {},
-                _ => return,
-            }
-        }
+    ) -> Result<NodeID, DocError> {
+        self.add_rectangle_with_id(group_id, gen_str_id(), partial_rectangle)
+    }
+
+    pub fn add_rectangle_with_id(
+        &mut self,
+        group_id: Option<String>,
+        rect_id: NodeID,
+        partial_rectangle: PartialSVGRectangle
+    ) -> Result<NodeID, DocError> {
         let mut rectangle = SVGRectangle::default();
         rectangle.apply_some(partial_rectangle);
-        let rect_id = gen_str_id();
         rectangle.id = rect_id.clone();
-        let item = NodeMapItem {
-            object: SVGObject::Rectangle(rectangle),
-            parent_id: Some(NEW_NODE_ROOT_ID.to_string()),
-            index: FractionalIndex::default()
-        };
-        self.node_map.insert(self.replica_id.clone(), rect_id.clone(), item.into());
-        // self.parent.insert(rect_id.clone(), (Some(NEW_NODE_ROOT_ID.to_string()), 0.5));
-        self.move_object(group_id, rect_id, None);
+        self.add_object(group_id, rect_id, SVGObject::Rectangle(rectangle))
     }
 
     pub fn add_path(
         &mut self,
         group_id: Option<NodeID>,
         partial_path: PartialSVGPath
-    ) {
-        if let Some(group_id) = group_id.clone() {
-            match self.node_map.get(&group_id).map(|v| v.value()) {
-                Some(NodeMapItem { object: SVGObject::Group(_), .. }) => {},
-                _ => return,
-            }
-        }
+    ) -> Result<NodeID, DocError> {
+        self.add_path_with_id(group_id, gen_str_id(), partial_path)
+    }
+
+    pub fn add_path_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        path_id: NodeID,
+        partial_path: PartialSVGPath
+    ) -> Result<NodeID, DocError> {
         let mut path = SVGPath::default();
         path.apply_some(partial_path);
-        let path_id = gen_str_id();
         path.id = path_id.clone();
-        let item = NodeMapItem {
-            object: SVGObject::Path(path),
-            parent_id: Some(NEW_NODE_ROOT_ID.to_string()),
-            index: FractionalIndex::default()
-        };
-        self.node_map.insert(self.replica_id.clone(), path_id.clone(), item.into());
-        self.move_object(group_id, path_id, None);
+        self.add_object(group_id, path_id, SVGObject::Path(path))
     }
 
-    pub fn add_point_to_path(
+    pub fn add_ellipse(
         &mut self,
-        path_id: String,
-        command_type: SVGPathCommandType,
-        pos: Vec2
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), parent_id, index }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value())
-            else { return; };
-        let mut path = path.clone();
-        let point_id = gen_str_id();
-        match command_type {
-            SVGPathCommandType::START => {
-                path.points.push(SVGPathCommand::Start { id: point_id, pos });
-            },
-            SVGPathCommandType::LINE => {
-                path.points.push(SVGPathCommand::Line { id: point_id, pos });
-            },
-            SVGPathCommandType::CLOSE => {
-                path.points.push(SVGPathCommand::Close { id: point_id });
-            },
-            SVGPathCommandType::BEZIER => {
-                let handle1 = Vec2 { x: pos.x + 20, y: pos.y + 20 };
-                let handle2 = Vec2 { x: pos.x + 20, y: pos.y - 20 };
-                path.points.push(SVGPathCommand::Bezier { id: point_id, handle1, handle2, pos });
-            },
-            SVGPathCommandType::BEZIER_QUAD => {
-                let handle = Vec2 { x: pos.x, y: pos.y + 20 };
-                path.points.push(SVGPathCommand::BezierQuad { id: point_id, handle, pos });
-            },
-        };
-        let item = NodeMapItem {
-            object: SVGObject::Path(path.clone()),
-            parent_id: parent_id.clone(),
-            index: index.clone()
-        };
-        self.node_map.insert(self.replica_id.clone(), path_id, item.into());
+        group_id: Option<NodeID>,
+        partial_ellipse: PartialSVGEllipse
+    ) -> Result<NodeID, DocError> {
+        self.add_ellipse_with_id(group_id, gen_str_id(), partial_ellipse)
     }
 
-    pub fn edit_circle(&mut self, circle_id: NodeID, edits: PartialSVGCircle) {
-        let Some(NodeMapItem { object: SVGObject::Circle(_), .. }) = self.node_map
-            .get(&circle_id.clone()) 
-            .map(|v| v.value())
-            else { return; };
-        // let mut circle = circle.clone();
-        // circle.apply_some(edits); 
-        let Some(item) = self.node_map.get(&circle_id) else { return; };
-        let mut item = item.clone();
-        item.update_circle(edits);
-        self.node_map.insert(self.replica_id.clone(), circle_id, item);
-    }
-
-    pub fn edit_group(&mut self, group_id: NodeID, edits: PartialSVGGroup) {
-        let Some(NodeMapItem { object: SVGObject::Group(_), .. }) = self.node_map
-            .get(&group_id)
-            .map(|v| v.value()) else { return; };
-        let Some(item) = self.node_map.get(&group_id) else { return; };
-        let mut item = item.clone();
-        item.update_group(edits);
-        self.node_map.insert(self.replica_id.clone(), group_id, item);
-    }
-
-    pub fn edit_rectangle(&mut self, rectangle_id: NodeID, edits: PartialSVGRectangle) {
-        let Some(NodeMapItem { object: SVGObject::Rectangle(_), .. }) = self.node_map
-            .get(&rectangle_id)
-            .map(|v| v.value()) else { return; };
-        // let mut rect = rect.clone();
-        // rect.apply_some(edits);
-        let Some(item) = self.node_map.get(&rectangle_id) else { return; };
-        let mut item = item.clone();
-        item.update_rectangle(edits);
-        // item.update_object(SVGObject::Rectangle(rect));
-        self.node_map.insert(self.replica_id.clone(), rectangle_id, item);
-    }
-
-    pub fn edit_path(&mut self, path_id: NodeID, edits: PartialSVGPath) {
-        let Some(NodeMapItem { object: SVGObject::Path(_), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        // let mut path = path.clone();
-        // path.apply_some(edits);
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        item.update_path(edits);
-        // item.update_object(SVGObject::Path(path));
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
+    pub fn add_ellipse_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        ellipse_id: NodeID,
+        partial_ellipse: PartialSVGEllipse
+    ) -> Result<NodeID, DocError> {
+        let mut ellipse = SVGEllipse::default();
+        ellipse.apply_some(partial_ellipse);
+        ellipse.id = ellipse_id.clone();
+        self.add_object(group_id, ellipse_id, SVGObject::Ellipse(ellipse))
     }
 
-    pub fn edit_path_point_type(
-        &mut self, 
-        path_id: NodeID, 
-        point_id: NodeID, 
-        command_type: SVGPathCommandType
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        let mut points = path.points.clone();
-        let idx = points.iter().position(|p| p.get_id() == &point_id);
-        let Some(idx) = idx else { return; };
-        let pos = Vec2 { x: 0, y: 0 };
-        let command = match command_type {
-            SVGPathCommandType::START => SVGPathCommand::Start { id: gen_str_id(), pos },
-            SVGPathCommandType::LINE => SVGPathCommand::Line { id: gen_str_id(), pos },
-            SVGPathCommandType::CLOSE => SVGPathCommand::Close { id: gen_str_id() },
-            SVGPathCommandType::BEZIER => {
-                let handle1 = Vec2 { x: pos.x + 20, y: pos.y + 20 };
-                let handle2 = Vec2 { x: pos.x + 20, y: pos.y - 20 };
-                SVGPathCommand::Bezier { id: gen_str_id(), handle1, handle2, pos }
-            },
-            SVGPathCommandType::BEZIER_QUAD => {
-                let handle = Vec2 { x: pos.x, y: pos.y + 20 };
-                SVGPathCommand::BezierQuad { id: gen_str_id(), handle, pos }
-            },
-        };
-        match points.get_mut(idx) {
-            Some(point) => {
-                *point = command
-            },
-            _ => {}
-        };
-        // path.points = points;
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        item.update_path_points(points);
-        // item.update_object(SVGObject::Path(path));
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
+    pub fn add_line(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_line: PartialSVGLine
+    ) -> Result<NodeID, DocError> {
+        self.add_line_with_id(group_id, gen_str_id(), partial_line)
     }
 
-    pub fn edit_path_point_pos(
+    pub fn add_line_with_id(
         &mut self,
-        path_id: NodeID,
-        point_id: NodeID,
-        new_pos: Vec2
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        // let mut path = path.clone();
-        let mut points = path.points.clone();
-        let Some(idx) = points.iter().position(|p| p.get_id() == point_id) else {
-            return;
-        };
-        match points.get_mut(idx) {
-            Some(point) => {
-                match point {
-                    SVGPathCommand::Start { pos, .. } => {
-                        *pos = new_pos;
-                    },
-                    SVGPathCommand::Line { pos, .. } => {
-                        *pos = new_pos;
-                    },
-                    SVGPathCommand::Bezier { pos, .. } => {
-                        *pos = new_pos;
-                    },
-                    SVGPathCommand::BezierQuad { pos, .. } => {
-                        *pos = new_pos;
-                    },
-                    _ => ()
-                };
-            },
-            _ => {}
-        };
-        // path.points = points;
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        item.update_path_points(points);
-        // item.update_object(SVGObject::Path(path));
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
+        group_id: Option<NodeID>,
+        line_id: NodeID,
+        partial_line: PartialSVGLine
+    ) -> Result<NodeID, DocError> {
+        let mut line = SVGLine::default();
+        line.apply_some(partial_line);
+        line.id = line_id.clone();
+        self.add_object(group_id, line_id, SVGObject::Line(line))
     }
 
-    pub fn edit_path_point_handle1(
+    pub fn add_polyline(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_polyline: PartialSVGPolyline
+    ) -> Result<NodeID, DocError> {
+        self.add_polyline_with_id(group_id, gen_str_id(), partial_polyline)
+    }
+
+    pub fn add_polyline_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        polyline_id: NodeID,
+        partial_polyline: PartialSVGPolyline
+    ) -> Result<NodeID, DocError> {
+        let mut polyline = SVGPolyline::default();
+        polyline.apply_some(partial_polyline);
+        polyline.id = polyline_id.clone();
+        self.add_object(group_id, polyline_id, SVGObject::Polyline(polyline))
+    }
+
+    pub fn add_polygon(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_polygon: PartialSVGPolygon
+    ) -> Result<NodeID, DocError> {
+        self.add_polygon_with_id(group_id, gen_str_id(), partial_polygon)
+    }
+
+    pub fn add_polygon_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        polygon_id: NodeID,
+        partial_polygon: PartialSVGPolygon
+    ) -> Result<NodeID, DocError> {
+        let mut polygon = SVGPolygon::default();
+        polygon.apply_some(partial_polygon);
+        polygon.id = polygon_id.clone();
+        self.add_object(group_id, polygon_id, SVGObject::Polygon(polygon))
+    }
+
+    pub fn add_text(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_text: PartialSVGText
+    ) -> Result<NodeID, DocError> {
+        self.add_text_with_id(group_id, gen_str_id(), partial_text)
+    }
+
+    pub fn add_text_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        text_id: NodeID,
+        partial_text: PartialSVGText
+    ) -> Result<NodeID, DocError> {
+        let mut text = SVGText::default();
+        text.apply_some(partial_text);
+        text.id = text_id.clone();
+        self.add_object(group_id, text_id, SVGObject::Text(text))
+    }
+
+    pub fn add_image(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_image: PartialSVGImage
+    ) -> Result<NodeID, DocError> {
+        self.add_image_with_id(group_id, gen_str_id(), partial_image)
+    }
+
+    pub fn add_image_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        image_id: NodeID,
+        partial_image: PartialSVGImage
+    ) -> Result<NodeID, DocError> {
+        let mut image = SVGImage::default();
+        image.apply_some(partial_image);
+        image.id = image_id.clone();
+        self.add_object(group_id, image_id, SVGObject::Image(image))
+    }
+
+    pub fn add_gradient(&mut self, partial_gradient: PartialSVGGradient) -> Result<NodeID, DocError> {
+        self.add_gradient_with_id(gen_str_id(), partial_gradient)
+    }
+
+    pub fn add_gradient_with_id(
+        &mut self,
+        gradient_id: NodeID,
+        partial_gradient: PartialSVGGradient
+    ) -> Result<NodeID, DocError> {
+        let mut gradient = SVGGradient::default();
+        gradient.apply_some(partial_gradient);
+        gradient.id = gradient_id.clone();
+        self.add_def(gradient_id, SVGObject::Gradient(gradient))
+    }
+
+    // Gradients and symbols are not placed in the tree, so unlike other
+    // objects there is no move and their only parent is the defs root.
+    fn add_def(&mut self, object_id: NodeID, object: SVGObject) -> Result<NodeID, DocError> {
+        self.step(|doc| {
+            if doc.node_map.get(&object_id).is_some() { return Err(DocError::DuplicateId(object_id)); }
+            let item = NodeMapItem {
+                object,
+                parent_id: Some(DEFS_ROOT_ID.to_string()),
+                index: FractionalIndex::default()
+            };
+            let now = doc.stamp();
+            doc.insert_node(object_id.clone(), LWWNodeMapItem::new(item, &now));
+            Ok(object_id)
+        })
+    }
+
+    // A symbol is a group kept in the defs. Objects are added to and
+    // edited in it like in any group, and every instance draws the result.
+    pub fn add_symbol(&mut self, partial_group: PartialSVGGroup) -> Result<NodeID, DocError> {
+        self.add_symbol_with_id(gen_str_id(), partial_group)
+    }
+
+    pub fn add_symbol_with_id(&mut self, symbol_id: NodeID, partial_group: PartialSVGGroup) -> Result<NodeID, DocError> {
+        let mut symbol = SVGGroup::default();
+        symbol.apply_some(partial_group);
+        symbol.id = symbol_id.clone();
+        self.add_def(symbol_id, SVGObject::Group(symbol))
+    }
+
+    pub fn add_instance(
+        &mut self,
+        group_id: Option<NodeID>,
+        partial_instance: PartialSVGInstance
+    ) -> Result<NodeID, DocError> {
+        self.add_instance_with_id(group_id, gen_str_id(), partial_instance)
+    }
+
+    pub fn add_instance_with_id(
+        &mut self,
+        group_id: Option<NodeID>,
+        instance_id: NodeID,
+        partial_instance: PartialSVGInstance
+    ) -> Result<NodeID, DocError> {
+        let mut instance = SVGInstance::default();
+        instance.apply_some(partial_instance);
+        instance.id = instance_id.clone();
+        self.step(|doc| {
+            doc.check_symbol(&instance.symbol_id)?;
+            doc.add_object(group_id, instance_id, SVGObject::Instance(instance))
+        })
+    }
+
+    // Stores the bytes once and returns their hash, reference them from an
+    // image with `asset:<hash>`. Not part of the undo history.
+    pub fn add_asset(&mut self, data: Vec<u8>, mime_type: String) -> String {
+        let hash = Asset::hash(&data);
+        if !self.assets.has(&hash) {
+            self.assets.insert(Asset { mime_type, data });
+            self.unsent_assets.insert(hash.clone());
+        }
+        hash
+    }
+
+    pub fn get_asset(&self, hash: &str) -> Option<Asset> {
+        self.assets.get(hash).cloned()
+    }
+
+    // Hashes of assets that images refer to but that have not arrived yet.
+    pub fn missing_assets(&self) -> Vec<String> {
+        let mut missing = self.referenced_assets()
+            .into_iter()
+            .filter(|hash| !self.assets.has(hash))
+            .collect::<Vec<_>>();
+        missing.sort();
+        missing
+    }
+
+    // Assets referenced by any stored image, including removed ones that a
+    // concurrent edit or an undo could still bring back.
+    fn referenced_assets(&self) -> HashSet<String> {
+        self.node_map.iter()
+            .filter_map(|(_, item)| match item.object.value() {
+                LWWSVGObject::Image(image) => image.href.value()
+                    .strip_prefix(ASSET_HREF_PREFIX)
+                    .map(|hash| hash.to_string()),
+                _ => None
+            })
+            .collect()
+    }
+
+    pub fn add_point_to_path(
+        &mut self,
+        path_id: String,
+        command_type: SVGPathCommandType,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.add_point_to_path_with_id(path_id, gen_str_id(), command_type, pos)
+    }
+
+    pub fn add_point_to_path_with_id(
+        &mut self,
+        path_id: String,
+        point_id: String,
+        command_type: SVGPathCommandType,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.get_item_of_kind(&path_id, "PATH")?;
+        let last_point_id = self.get_path(path_id.clone())
+            .and_then(|path| path.points.last().map(|p| p.get_id().to_string()));
+        self.insert_point_into_path_with_id(path_id, last_point_id, point_id, command_type, pos)
+    }
+
+    pub fn insert_point_into_path(
         &mut self,
         path_id: NodeID,
-        point_id: NodeID,
-        new_handle1: Vec2
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        // let mut path = path.clone();
-        let mut points = path.points.clone();
-        let Some(idx) = points.iter().position(|p| p.get_id() == point_id) else {
-            return;
-        };
-        match points.get_mut(idx) {
-            Some(point) => {
-                match point {
-                    SVGPathCommand::Bezier { handle1, .. } => {
-                        *handle1 = new_handle1;
-                    },
-                    SVGPathCommand::BezierQuad { handle, .. } => {
-                        *handle = new_handle1;
-                    },
-                    _ => ()
+        after_point_id: Option<String>,
+        command_type: SVGPathCommandType,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.insert_point_into_path_with_id(path_id, after_point_id, gen_str_id(), command_type, pos)
+    }
+
+    pub fn insert_point_into_path_with_id(
+        &mut self,
+        path_id: NodeID,
+        after_point_id: Option<String>,
+        point_id: String,
+        command_type: SVGPathCommandType,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        let point = SVGPathCommand::new(point_id.clone(), command_type, pos);
+        self.edit_path_points(path_id, |path, now| path.insert_point_after(after_point_id.as_deref(), point, now))?;
+        Ok(point_id)
+    }
+
+    pub fn edit_circle(&mut self, circle_id: NodeID, edits: PartialSVGCircle) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&circle_id, "CIRCLE")?;
+            doc.check_unlocked(&circle_id)?;
+            let now = doc.stamp();
+            item.update_circle(edits, &now);
+            doc.insert_node(circle_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_group(&mut self, group_id: NodeID, edits: PartialSVGGroup) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&group_id, "GROUP")?;
+            doc.check_unlocked(&group_id)?;
+            let now = doc.stamp();
+            item.update_group(edits, &now);
+            doc.insert_node(group_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_rectangle(&mut self, rectangle_id: NodeID, edits: PartialSVGRectangle) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&rectangle_id, "RECTANGLE")?;
+            doc.check_unlocked(&rectangle_id)?;
+            let now = doc.stamp();
+            item.update_rectangle(edits, &now);
+            doc.insert_node(rectangle_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_path(&mut self, path_id: NodeID, edits: PartialSVGPath) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&path_id, "PATH")?;
+            doc.check_unlocked(&path_id)?;
+            let now = doc.stamp();
+            item.update_path(edits, &now);
+            doc.insert_node(path_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_ellipse(&mut self, ellipse_id: NodeID, edits: PartialSVGEllipse) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&ellipse_id, "ELLIPSE")?;
+            doc.check_unlocked(&ellipse_id)?;
+            let now = doc.stamp();
+            item.update_ellipse(edits, &now);
+            doc.insert_node(ellipse_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_line(&mut self, line_id: NodeID, edits: PartialSVGLine) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&line_id, "LINE")?;
+            doc.check_unlocked(&line_id)?;
+            let now = doc.stamp();
+            item.update_line(edits, &now);
+            doc.insert_node(line_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_polyline(&mut self, polyline_id: NodeID, edits: PartialSVGPolyline) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&polyline_id, "POLYLINE")?;
+            doc.check_unlocked(&polyline_id)?;
+            let now = doc.stamp();
+            item.update_polyline(edits, &now);
+            doc.insert_node(polyline_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_polygon(&mut self, polygon_id: NodeID, edits: PartialSVGPolygon) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&polygon_id, "POLYGON")?;
+            doc.check_unlocked(&polygon_id)?;
+            let now = doc.stamp();
+            item.update_polygon(edits, &now);
+            doc.insert_node(polygon_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_text(&mut self, text_id: NodeID, edits: PartialSVGText) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&text_id, "TEXT")?;
+            doc.check_unlocked(&text_id)?;
+            let now = doc.stamp();
+            item.update_text(edits, &now);
+            doc.insert_node(text_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_image(&mut self, image_id: NodeID, edits: PartialSVGImage) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&image_id, "IMAGE")?;
+            doc.check_unlocked(&image_id)?;
+            let now = doc.stamp();
+            item.update_image(edits, &now);
+            doc.insert_node(image_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_instance(&mut self, instance_id: NodeID, edits: PartialSVGInstance) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&instance_id, "INSTANCE")?;
+            doc.check_unlocked(&instance_id)?;
+            if let Some(symbol_id) = edits.symbol_id.as_ref() {
+                doc.check_symbol(symbol_id)?;
+                if let Some(enclosing_id) = doc.enclosing_symbol(&instance_id) {
+                    if doc.draws_symbol(symbol_id, &enclosing_id) {
+                        return Err(DocError::CycleRejected { object_id: instance_id, group_id: enclosing_id });
+                    }
                 }
-            },
-            _ => {}
+            }
+            let now = doc.stamp();
+            item.update_instance(edits, &now);
+            doc.insert_node(instance_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_gradient(&mut self, gradient_id: NodeID, edits: PartialSVGGradient) -> Result<(), DocError> {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&gradient_id, "GRADIENT")?;
+            doc.check_unlocked(&gradient_id)?;
+            let now = doc.stamp();
+            item.update_gradient(edits, &now);
+            doc.insert_node(gradient_id, item);
+            Ok(())
+        })
+    }
+
+    fn edit_gradient_stops<F>(&mut self, gradient_id: NodeID, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut LWWGradientStops, &Stamp) -> Result<(), DocError>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&gradient_id, "GRADIENT")?;
+            doc.check_unlocked(&gradient_id)?;
+            let now = doc.stamp();
+            let Some(stops) = item.gradient_stops_mut() else { return Ok(()); };
+            f(stops, &now)?;
+            doc.insert_node(gradient_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn add_gradient_stop(&mut self, gradient_id: NodeID, offset: f64, color: Color) -> Result<String, DocError> {
+        self.add_gradient_stop_with_id(gradient_id, gen_str_id(), offset, color)
+    }
+
+    pub fn add_gradient_stop_with_id(
+        &mut self,
+        gradient_id: NodeID,
+        stop_id: String,
+        offset: f64,
+        color: Color
+    ) -> Result<String, DocError> {
+        let stop = SVGGradientStop { id: stop_id.clone(), offset, color };
+        self.edit_gradient_stops(gradient_id, |stops, now| stops.insert(stop, now))?;
+        Ok(stop_id)
+    }
+
+    pub fn edit_gradient_stop(
+        &mut self,
+        gradient_id: NodeID,
+        stop_id: String,
+        edits: PartialSVGGradientStop
+    ) -> Result<(), DocError> {
+        self.edit_gradient_stops(gradient_id, |stops, now| stops.edit(&stop_id, edits, now))
+    }
+
+    pub fn remove_gradient_stop(&mut self, gradient_id: NodeID, stop_id: String) -> Result<(), DocError> {
+        self.edit_gradient_stops(gradient_id, |stops, now| stops.remove(&stop_id, now))
+    }
+
+    fn edit_transform<F>(&mut self, object_id: NodeID, f: F) -> Result<(), DocError>
+        where F: FnOnce(Transform) -> Transform
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item(&object_id)?;
+            doc.check_unlocked(&object_id)?;
+            let now = doc.stamp();
+            let transform = item.transform_mut();
+            let edited = f(transform.value());
+            transform.set(edited, &now);
+            doc.insert_node(object_id, item);
+            Ok(())
+        })
+    }
+
+    // `pivot` is in the object's own coordinates, the same ones as its
+    // `pos`, e.g. the center of a circle to spin it in place.
+    pub fn rotate_object(&mut self, object_id: NodeID, degrees: f64, pivot: Vec2) -> Result<(), DocError> {
+        self.edit_transform(object_id, |transform| transform.rotated_around(degrees, pivot))
+    }
+
+    pub fn scale_object(&mut self, object_id: NodeID, scale_x: f64, scale_y: f64, pivot: Vec2) -> Result<(), DocError> {
+        self.edit_transform(object_id, |transform| transform.scaled_around(scale_x, scale_y, pivot))
+    }
+
+    // The source stays where it is in the tree but is only drawn through the
+    // objects it clips. `None` removes the clip.
+    pub fn set_clip_path(&mut self, object_id: NodeID, source_id: Option<NodeID>) -> Result<(), DocError> {
+        self.edit_clip_ref(object_id, source_id, LWWNodeMapItem::clip_path_mut)
+    }
+
+    pub fn set_mask(&mut self, object_id: NodeID, source_id: Option<NodeID>) -> Result<(), DocError> {
+        self.edit_clip_ref(object_id, source_id, LWWNodeMapItem::mask_mut)
+    }
+
+    // Naming, hiding and locking work on locked objects too, otherwise a
+    // locked object could never be unlocked.
+    pub fn set_name(&mut self, object_id: NodeID, name: String) -> Result<(), DocError> {
+        self.edit_layer(object_id, name, LWWNodeMapItem::name_mut)
+    }
+
+    pub fn set_hidden(&mut self, object_id: NodeID, hidden: bool) -> Result<(), DocError> {
+        self.edit_layer(object_id, hidden, LWWNodeMapItem::hidden_mut)
+    }
+
+    pub fn set_locked(&mut self, object_id: NodeID, locked: bool) -> Result<(), DocError> {
+        self.edit_layer(object_id, locked, LWWNodeMapItem::locked_mut)
+    }
+
+    fn edit_layer<T, F>(&mut self, object_id: NodeID, value: T, f: F) -> Result<(), DocError>
+        where T: Clone, F: FnOnce(&mut LWWNodeMapItem) -> Option<&mut LWWReg<T>>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item(&object_id)?;
+            let now = doc.stamp();
+            let Some(field) = f(&mut item) else {
+                return Err(DocError::WrongKind { id: object_id, expected: "shape or group" });
+            };
+            field.set(value, &now);
+            doc.insert_node(object_id, item);
+            Ok(())
+        })
+    }
+
+    fn edit_clip_ref<F>(&mut self, object_id: NodeID, source_id: Option<NodeID>, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut LWWNodeMapItem) -> Option<&mut LWWReg<Option<NodeID>>>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item(&object_id)?;
+            doc.check_unlocked(&object_id)?;
+            if let Some(source_id) = &source_id {
+                doc.check_clip_source(&object_id, source_id)?;
+            }
+            let now = doc.stamp();
+            let Some(reference) = f(&mut item) else {
+                return Err(DocError::WrongKind { id: object_id, expected: "shape or group" });
+            };
+            reference.set(source_id, &now);
+            doc.insert_node(object_id, item);
+            Ok(())
+        })
+    }
+
+    // Concurrent moves can still make the source an ancestor, export skips
+    // such clips.
+    fn check_clip_source(&self, object_id: &NodeID, source_id: &NodeID) -> Result<(), DocError> {
+        let source = self.get_item(source_id)?;
+        if source.object.value().kind() == "GRADIENT" {
+            return Err(DocError::WrongKind { id: source_id.clone(), expected: "shape or group" });
+        }
+        if source_id == object_id || self.is_ancestor(source_id, object_id) {
+            return Err(DocError::ClipCycle { object_id: object_id.clone(), source_id: source_id.clone() });
+        }
+        Ok(())
+    }
+
+    fn edit_text_content<F>(&mut self, text_id: NodeID, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut TextSequence, &Stamp) -> Result<(), DocError>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&text_id, "TEXT")?;
+            doc.check_unlocked(&text_id)?;
+            let now = doc.stamp();
+            let Some(content) = item.text_mut() else { return Ok(()); };
+            f(content, &now)?;
+            doc.insert_node(text_id, item);
+            Ok(())
+        })
+    }
+
+    // `index` counts characters, not bytes.
+    pub fn insert_text(&mut self, text_id: NodeID, index: usize, text: String) -> Result<(), DocError> {
+        self.edit_text_content(text_id, |content, now| content.insert(index, &text, now))
+    }
+
+    pub fn delete_text(&mut self, text_id: NodeID, index: usize, length: usize) -> Result<(), DocError> {
+        self.edit_text_content(text_id, |content, now| content.delete(index, length, now))
+    }
+
+    // Vertex edits apply to polylines and polygons alike.
+    fn edit_vertices<F>(&mut self, object_id: NodeID, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut LWWSVGVertices, &Stamp) -> Result<(), DocError>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item(&object_id)?;
+            doc.check_unlocked(&object_id)?;
+            let now = doc.stamp();
+            let Some(vertices) = item.vertices_mut() else {
+                return Err(DocError::WrongKind { id: object_id, expected: "POLYLINE or POLYGON" });
+            };
+            f(vertices, &now)?;
+            doc.insert_node(object_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn add_vertex(&mut self, object_id: NodeID, pos: Vec2) -> Result<String, DocError> {
+        self.add_vertex_with_id(object_id, gen_str_id(), pos)
+    }
+
+    pub fn add_vertex_with_id(&mut self, object_id: NodeID, vertex_id: String, pos: Vec2) -> Result<String, DocError> {
+        let last_vertex_id = match self.get_item(&object_id)?.value().object {
+            SVGObject::Polyline(polyline) => polyline.points.last().map(|v| v.id.clone()),
+            SVGObject::Polygon(polygon) => polygon.points.last().map(|v| v.id.clone()),
+            _ => None
         };
-        // path.points = points;
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        // item.update_object(SVGObject::Path(path));
-        item.update_path_points(points);
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
+        self.insert_vertex_with_id(object_id, last_vertex_id, vertex_id, pos)
     }
 
-    pub fn edit_path_point_handle2(
+    pub fn insert_vertex(
         &mut self,
-        path_id: NodeID,
-        point_id: NodeID,
+        object_id: NodeID,
+        after_vertex_id: Option<String>,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.insert_vertex_with_id(object_id, after_vertex_id, gen_str_id(), pos)
+    }
+
+    pub fn insert_vertex_with_id(
+        &mut self,
+        object_id: NodeID,
+        after_vertex_id: Option<String>,
+        vertex_id: String,
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        let vertex = SVGVertex { id: vertex_id.clone(), pos };
+        self.edit_vertices(object_id, |vertices, now| vertices.insert_after(after_vertex_id.as_deref(), vertex, now))?;
+        Ok(vertex_id)
+    }
+
+    pub fn edit_vertex_pos(&mut self, object_id: NodeID, vertex_id: String, new_pos: Vec2) -> Result<(), DocError> {
+        self.edit_vertices(object_id, |vertices, now| vertices.edit_pos(&vertex_id, new_pos, now))
+    }
+
+    pub fn remove_vertex(&mut self, object_id: NodeID, vertex_id: String) -> Result<(), DocError> {
+        self.edit_vertices(object_id, |vertices, now| vertices.remove(&vertex_id, now))
+    }
+
+    fn edit_path_points<F>(&mut self, path_id: NodeID, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut LWWSVGPath, &Stamp) -> Result<(), DocError>
+    {
+        self.step(|doc| {
+            let mut item = doc.get_item_of_kind(&path_id, "PATH")?;
+            doc.check_unlocked(&path_id)?;
+            let now = doc.stamp();
+            let Some(path) = item.path_mut() else { return Err(DocError::WrongKind { id: path_id, expected: "PATH" }); };
+            f(path, &now)?;
+            doc.insert_node(path_id, item);
+            Ok(())
+        })
+    }
+
+    pub fn edit_path_point_type(
+        &mut self, 
+        path_id: NodeID, 
+        point_id: NodeID, 
+        command_type: SVGPathCommandType
+    ) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.edit_point_type(&point_id, command_type, now))
+    }
+
+    pub fn edit_path_point_pos(
+        &mut self, 
+        path_id: NodeID, 
+        point_id: NodeID, 
+        new_pos: Vec2
+    ) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.edit_point_pos(&point_id, new_pos, now))
+    }
+
+    pub fn edit_path_point_handle1(
+        &mut self, 
+        path_id: NodeID, 
+        point_id: NodeID, 
+        new_handle1: Vec2
+    ) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.edit_point_handle1(&point_id, new_handle1, now))
+    }
+
+    pub fn edit_path_point_handle2(
+        &mut self, 
+        path_id: NodeID, 
+        point_id: NodeID, 
         new_handle2: Vec2
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        // let mut path = path.clone();
-        let mut points = path.points.clone();
-        let Some(idx) = points.iter().position(|p| p.get_id() == point_id) else {
-            return;
-        };
-        match points.get_mut(idx) {
-            Some(point) => {
-                match point {
-                    SVGPathCommand::Bezier { handle2, .. } => {
-                        *handle2 = new_handle2;
-                    },
-                    _ => ()
-                };
-            },
-            _ => {}
-        };
-        // path.points = points;
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        // item.update_object(SVGObject::Path(path));
-        item.update_path_points(points);
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
+    ) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.edit_point_handle2(&point_id, new_handle2, now))
     }
 
-    pub fn remove_object(&mut self, node_id: NodeID) {
-        self.node_map.remove(self.replica_id.clone(), node_id.clone());
-        // let Some(group_id) = self.parent.remove(&node_id.clone()) else { return; };
+    // Removing a gradient leaves the fills and strokes that use it pointing
+    // at nothing, and they are painted with their color instead. Objects
+    // clipped or masked by a removed object are drawn unclipped, and a
+    // source no longer used by anything is drawn again. `policy` decides
+    // what happens to the children when the object is a group.
+    pub fn remove_object(&mut self, node_id: NodeID, policy: DeletePolicy) -> Result<(), DocError> {
+        self.step(|doc| {
+            let item = doc.get_item(&node_id)?;
+            doc.check_unlocked(&node_id)?;
+            if policy == DeletePolicy::REPARENT_CHILDREN && item.object.value().kind() == "GROUP" {
+                let parent_id = item.value().parent_id;
+                let position = doc.get_children(&parent_id)
+                    .unwrap_or_default()
+                    .iter()
+                    .position(|(_, id)| *id == node_id)
+                    .unwrap_or_default();
+                let children = doc.get_children(&Some(node_id.clone())).unwrap_or_default();
+                // All or nothing, so no child is moved out if another one
+                // is locked.
+                if let Some((_, locked_id)) = children.iter().find(|(_, id)| doc.is_locked(id)) {
+                    return Err(DocError::Locked(locked_id.clone()));
+                }
+                // Each child lands right before the group, so they keep their order.
+                for (i, (_, child_id)) in children.into_iter().enumerate() {
+                    doc.move_object(parent_id.clone(), child_id, Some(position + i))?;
+                }
+                doc.remove_node(node_id);
+                return Ok(());
+            }
+            // Locked objects inside would go along with the group.
+            let descendants = doc.descendants(&node_id);
+            if let Some(locked_id) = descendants.iter().find(|id| doc.is_locked(id)) {
+                return Err(DocError::Locked(locked_id.clone()));
+            }
+            for descendant_id in descendants {
+                doc.remove_node(descendant_id);
+            }
+            doc.remove_node(node_id);
+            Ok(())
+        })
+    }
+
+    pub fn remove_group(&mut self, group_id: NodeID, policy: DeletePolicy) -> Result<(), DocError> {
+        self.get_item_of_kind(&group_id, "GROUP")?;
+        self.remove_object(group_id, policy)
     }
 
     pub fn remove_path_point(
         &mut self,
         path_id: NodeID,
         point_id: NodeID
-    ) {
-        let Some(NodeMapItem { object: SVGObject::Path(path), .. }) = self.node_map
-            .get(&path_id)
-            .map(|v| v.value()) else { return; };
-        let mut points = path.points;
-        let index = points.iter()
-            .position(|o| o.get_id().eq(&point_id));
-        let Some(index) = index else { return; };
-        points.remove(index);
-        let Some(item) = self.node_map.get(&path_id) else { return; };
-        let mut item = item.clone();
-        // item.update_object(SVGObject::Path(path));
-        item.update_path_points(points);
-        self.node_map.insert(self.replica_id.clone(), path_id, item);
-    }
-
-    pub fn move_object(&mut self, group_id: Option<NodeID>, object_id: String, index: Option<usize>) {
-        let now = epoch_now_nanos();
-        let Some(NodeMapItem { parent_id: old_group_id, .. }) = self.node_map
-            .get(&object_id)
-            .map(|v| v.value()) else { return; };
-        let old_group_id = old_group_id.clone();
-        let Some(_) = self.node_map.get(&object_id).map(|o| o.clone()) else { return; };
-        if let Some(group_id) = group_id {
-            if self.is_ancestor(&object_id, &group_id) { return; }
-
-            // get Fractional index
-            let Some(index) = self.get_fractional_index_insert_at(&Some(group_id.clone()), &object_id, index) else { return; };
-            let Some(item) = self.node_map.get(&object_id) else { return; };
-            let mut item = item.clone();
-            item.update_parent_id(Some(group_id.clone()));
-            item.update_index(index.clone());
-            self.node_map.insert(self.replica_id.clone(), object_id.clone(), item);
-            let move_log = MoveLog { new_group_id: Some(group_id), old_group_id, index, object_id, timestamp: now };
-            self.send_buffer.push(move_log.clone());
-            self.move_history.push(move_log);
-            return;
-        }
-        // get fractional index
-
-        let Some(index) = self.get_fractional_index_insert_at(&None, &object_id, index) else { return; };
-        let Some(item) = self.node_map.get(&object_id) else { return; };
-        let mut item = item.clone();
-        item.update_parent_id(None);
-        item.update_index(index.clone());
-        self.node_map.insert(self.replica_id.clone(), object_id.clone(), item);
-        let move_log = MoveLog { new_group_id: None, old_group_id, index, object_id, timestamp: now };
+    ) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.remove_point(&point_id, now))
+    }
+
+    pub fn get_path_subpaths(&self, path_id: NodeID) -> Result<Vec<SVGSubpath>, DocError> {
+        self.get_item_of_kind(&path_id, "PATH")?;
+        Ok(self.get_path(path_id).map(|path| path.subpaths()).unwrap_or_default())
+    }
+
+    // `index` counts subpaths, with the subpath itself left out.
+    pub fn move_subpath(&mut self, path_id: NodeID, subpath_id: String, index: usize) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.move_subpath(&subpath_id, index, now))
+    }
+
+    pub fn reverse_subpath(&mut self, path_id: NodeID, subpath_id: String) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.reverse_subpath(&subpath_id, now))
+    }
+
+    pub fn remove_subpath(&mut self, path_id: NodeID, subpath_id: String) -> Result<(), DocError> {
+        self.edit_path_points(path_id, |path, now| path.remove_subpath(&subpath_id, now))
+    }
+
+    pub fn move_object(&mut self, group_id: Option<NodeID>, object_id: String, index: Option<usize>) -> Result<(), DocError> {
+        self.step(|doc| {
+            let item = doc.check_move(&group_id, &object_id)?;
+            // Undo moves through `check_move` alone, so only calls made
+            // here are held back by locks.
+            doc.check_unlocked(&object_id)?;
+            if let Some(group_id) = group_id.as_ref() {
+                doc.check_unlocked(group_id)?;
+            }
+            let Some(index) = doc.get_fractional_index_insert_at(&group_id, &object_id, index) else {
+                return Err(DocError::NotFound(group_id.unwrap_or_default()));
+            };
+            doc.place_object(item, group_id, object_id, index);
+            Ok(())
+        })
+    }
+
+    fn check_move(&self, group_id: &Option<NodeID>, object_id: &NodeID) -> Result<LWWNodeMapItem, DocError> {
+        let item = self.get_item(object_id)?;
+        if item.parent_id.value().as_deref() == Some(DEFS_ROOT_ID) {
+            return Err(DocError::WrongKind { id: object_id.clone(), expected: "shape or group" });
+        }
+        if let Some(group_id) = group_id.as_ref() {
+            self.get_item_of_kind(group_id, "GROUP")?;
+            if object_id == group_id || self.is_ancestor(object_id, group_id) {
+                return Err(DocError::CycleRejected { object_id: object_id.clone(), group_id: group_id.clone() });
+            }
+            // A symbol may not draw itself through the instances moved in.
+            if let Some(symbol_id) = self.enclosing_symbol(group_id) {
+                let moved = std::iter::once(object_id.clone()).chain(self.descendants(object_id));
+                let draws_itself = moved.filter_map(|node_id| self.instance_symbol(&node_id))
+                    .any(|used_id| self.draws_symbol(&used_id, &symbol_id));
+                if draws_itself {
+                    return Err(DocError::CycleRejected { object_id: object_id.clone(), group_id: symbol_id });
+                }
+            }
+        }
+        Ok(item)
+    }
+
+    fn check_symbol(&self, symbol_id: &NodeID) -> Result<(), DocError> {
+        let item = self.get_item_of_kind(symbol_id, "GROUP")?;
+        if item.parent_id.value().as_deref() != Some(DEFS_ROOT_ID) {
+            return Err(DocError::WrongKind { id: symbol_id.clone(), expected: "symbol" });
+        }
+        Ok(())
+    }
+
+    fn is_locked(&self, node_id: &NodeID) -> bool {
+        self.node_map.get(node_id).is_some_and(|item| item.object.value().is_locked())
+    }
+
+    // Locking a group locks everything inside it, so the lock may come
+    // from any of the node's groups.
+    fn check_unlocked(&self, node_id: &NodeID) -> Result<(), DocError> {
+        let mut visited = HashSet::new();
+        let mut node_id = node_id.clone();
+        while visited.insert(node_id.clone()) {
+            if self.is_locked(&node_id) { return Err(DocError::Locked(node_id)); }
+            let Some(parent_id) = self.node_map.get(&node_id).and_then(|item| item.parent_id.value().clone()) else { break; };
+            node_id = parent_id;
+        }
+        Ok(())
+    }
+
+    // The symbol the node is part of, the node itself included.
+    fn enclosing_symbol(&self, node_id: &NodeID) -> Option<NodeID> {
+        let mut visited = HashSet::new();
+        let mut node_id = node_id.clone();
+        loop {
+            if !visited.insert(node_id.clone()) { return None; }
+            let parent_id = self.node_map.get(&node_id)?.parent_id.value().clone()?;
+            if parent_id == DEFS_ROOT_ID { return Some(node_id); }
+            node_id = parent_id;
+        }
+    }
+
+    fn instance_symbol(&self, node_id: &NodeID) -> Option<NodeID> {
+        let LWWSVGObject::Instance(instance) = self.node_map.get(node_id)?.object.value() else { return None; };
+        Some(instance.symbol_id.value().clone())
+    }
+
+    // Whether drawing `symbol_id` ends up drawing `target_id`, counting the
+    // symbol itself.
+    fn draws_symbol(&self, symbol_id: &NodeID, target_id: &NodeID) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![symbol_id.clone()];
+        while let Some(symbol_id) = pending.pop() {
+            if &symbol_id == target_id { return true; }
+            if !visited.insert(symbol_id.clone()) { continue; }
+            pending.extend(self.descendants(&symbol_id).iter().filter_map(|node_id| self.instance_symbol(node_id)));
+        }
+        false
+    }
+
+    fn place_object(&mut self, mut item: LWWNodeMapItem, group_id: Option<NodeID>, object_id: NodeID, index: FractionalIndex) {
+        let old_group_id = item.value().parent_id;
+        let now = self.stamp();
+        item.update_parent_id(group_id.clone(), &now);
+        item.update_index(index.clone(), &now);
+        self.insert_node(object_id.clone(), item);
+        let vtime = self.node_map.vtime(&object_id).unwrap_or(VTime::zero());
+        let move_log = MoveLog { 
+            new_group_id: group_id, 
+            old_group_id, 
+            index, 
+            object_id, 
+            timestamp: now.time, 
+            replica_id: now.replica_id,
+            vtime
+        };
         self.send_buffer.push(move_log.clone());
         self.move_history.push(move_log);
     }
@@ -662,10 +1762,10 @@ impl SVGDocCrdt2 {
         }
         let item = LWWNodeMapItem {
             object: lww_node_map.object.clone(),
-            parent_id: LWWReg { val: new_group_id, time: lww_node_map.parent_id.time },
-            index: LWWReg { val: index, time: lww_node_map.index.time }
+            parent_id: LWWReg::new_at(new_group_id, &lww_node_map.parent_id.stamp()),
+            index: LWWReg::new_at(index, &lww_node_map.index.stamp())
         };
-        self.node_map.insert_novtime_update(object_id, item.into());
+        self.node_map.insert_novtime_update(object_id, item);
     }
 
     fn undo_move(&mut self, MoveLog { old_group_id, object_id, .. }: MoveLog) {
@@ -674,8 +1774,8 @@ impl SVGDocCrdt2 {
 
         let item = LWWNodeMapItem {
             object: lww_node_map.object.clone(),
-            index: LWWReg { val: FractionalIndex::default(), time: lww_node_map.index.time },
-            parent_id: LWWReg { val: old_group_id, time: lww_node_map.parent_id.time }
+            index: LWWReg::new_at(FractionalIndex::default(), &lww_node_map.index.stamp()),
+            parent_id: LWWReg::new_at(old_group_id, &lww_node_map.parent_id.stamp())
         };
         self.node_map.insert_novtime_update(object_id, item);
     }
@@ -685,11 +1785,11 @@ impl SVGDocCrdt2 {
         let move_history = self.move_history.clone();
         let mut move_already_exists = false;
         for (i, hist) in move_history.iter().enumerate().rev() {
-            if hist.timestamp == move_log.timestamp { 
+            if hist.order() == move_log.order() { 
                 move_already_exists = true;
                 break;
              }
-            if hist.timestamp < move_log.timestamp {
+            if hist.order() < move_log.order() {
                 break
             }
             self.undo_move(hist.clone());
@@ -706,44 +1806,306 @@ impl SVGDocCrdt2 {
     }
 
     fn merge_aux(&mut self, other_node_map: UWMap<NodeID, LWWNodeMapItem>, mut move_logs: Vec<MoveLog>) {
+        // Move past everything the other replica has seen so that edits made
+        // after this merge win over the ones we just received.
+        let remote_time = other_node_map.iter()
+            .map(|(_, item)| item.time())
+            .chain(move_logs.iter().map(|log| log.timestamp))
+            .max();
+        if let Some(remote_time) = remote_time {
+            self.clock.update(remote_time);
+        }
+        let before = other_node_map.keys()
+            .into_iter()
+            .map(|k| { let vtime = self.node_map.vtime(&k); (k, vtime) })
+            .collect::<Vec<_>>();
         self.node_map = UWMap::merge(&self.node_map, &other_node_map);
+        // Keys we learned about are relayed on our next delta broadcast,
+        // so peers that are not directly connected still converge.
+        for (k, vtime) in before {
+            if self.node_map.vtime(&k) != vtime {
+                self.dirty.insert(k);
+            }
+        }
         for log in move_logs.drain(..) {
+            if let Some(until) = self.compacted_until.as_ref() {
+                if log.order() <= (until.time, &until.replica_id) { continue; }
+            }
             self.add_to_move_log(log);
         }
     }
 
+    fn merge_update(&mut self, (other_node_map, move_logs, assets): Update) {
+        self.merge_aux(other_node_map, move_logs);
+        // Relayed like the keys above.
+        self.unsent_assets.extend(self.assets.merge_from(assets));
+    }
+
     fn broadcast_aux(&mut self) -> (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>){
         let res = (self.node_map.clone(), self.send_buffer.clone());
         self.send_buffer.clear();
+        self.dirty.clear();
         res
     }
 
+    fn broadcast_delta_aux(&mut self) -> (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>) {
+        let dirty = std::mem::take(&mut self.dirty);
+        let res = (self.node_map.delta(&dirty), self.send_buffer.clone());
+        self.send_buffer.clear();
+        res
+    }
+
+    fn broadcast_since_aux(&self, version: &HashMap<NodeID, VTime>) -> (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>) {
+        let delta = self.node_map.delta_since(version);
+        let keys = delta.keys();
+        // Every move bumps the vtime of the moved object, so the moves the
+        // peer is missing are among those of the objects in the delta.
+        let move_logs = self.move_history.iter()
+            .filter(|log| keys.contains(&log.object_id))
+            .cloned()
+            .collect();
+        (delta, move_logs)
+    }
+
+    // Broadcasts carry every referenced asset, deltas the ones that are new
+    // since the last broadcast. Assets no image uses yet wait for a delta.
+    fn full_update(&mut self) -> Update {
+        let (node_map, move_logs) = self.broadcast_aux();
+        let referenced = self.referenced_assets();
+        self.unsent_assets.retain(|hash| !referenced.contains(hash));
+        (node_map, move_logs, self.assets.subset(&referenced))
+    }
+
+    fn delta_update(&mut self) -> Update {
+        let (node_map, move_logs) = self.broadcast_delta_aux();
+        let unsent = std::mem::take(&mut self.unsent_assets);
+        (node_map, move_logs, self.assets.subset(&unsent))
+    }
+
+    // The peer may lack the assets of any image in the delta.
+    fn update_since(&self, version: &HashMap<NodeID, VTime>) -> Update {
+        let (node_map, move_logs) = self.broadcast_since_aux(version);
+        let hashes = node_map.iter()
+            .filter_map(|(_, item)| match item.object.value() {
+                LWWSVGObject::Image(image) => image.href.value().strip_prefix(ASSET_HREF_PREFIX),
+                _ => None
+            })
+            .map(|hash| hash.to_string())
+            .collect();
+        let assets = self.assets.subset(&hashes);
+        (node_map, move_logs, assets)
+    }
+
+    // Answers a peer's `missing_assets` with the ones stored here, merge the
+    // result like any other broadcast.
+    pub fn broadcast_assets(&self, hashes: Vec<String>) -> String {
+        let hashes = hashes.into_iter().collect();
+        let update: Update = (UWMap::new(), Vec::new(), self.assets.subset(&hashes));
+        serde_json::to_string(&update).unwrap()
+    }
+
     pub fn broadcast(&mut self) -> String {
-        let tup = self.broadcast_aux();
-        serde_json::to_string(&tup).unwrap()
+        let update = self.full_update();
+        serde_json::to_string(&update).unwrap()
     }
 
-    pub fn merge(&mut self, data: String) {
-        type Tup = (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>);
-        let tup = serde_json::from_str::<Tup>(&data).ok();
-        let Some((other_node_map, move_logs)) = tup else { return; };
-        self.merge_aux(other_node_map, move_logs);
+    pub fn broadcast_delta(&mut self) -> String {
+        let update = self.delta_update();
+        serde_json::to_string(&update).unwrap()
+    }
+
+    pub fn broadcast_since(&self, version: String) -> Result<String, DocError> {
+        let version = serde_json::from_str::<HashMap<NodeID, VTime>>(&version)
+            .map_err(|err| DocError::DecodeError(format!("unreadable version: {}", err)))?;
+        let update = self.update_since(&version);
+        Ok(serde_json::to_string(&update).unwrap())
+    }
+
+    pub fn version(&self) -> String {
+        serde_json::to_string(&self.node_map.version()).unwrap()
+    }
+
+    pub fn ack(&mut self) -> String {
+        let ack = Ack {
+            replica_id: self.replica_id.clone(),
+            version: self.node_map.version(),
+            time: self.clock.now()
+        };
+        serde_json::to_string(&ack).unwrap()
+    }
+
+    pub fn receive_ack(&mut self, data: String) -> Result<(), DocError> {
+        let ack = serde_json::from_str::<Ack>(&data)?;
+        match self.acks.get(&ack.replica_id) {
+            Some(known) if known.time >= ack.time => {},
+            _ => { self.acks.insert(ack.replica_id.clone(), ack); },
+        }
+        Ok(())
     }
 
-    pub fn save(&self) -> String {
+    fn compact_aux(&mut self, acks: Vec<Ack>) {
+        let version = self.node_map.version();
+        // Per object, the version every replica has seen.
+        let stable = acks.iter().fold(version, |stable, ack| {
+            stable.into_iter()
+                .filter_map(|(key, vtime)| {
+                    let acked = ack.version.get(&key)?;
+                    Some((key, VTime::meet(&vtime, acked)))
+                })
+                .collect::<HashMap<_, _>>()
+        });
+        // A move can be dropped once no replica can send a move ordered
+        // before it, which needs every peer's clock to be past it and all
+        // of their earlier moves to have arrived here.
+        let caught_up = acks.iter().all(|ack| self.node_map.has_seen(&ack.version));
+        if caught_up {
+            let horizon = acks.iter()
+                .map(|ack| ack.time)
+                .min()
+                .unwrap_or(self.clock.timestamp());
+            let stable_moves = self.move_history.iter()
+                .take_while(|log| {
+                    let received = match stable.get(&log.object_id) {
+                        Some(vtime) => matches!(log.vtime.partial_cmp(vtime), Some(Ordering::Less) | Some(Ordering::Equal)),
+                        None => false,
+                    };
+                    log.timestamp <= horizon && received
+                })
+                .count();
+            if let Some(last) = self.move_history.drain(..stable_moves).next_back() {
+                self.compacted_until = Some(Stamp::new(last.timestamp, last.replica_id));
+            }
+        }
+        self.node_map.compact(&stable);
+        // Writes inside an object bump the object's vtime too, so replicas
+        // that have seen the object as it is here have seen the tombstones
+        // inside it.
+        let seen_by_all = self.node_map.version()
+            .into_iter()
+            .filter(|(key, vtime)| match stable.get(key) {
+                Some(stable) => matches!(vtime.partial_cmp(stable), Some(Ordering::Less) | Some(Ordering::Equal)),
+                None => false,
+            })
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        for key in seen_by_all {
+            let Some(mut item) = self.node_map.get(&key).cloned() else { continue; };
+            item.object.val.compact();
+            self.node_map.insert_novtime_update(key, item);
+        }
+        // Once no image, live or removed, refers to an asset it is gone
+        // for good.
+        let referenced = self.referenced_assets();
+        self.assets.retain(&referenced);
+    }
+
+    // Drops tombstones and move history that every replica has
+    // acknowledged. Every replica that wrote to the document must have
+    // sent an ack, or one of them could still send ops the dropped
+    // history is needed for.
+    pub fn compact(&mut self) -> Result<(), DocError> {
+        let mut replica_ids = self.node_map.version()
+            .values()
+            .flat_map(|vtime| vtime.replicas().cloned().collect::<Vec<_>>())
+            .chain(self.move_history.iter().map(|log| log.replica_id.clone()))
+            .chain(self.acks.keys().cloned())
+            .filter(|replica_id| *replica_id != self.replica_id)
+            .collect::<Vec<_>>();
+        replica_ids.sort();
+        replica_ids.dedup();
+        if replica_ids.is_empty() {
+            return Err(DocError::NoAcks);
+        }
+        let mut acks = Vec::new();
+        for replica_id in replica_ids {
+            let Some(ack) = self.acks.get(&replica_id) else { return Err(DocError::NotFound(replica_id)); };
+            acks.push(ack.clone());
+        }
+        self.compact_aux(acks);
+        Ok(())
+    }
+
+    pub fn merge(&mut self, data: String) -> Result<(), DocError> {
         type Tup = (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>);
-        let node_map = self.node_map.clone();
-        let move_history = self.move_history.clone();
-        let tup: Tup = (node_map, move_history);
-        serde_json::to_string(&tup).unwrap()
+        // Broadcasts from before the asset store have no assets. Saves
+        // carry the same state as a broadcast and can be merged too.
+        let update = match serde_json::from_str::<Update>(&data) {
+            Ok(update) => update,
+            Err(err) => match serde_json::from_str::<Tup>(&data) {
+                Ok((node_map, move_logs)) => (node_map, move_logs, AssetStore::new()),
+                Err(_) => {
+                    let saved = format::decode(&data).map_err(|save_err| unreadable_update(err, save_err))?;
+                    (saved.node_map, saved.move_history, saved.assets)
+                }
+            }
+        };
+        self.merge_update(update);
+        Ok(())
+    }
+
+    pub(crate) fn to_saved(&self) -> SavedDoc {
+        SavedDoc {
+            node_map: self.node_map.clone(),
+            move_history: self.move_history.clone(),
+            compacted_until: self.compacted_until.clone(),
+            assets: self.assets.subset(&self.referenced_assets())
+        }
     }
 
-    pub fn load(&mut self, data: String) {
+    fn restore(&mut self, SavedDoc { node_map, move_history, compacted_until, assets }: SavedDoc) {
         self.clear();
+        self.merge_update((node_map, move_history, assets));
+        self.compacted_until = compacted_until;
+    }
+
+    pub fn save(&self) -> Result<String, DocError> {
+        format::encode(&self.to_saved())
+    }
+
+    // Accepts saves from every earlier format version, see `format`.
+    pub fn load(&mut self, data: String) -> Result<(), DocError> {
+        // Decode before clearing so a bad save leaves the document as it was.
+        let saved = format::decode(&data)?;
+        self.restore(saved);
+        Ok(())
+    }
+
+    // Binary counterparts of the JSON methods above, see `codec` for the
+    // encoding. The JSON ones are kept for debugging.
+    pub fn broadcast_binary(&mut self) -> Result<Vec<u8>, DocError> {
+        let update = self.full_update();
+        Ok(codec::to_bytes(&update)?)
+    }
+
+    pub fn broadcast_delta_binary(&mut self) -> Result<Vec<u8>, DocError> {
+        let update = self.delta_update();
+        Ok(codec::to_bytes(&update)?)
+    }
+
+    pub fn merge_binary(&mut self, data: &[u8]) -> Result<(), DocError> {
         type Tup = (UWMap<String, LWWNodeMapItem>, Vec<MoveLog>);
-        let tup = serde_json::from_str::<Tup>(&data).ok();
-        let Some((other_node_map, move_logs)) = tup else { return; };
-        self.merge_aux(other_node_map, move_logs);
+        let update = match codec::from_bytes::<Update>(data) {
+            Ok(update) => update,
+            Err(err) => match codec::from_bytes::<Tup>(data) {
+                Ok((node_map, move_logs)) => (node_map, move_logs, AssetStore::new()),
+                Err(_) => {
+                    let saved = format::decode_binary(data).map_err(|save_err| unreadable_update(err, save_err))?;
+                    (saved.node_map, saved.move_history, saved.assets)
+                }
+            }
+        };
+        self.merge_update(update);
+        Ok(())
+    }
+
+    pub fn save_binary(&self) -> Result<Vec<u8>, DocError> {
+        format::encode_binary(&self.to_saved())
+    }
+
+    pub fn load_binary(&mut self, data: &[u8]) -> Result<(), DocError> {
+        let saved = format::decode_binary(data)?;
+        self.restore(saved);
+        Ok(())
     }
 
     fn dfs(&self, object_id: &NodeID, visited: &mut HashMap<NodeID, bool>, res: &mut Vec<NodeID>) {
@@ -778,14 +2140,18 @@ impl SVGDocCrdt2 {
 
     pub fn tree(&self) -> SVGDocTree {
         let mut res = SVGDocTree::new();
-        let mut node_map = self.node_map.value();
+        let mut node_map = self.node_map.value()
+            .into_iter()
+            .map(|(k, v)| (k, v.value()))
+            .collect::<HashMap<_, _>>();
         let mut nodes = self.top_sort_nodes();
         nodes.reverse();
+        let mut symbols = Vec::new();
         console_log!("[BRANCHES] Rendering tree");
         // Apply index according to timestamp
         for node in nodes.iter() {
             let Some(NodeMapItem { parent_id: Some(group_id), index:  idx, .. }) = self.node_map.get(node).map(|v| v.value()) else { continue; };
-            match node_map.remove(&group_id).map(|v| v.value()) {
+            match node_map.remove(&group_id) {
                 Some(NodeMapItem { object: SVGObject::Group(g), parent_id: g_parent_id, index: g_index }) => {
                     let mut group = g;
                     let mut i = group.children.len();
@@ -795,16 +2161,16 @@ impl SVGDocCrdt2 {
                         let NodeMapItem { index: nxt_idx, .. } = self.node_map.get(&next_id.to_string())
                             .map(|v| v.value())
                             .expect("node is missing in parent");
-                        if nxt_idx < idx { break; }
+                        if (&nxt_idx, next_id) < (&idx, node.as_str()) { break; }
                         i -= 1;
                     }
-                    let Some(NodeMapItem { object, .. }) = node_map.remove(node).map(|v| v.value()) else { 
+                    let Some(NodeMapItem { object, .. }) = node_map.remove(node) else { 
                         let item = NodeMapItem {
                             object: SVGObject::Group(group),
                             parent_id: g_parent_id.clone(),
                             index: g_index.clone()
                         };
-                        node_map.insert(group_id.clone(), item.into());
+                        node_map.insert(group_id.clone(), item);
                         continue;
                     };
                     group.children.insert(i, object);
@@ -813,25 +2179,72 @@ impl SVGDocCrdt2 {
                         parent_id: g_parent_id.clone(),
                         index: g_index.clone()
                     };
-                    node_map.insert(group_id.clone(), item.into());
+                    node_map.insert(group_id.clone(), item);
                 },
                 Some(o) => {
-                    node_map.insert(group_id.clone(), o.into());
+                    node_map.insert(group_id.clone(), o);
                 },
                 _ => {
-                    node_map.remove(node);
+                    // Symbols are put together like groups and then set
+                    // aside.
+                    let removed = node_map.remove(node);
+                    if let Some(NodeMapItem { object: SVGObject::Group(symbol), .. }) = removed {
+                        if group_id == DEFS_ROOT_ID { symbols.push(symbol); }
+                    }
                 },
             }
         };
         console_log!("[BASE] Rendering tree");
-        let mut tmp = node_map.drain().map(|(_, o)| o.value()).collect::<Vec<_>>();
-        tmp.sort_by(|NodeMapItem { index: index_a, .. }, NodeMapItem { index: index_b, .. }| {
-            index_a.cmp(index_b)
+        let mut tmp = node_map.drain().map(|(_, o)| o).collect::<Vec<_>>();
+        tmp.sort_by(|
+            NodeMapItem { index: index_a, object: object_a, .. }, 
+            NodeMapItem { index: index_b, object: object_b, .. }
+        | {
+            (index_a, object_a.get_id()).cmp(&(index_b, object_b.get_id()))
         });
         res.children = tmp.drain(..).map(|NodeMapItem { object, .. }| object).collect();
+        res.gradients = self.gradients();
+        symbols.sort_by(|a, b| a.id.cmp(&b.id));
+        res.symbols = symbols;
         console_log!("[BASE] Finished");
         res
     }
+
+    // The tree without hidden objects and what they hold.
+    pub fn visible_tree(&self) -> SVGDocTree {
+        self.tree().without_hidden()
+    }
+
+    pub fn export_svg(&self) -> String {
+        self.export_tree(self.tree())
+    }
+
+    pub fn export_visible_svg(&self) -> String {
+        self.export_tree(self.visible_tree())
+    }
+
+    // Images from the asset store are written as data URLs so the markup
+    // stands on its own.
+    fn export_tree(&self, mut tree: SVGDocTree) -> String {
+        self.embed_assets(&mut tree.children);
+        for symbol in tree.symbols.iter_mut() {
+            self.embed_assets(&mut symbol.children);
+        }
+        tree.export_svg()
+    }
+
+    fn embed_assets(&self, objects: &mut [SVGObject]) {
+        for object in objects.iter_mut() {
+            match object {
+                SVGObject::Group(group) => self.embed_assets(&mut group.children),
+                SVGObject::Image(image) => {
+                    let Some(asset) = image.asset_hash().and_then(|hash| self.assets.get(hash)) else { continue; };
+                    image.href = asset.data_url();
+                },
+                _ => {}
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -852,11 +2265,36 @@ mod tests {
         assert_eq!(t1_str, t2_str);
     }
 
+    // Rewrites a save as one of an older version, whose objects did not
+    // have `fields` yet.
+    fn downgrade(save: String, version: u32, fields: &[&str]) -> String {
+        let header = serde_json::from_str::<HashMap<String, Box<serde_json::value::RawValue>>>(&save).unwrap();
+        let doc = format::edit_objects(header["doc"].get(), |_, object| {
+            for field in fields.iter() {
+                object.remove(*field);
+            }
+            Ok(())
+        }).unwrap();
+        format!("{{\"version\":{},\"doc\":{}}}", version, doc)
+    }
+
+    // Moves the fields nested under `key` in a save back onto the objects.
+    fn unnest(save: String, key: &str) -> String {
+        let header = serde_json::from_str::<HashMap<String, Box<serde_json::value::RawValue>>>(&save).unwrap();
+        let doc = format::edit_objects(header["doc"].get(), |_, object| {
+            let Some(nested) = object.remove(key) else { return Ok(()); };
+            let nested = serde_json::from_str::<HashMap<String, Box<serde_json::value::RawValue>>>(nested.get())?;
+            object.extend(nested);
+            Ok(())
+        }).unwrap();
+        format!("{{\"version\":{},\"doc\":{}}}", header["version"], doc)
+    }
+
     #[test]
     fn test_create_circle() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_circle(None, PartialSVGCircle::empty());
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 1);
         match tree.children.get(0) {
@@ -871,7 +2309,7 @@ mod tests {
     fn test_create_group() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_group(None, PartialSVGGroup::empty());
+        doc.add_group(None, PartialSVGGroup::empty()).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 1);
         match tree.children.get(0) {
@@ -886,7 +2324,7 @@ mod tests {
     fn test_create_circle_within_group() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_group(None, PartialSVGGroup::empty());
+        doc.add_group(None, PartialSVGGroup::empty()).unwrap();
         let tree = doc.tree();
         let group_id = match tree.children.get(0) {
             Some(SVGObject::Group(g)) => &g.id,
@@ -894,8 +2332,8 @@ mod tests {
                 panic!("Group should be at index 0");
             }
         };
-        doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty());
-        doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty());
+        doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
         let tree = doc.tree();
         let group = match tree.children.get(0) {
             Some(SVGObject::Group(g)) => g,
@@ -921,13 +2359,13 @@ mod tests {
     fn test_no_cycle() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_group(None, PartialSVGGroup::empty());
+        doc.add_group(None, PartialSVGGroup::empty()).unwrap();
         let tree = doc.tree();
         let group_id = match tree.children.get(0) {
             Some(SVGObject::Group(g)) => &g.id,
             _ => panic!("Group should exist at index 0")
         };
-        doc.add_group(Some(group_id.clone()), PartialSVGGroup::empty());
+        doc.add_group(Some(group_id.clone()), PartialSVGGroup::empty()).unwrap();
         let tree = doc.tree();
         let group = match tree.children.get(0) {
             Some(SVGObject::Group(g)) => g,
@@ -937,7 +2375,10 @@ mod tests {
             Some(SVGObject::Group(g)) => &g.id,
             _ => panic!("Group should exist within group at index 0")
         };
-        doc.move_object(Some(child_group_id.to_string()), group_id.clone(), Some(0));
+        assert_eq!(
+            doc.move_object(Some(child_group_id.to_string()), group_id.clone(), Some(0)),
+            Err(DocError::CycleRejected { object_id: group_id.clone(), group_id: child_group_id.clone() })
+        );
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 1);
         let group = match tree.children.get(0) {
@@ -955,8 +2396,8 @@ mod tests {
     fn test_move() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_group(None, PartialSVGGroup::empty());
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_group(None, PartialSVGGroup::empty()).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 2);
         let circle_id = match tree.children.get(0) {
@@ -967,7 +2408,7 @@ mod tests {
             Some(SVGObject::Group(group)) => &group.id,
             _ => panic!("Group should exist at index 1")
         };
-        doc.move_object(Some(group_id.clone()), circle_id.clone(), None);
+        doc.move_object(Some(group_id.clone()), circle_id.clone(), None).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 1);
         let group = match tree.children.get(0) {
@@ -985,8 +2426,8 @@ mod tests {
     fn test_move_order() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_circle(None, PartialSVGCircle::empty());
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 2);
         let first_id = match tree.children.get(0) {
@@ -998,7 +2439,7 @@ mod tests {
             _ => panic!("Circle should exist at index 1")
         };
         assert_ne!(first_id, second_id);
-        doc.move_object(None, first_id.clone(), Some(1));
+        doc.move_object(None, first_id.clone(), Some(1)).unwrap();
         let tree = doc.tree();
         assert_eq!(tree.children.len(), 2);
         let act_first_id = match tree.children.get(0) {
@@ -1017,11 +2458,11 @@ mod tests {
     fn test_move_order_multiple() {
         let r1 = "r1";
         let mut doc = SVGDocCrdt2::new(r1.to_string());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_circle(None, PartialSVGCircle::empty());
-        doc.add_circle(None, PartialSVGCircle::empty());
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let tree = doc.tree();
         let first_id = match tree.children.get(0) {
             Some(SVGObject::Circle(circle)) => &circle.id,
@@ -1043,10 +2484,10 @@ mod tests {
             Some(SVGObject::Circle(circle)) => &circle.id,
             _ => panic!("Circle should exist at index 4")
         };
-        doc.move_object(None, first_id.clone(), Some(1));
-        doc.move_object(None, third_id.clone(), Some(0));
-        doc.move_object(None, fifth_id.clone(), Some(1));
-        doc.move_object(None, fourth_id.clone(), Some(1));
+        doc.move_object(None, first_id.clone(), Some(1)).unwrap();
+        doc.move_object(None, third_id.clone(), Some(0)).unwrap();
+        doc.move_object(None, fifth_id.clone(), Some(1)).unwrap();
+        doc.move_object(None, fourth_id.clone(), Some(1)).unwrap();
         let tree = doc.tree();
         let act_first_id = match tree.children.get(0) {
             Some(SVGObject::Circle(circle)) => &circle.id,
@@ -1081,8 +2522,8 @@ mod tests {
         let r2 = "r2";
         let mut doc1 = SVGDocCrdt2::new(r1.to_string());
         let mut doc2 = SVGDocCrdt2::new(r2.to_string());
-        doc1.add_circle(None, PartialSVGCircle::empty());
-        doc2.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc2.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let (d1_node_map, d1_move_logs) = doc1.broadcast_aux();
         let (d2_node_map, d2_move_logs) = doc2.broadcast_aux();
         doc2.merge_aux(d1_node_map, d1_move_logs);
@@ -1100,9 +2541,9 @@ mod tests {
         let r2 = "r2";
         let mut doc1 = SVGDocCrdt2::new(r1.to_string());
         let mut doc2 = SVGDocCrdt2::new(r2.to_string());
-        doc1.add_circle(None, PartialSVGCircle::empty());
-        doc1.add_circle(None, PartialSVGCircle::empty());
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let first_id = match doc1.tree().children.get(0) {
             Some(SVGObject::Circle(circle)) => circle.id.clone(),
             _ => panic!("Circle should exist at index 0")
@@ -1118,8 +2559,8 @@ mod tests {
 
         let (r1_node_map, r1_move_history) = doc1.broadcast_aux();
         doc2.merge_aux(r1_node_map, r1_move_history);
-        doc2.move_object(None, first_id.clone(), Some(2));
-        doc1.move_object(None, second_id.clone(), Some(0));
+        doc2.move_object(None, first_id.clone(), Some(2)).unwrap();
+        doc1.move_object(None, second_id.clone(), Some(0)).unwrap();
 
         let (r1_node_map, r1_move_history) = doc1.broadcast_aux();
         let (r2_node_map, r2_move_history) = doc2.broadcast_aux();
@@ -1154,9 +2595,9 @@ mod tests {
         let r2 = "r2";
         let mut doc1 = SVGDocCrdt2::new(r1.to_string());
         let mut doc2 = SVGDocCrdt2::new(r2.to_string());
-        doc1.add_circle(None, PartialSVGCircle::empty());
-        doc1.add_circle(None, PartialSVGCircle::empty());
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let first_id = match doc1.tree().children.get(0) {
             Some(SVGObject::Circle(circle)) => circle.id.clone(),
             _ => panic!("Circle should exist at index 0")
@@ -1174,9 +2615,9 @@ mod tests {
         doc2.merge_aux(r1_node_map, r1_move_history);
         let mut edits = PartialSVGCircle::empty();
         edits.opacity = Some(0.5);
-        doc2.move_object(None, first_id.clone(), Some(2));
-        doc2.edit_circle(first_id.clone(), edits);
-        doc1.move_object(None, first_id.clone(), Some(1));
+        doc2.move_object(None, first_id.clone(), Some(2)).unwrap();
+        doc2.edit_circle(first_id.clone(), edits).unwrap();
+        doc1.move_object(None, first_id.clone(), Some(1)).unwrap();
 
         let (r1_node_map, r1_move_history) = doc1.broadcast_aux();
         let (r2_node_map, r2_move_history) = doc2.broadcast_aux();
@@ -1215,14 +2656,14 @@ mod tests {
     fn test_delete() {
         let r1 = "r1".to_string();
         let mut doc1 = SVGDocCrdt2::new(r1.clone());
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
         let tree = doc1.tree();
         assert_eq!(tree.children.len(), 1);
         let circle_id = match tree.children.get(0) {
             Some(SVGObject::Circle(circle)) => circle.id.clone(),
             _ => panic!("Circle should exist at index 0")
         };
-        doc1.remove_object(circle_id);
+        doc1.remove_object(circle_id, DeletePolicy::DELETE_SUBTREE).unwrap();
         let tree = doc1.tree();
         assert_eq!(tree.children.len(), 0);
     }
@@ -1235,7 +2676,7 @@ mod tests {
         let mut doc1 = SVGDocCrdt2::new(r1.clone());
         let mut doc2 = SVGDocCrdt2::new(r2.clone());
 
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1247,8 +2688,8 @@ mod tests {
 
         let mut edits = PartialSVGCircle::empty();
         edits.opacity = Some(0.5);
-        doc2.edit_circle(circle_id.clone(), edits);
-        doc1.remove_object(circle_id);
+        doc2.edit_circle(circle_id.clone(), edits).unwrap();
+        doc1.remove_object(circle_id, DeletePolicy::DELETE_SUBTREE).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1273,13 +2714,13 @@ mod tests {
         let r1 = "r1".to_string();
 
         let mut doc1 = SVGDocCrdt2::new(r1);
-        doc1.add_group(None, PartialSVGGroup::empty());
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
         let tree = doc1.tree();
         let group_id = match tree.children.get(0) {
             Some(SVGObject::Group(g)) => g.id.clone(),
             _ => panic!("Group should exist")
         };
-        doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty());
+        doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
 
         // ensure that circle is inside group.
         let tree = doc1.tree();
@@ -1291,11 +2732,175 @@ mod tests {
             Some(SVGObject::Circle(c)) => c.id.clone(),
             _ => panic!("Circle should exist")
         };
-        doc1.remove_object(group_id.clone());
+        doc1.remove_object(group_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
         let tree = doc1.tree();
         assert_eq!(tree.children.len(), 0);
     }
 
+    #[test]
+    fn test_broadcast_delta_only_sends_changed_keys() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let circle_id = match doc1.tree().children.get(0) {
+            Some(SVGObject::Circle(c)) => c.id.clone(),
+            _ => panic!("Circle should exist")
+        };
+        let mut edits = PartialSVGCircle::empty();
+        edits.radius = Some(42.0);
+        doc1.edit_circle(circle_id.clone(), edits).unwrap();
+
+        let (delta, move_logs) = doc1.broadcast_delta_aux();
+        assert_eq!(delta.keys(), HashSet::from([circle_id.clone()]));
+        doc2.merge_aux(delta, move_logs);
+
+        let t1 = serde_json::to_string(&doc1.tree()).unwrap();
+        let t2 = serde_json::to_string(&doc2.tree()).unwrap();
+        assert_eq!(t1, t2);
+
+        let (delta, _) = doc1.broadcast_delta_aux();
+        assert_eq!(delta.keys().len(), 0);
+    }
+
+    #[test]
+    fn test_broadcast_since_version() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        let group_id = tree.children.get(0).expect("Group should exist").get_id().to_string();
+        let circle_id = tree.children.get(1).expect("Circle should exist").get_id().to_string();
+        doc1.move_object(Some(group_id), circle_id.clone(), None).unwrap();
+        doc1.send_buffer.clear();
+
+        let version = doc2.node_map.version();
+        let (delta, move_logs) = doc1.broadcast_since_aux(&version);
+        assert_eq!(delta.keys(), HashSet::from([circle_id]));
+        assert_eq!(move_logs.len(), 2);
+        doc2.merge_aux(delta, move_logs);
+
+        let t1 = serde_json::to_string(&doc1.tree()).unwrap();
+        let t2 = serde_json::to_string(&doc2.tree()).unwrap();
+        assert_eq!(t1, t2);
+
+        // A garbled version is an error rather than a request for everything.
+        assert!(matches!(doc1.broadcast_since("{".to_string()), Err(DocError::DecodeError(_))));
+        assert!(doc1.broadcast_since(doc2.version()).is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_cyclic_moves_converge() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let x = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let y = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+
+        // Each move is fine on its own, together they would form a cycle.
+        doc1.move_object(Some(y.clone()), x.clone(), None).unwrap();
+        doc2.move_object(Some(x.clone()), y.clone(), None).unwrap();
+        let update1 = doc1.broadcast();
+        let update2 = doc2.broadcast();
+        doc1.merge(update2).unwrap();
+        doc2.merge(update1).unwrap();
+
+        let tree1 = serde_json::to_string(&doc1.tree()).unwrap();
+        assert_eq!(tree1, serde_json::to_string(&doc2.tree()).unwrap());
+        let children = doc1.tree().children;
+        assert_eq!(children.len(), 1);
+        let SVGObject::Group(outer) = &children[0] else { panic!("expected a group") };
+        assert!(matches!(outer.children.as_slice(), [SVGObject::Group(_)]));
+    }
+
+    #[test]
+    fn test_causally_later_edit_wins_over_skewed_clock() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        // Replica 1's clock runs an hour ahead.
+        let hour = 3_600_000_000_000;
+        doc1.clock.update(HybridLogicalClock::pack(epoch_now_nanos() + hour, 0));
+
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let circle_id = doc1.tree().children.get(0)
+            .expect("Circle should exist")
+            .get_id()
+            .to_string();
+        let mut edits = PartialSVGCircle::empty();
+        edits.radius = Some(1.0);
+        doc1.edit_circle(circle_id.clone(), edits).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let mut edits = PartialSVGCircle::empty();
+        edits.radius = Some(2.0);
+        doc2.edit_circle(circle_id.clone(), edits).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        assert_eq!(doc1.get_circle(circle_id.clone()).map(|c| c.radius), Some(2.0));
+        assert_eq!(doc2.get_circle(circle_id).map(|c| c.radius), Some(2.0));
+    }
+
+    #[test]
+    fn test_concurrent_moves_with_equal_timestamps_are_both_kept() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1.clone());
+        let mut doc2 = SVGDocCrdt2::new(r2.clone());
+
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        let group1_id = tree.children.get(0).expect("Group should exist").get_id().to_string();
+        let group2_id = tree.children.get(1).expect("Group should exist").get_id().to_string();
+        let circle_id = tree.children.get(2).expect("Circle should exist").get_id().to_string();
+
+        doc1.move_object(Some(group1_id), circle_id.clone(), None).unwrap();
+        doc2.move_object(Some(group2_id.clone()), circle_id.clone(), None).unwrap();
+
+        // Pretend both moves happened at the same instant.
+        let timestamp = doc1.clock.now().max(doc2.clock.now());
+        for log in doc1.send_buffer.iter_mut().chain(doc1.move_history.iter_mut().rev().take(1)) {
+            log.timestamp = timestamp;
+        }
+        for log in doc2.send_buffer.iter_mut().chain(doc2.move_history.iter_mut().rev().take(1)) {
+            log.timestamp = timestamp;
+        }
+        let history_len = doc1.move_history.len();
+
+        merge_docs(&mut doc1, &mut doc2);
+
+        assert_eq!(doc1.move_history.len(), history_len + 1);
+        assert_eq!(doc2.move_history.len(), history_len + 1);
+        // r2 orders after r1, so its move is the one applied last.
+        let tree = doc1.tree();
+        let group2 = tree.children.get(1).expect("Group should exist");
+        match group2 {
+            SVGObject::Group(g) => assert_eq!(g.children.get(0).map(|c| c.get_id()), Some(circle_id.as_str())),
+            _ => panic!("Group should exist")
+        };
+    }
+
     #[test]
     fn test_case_1_edit_different_attributes_circle() {
         let r1 = "r1".to_string();
@@ -1304,7 +2909,7 @@ mod tests {
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
         merge_docs(&mut doc1, &mut doc2);
 
         let t1 = doc1.tree();
@@ -1315,145 +2920,707 @@ mod tests {
 
         let mut edits1 = PartialSVGCircle::empty();
         edits1.opacity = Some(0.5);
-        doc1.edit_circle(circle_id.to_string(), edits1);
+        doc1.edit_circle(circle_id.to_string(), edits1).unwrap();
 
         let mut edits2 = PartialSVGCircle::empty();
-        edits2.radius = Some(100);
+        edits2.radius = Some(100.0);
+
+        doc2.edit_circle(circle_id.to_string(), edits2).unwrap();
+
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        match tree.children.get(0) {
+            Some(SVGObject::Circle(c)) => {
+                assert_eq!(c.opacity, 0.5);
+                assert_eq!(c.radius, 100.0);
+            },
+            _ => panic!("Circle should exist")
+        };
+    }
+
+    #[test]
+    fn test_case_1_edit_different_attributes_rect() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let t1 = doc1.tree();
+        let rect_id = match t1.children.get(0) {
+            Some(SVGObject::Rectangle(r)) => &r.id,
+            _ => panic!("Rectangle should exist")
+        };
+
+        let mut edits1 = PartialSVGRectangle::empty();
+        edits1.opacity = Some(0.5);
+        doc1.edit_rectangle(rect_id.to_string(), edits1).unwrap();
+
+        let mut edits2 = PartialSVGRectangle::empty();
+        edits2.width = Some(100.0);
+
+        doc2.edit_rectangle(rect_id.to_string(), edits2).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        match tree.children.get(0) {
+            Some(SVGObject::Rectangle(c)) => {
+                assert_eq!(c.opacity, 0.5);
+                assert_eq!(c.width, 100.0);
+            },
+            _ => panic!("Rectangle should exist")
+        };
+    }
+
+    #[test]
+    fn test_case_1_edit_different_attributes_path() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let t1 = doc1.tree();
+
+        let path_id = t1.children.get(0)
+            .map(|it| { let SVGObject::Path(o) = it else { return None; };
+                Some(&o.id)
+            })
+            .flatten()
+            .expect("Path should exist");
+        
+        let mut edits1 = PartialSVGPath::empty();
+        edits1.opacity = Some(0.5);
+        doc1.edit_path(path_id.to_string(), edits1).unwrap();
+
+        let mut edits2 = PartialSVGPath::empty();
+        edits2.stroke_width = Some(100.0);
+        doc2.edit_path(path_id.to_string(), edits2).unwrap();
+
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        let path = tree.children.get(0)
+            .map(|it| {
+                let SVGObject::Path(o) = it else { return None; };
+                Some(o)  
+            })
+            .flatten()
+            .expect("Path should exist");
+        assert_eq!(path.opacity, 0.5);
+        assert_eq!(path.stroke_width, 100.0);
+    }
+
+    #[test]
+    fn test_case_1_edit_different_attributes_group() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let t1 = doc1.tree();
+
+        let group_id = t1.children.get(0)
+            .map(|it| {
+                let SVGObject::Group(o) = it else { return None; };
+                Some(&o.id)
+            })
+            .flatten()
+            .expect("Group should exist");
+        
+        let mut edits1 = PartialSVGGroup::empty();
+        edits1.opacity = Some(JSNullable::Some { item: 0.5 });
+        doc1.edit_group(group_id.to_string(), edits1).unwrap();
+
+        let mut edits2 = PartialSVGGroup::empty();
+        edits2.stroke_width = Some(JSNullable::Some { item: 100.0 });
+        doc2.edit_group(group_id.to_string(), edits2).unwrap();
+
+        merge_docs(&mut doc1, &mut doc2);
+
+        let tree = doc1.tree();
+        let group = tree.children.get(0)
+            .map(|it| {
+                let SVGObject::Group(o) = it else { return None; };
+                Some(o)  
+            })
+            .flatten()
+            .expect("Group should exist");
+        assert_eq!(group.opacity, Some(0.5));
+        assert_eq!(group.stroke_width, Some(100.0));
+    }
+
+    #[test]
+    fn test_concurrent_add_points_to_path() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let path_id = doc1.tree().children.get(0)
+            .expect("Path should exist")
+            .get_id()
+            .to_string();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 10.0, y: 0.0 }).unwrap();
+        doc2.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 0.0, y: 10.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let path = doc1.get_path(path_id).expect("Path should exist");
+        assert_eq!(path.points.len(), 3);
+        match path.points.get(0) {
+            Some(SVGPathCommand::Start { .. }) => {},
+            _ => panic!("Start point should be first")
+        };
+    }
+
+    #[test]
+    fn test_concurrent_edit_different_path_points() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let path_id = doc1.tree().children.get(0)
+            .expect("Path should exist")
+            .get_id()
+            .to_string();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::BEZIER, Vec2 { x: 50.0, y: 50.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let path = doc1.get_path(path_id.clone()).expect("Path should exist");
+        let start_id = path.points[0].get_id().to_string();
+        let bezier_id = path.points[1].get_id().to_string();
+
+        doc1.edit_path_point_pos(path_id.clone(), start_id.clone(), Vec2 { x: 5.0, y: 5.0 }).unwrap();
+        doc2.edit_path_point_handle1(path_id.clone(), bezier_id.clone(), Vec2 { x: 1.0, y: 2.0 }).unwrap();
+        doc2.edit_path_point_pos(path_id.clone(), bezier_id.clone(), Vec2 { x: 60.0, y: 60.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let path = doc1.get_path(path_id).expect("Path should exist");
+        match &path.points[0] {
+            SVGPathCommand::Start { pos, .. } => assert_eq!((pos.x, pos.y), (5.0, 5.0)),
+            _ => panic!("Start point should be first")
+        };
+        match &path.points[1] {
+            SVGPathCommand::Bezier { pos, handle1, .. } => {
+                assert_eq!((pos.x, pos.y), (60.0, 60.0));
+                assert_eq!((handle1.x, handle1.y), (1.0, 2.0));
+            },
+            _ => panic!("Bezier point should be second")
+        };
+    }
+
+    #[test]
+    fn test_concurrent_edit_and_remove_path_point() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let path_id = doc1.tree().children.get(0)
+            .expect("Path should exist")
+            .get_id()
+            .to_string();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 10.0, y: 10.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let path = doc1.get_path(path_id.clone()).expect("Path should exist");
+        let start_id = path.points[0].get_id().to_string();
+        let line_id = path.points[1].get_id().to_string();
+
+        doc1.remove_path_point(path_id.clone(), line_id).unwrap();
+        doc2.edit_path_point_pos(path_id.clone(), start_id, Vec2 { x: 3.0, y: 3.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let path = doc1.get_path(path_id).expect("Path should exist");
+        assert_eq!(path.points.len(), 1);
+        match &path.points[0] {
+            SVGPathCommand::Start { pos, .. } => assert_eq!((pos.x, pos.y), (3.0, 3.0)),
+            _ => panic!("Start point should remain")
+        };
+    }
+
+    #[test]
+    fn test_insert_point_into_path() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1);
+        let mut doc2 = SVGDocCrdt2::new(r2);
+
+        doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let path_id = doc1.tree().children.get(0)
+            .expect("Path should exist")
+            .get_id()
+            .to_string();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 100.0, y: 0.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
 
-        doc2.edit_circle(circle_id.to_string(), edits2);
+        let path = doc1.get_path(path_id.clone()).expect("Path should exist");
+        let start_id = path.points[0].get_id().to_string();
+        let end_id = path.points[1].get_id().to_string();
 
+        doc1.insert_point_into_path(path_id.clone(), Some(start_id.clone()), SVGPathCommandType::LINE, Vec2 { x: 50.0, y: 0.0 }).unwrap();
+        doc2.insert_point_into_path(path_id.clone(), Some(start_id.clone()), SVGPathCommandType::LINE, Vec2 { x: 50.0, y: 50.0 }).unwrap();
+        doc2.insert_point_into_path(path_id.clone(), None, SVGPathCommandType::START, Vec2 { x: -10.0, y: 0.0 }).unwrap();
         merge_docs(&mut doc1, &mut doc2);
 
-        let tree = doc1.tree();
-        match tree.children.get(0) {
-            Some(SVGObject::Circle(c)) => {
-                assert_eq!(c.opacity, 0.5);
-                assert_eq!(c.radius, 100);
-            },
-            _ => panic!("Circle should exist")
+        let path = doc1.get_path(path_id).expect("Path should exist");
+        assert_eq!(path.points.len(), 5);
+        assert_eq!(path.points[1].get_id(), start_id);
+        assert_eq!(path.points[4].get_id(), end_id);
+        match &path.points[0] {
+            SVGPathCommand::Start { pos, .. } => assert_eq!((pos.x, pos.y), (-10.0, 0.0)),
+            _ => panic!("Inserted start point should be first")
         };
     }
 
     #[test]
-    fn test_case_1_edit_different_attributes_rect() {
+    fn test_add_returns_ids_and_accepts_caller_ids() {
         let r1 = "r1".to_string();
         let r2 = "r2".to_string();
 
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_rectangle(None, PartialSVGRectangle::empty());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty())
+            .expect("Group should be added");
+        let circle_id = doc1.add_circle_with_id(Some(group_id.clone()), "circle".to_string(), PartialSVGCircle::empty())
+            .expect("Circle should be added");
+        assert_eq!(circle_id, "circle");
+        // Ids already in use and missing parent groups are rejected.
+        assert_eq!(
+            doc1.add_rectangle_with_id(None, "circle".to_string(), PartialSVGRectangle::empty()),
+            Err(DocError::DuplicateId("circle".to_string()))
+        );
+        assert_eq!(
+            doc1.add_circle(Some("missing".to_string()), PartialSVGCircle::empty()),
+            Err(DocError::NotFound("missing".to_string()))
+        );
+
+        let path_id = doc1.add_path_with_id(None, "path".to_string(), PartialSVGPath::empty())
+            .expect("Path should be added");
+        let start_id = doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 })
+            .expect("Point should be added");
+        let end_id = doc1.add_point_to_path_with_id(path_id.clone(), "end".to_string(), SVGPathCommandType::LINE, Vec2 { x: 10.0, y: 0.0 })
+            .expect("Point should be added");
+        assert_eq!(end_id, "end");
+        assert_eq!(
+            doc1.add_point_to_path_with_id(path_id.clone(), "end".to_string(), SVGPathCommandType::LINE, Vec2 { x: 20.0, y: 0.0 }),
+            Err(DocError::DuplicateId("end".to_string()))
+        );
         merge_docs(&mut doc1, &mut doc2);
 
-        let t1 = doc1.tree();
-        let rect_id = match t1.children.get(0) {
-            Some(SVGObject::Rectangle(r)) => &r.id,
-            _ => panic!("Rectangle should exist")
-        };
+        let tree = doc2.tree();
+        assert_eq!(tree.children.len(), 2);
+        let group = tree.find_group(&group_id).expect("Group should exist");
+        assert_eq!(group.children[0].get_id(), circle_id);
+        let path = doc2.get_path(path_id).expect("Path should exist");
+        let point_ids = path.points.iter().map(|p| p.get_id()).collect::<Vec<_>>();
+        assert_eq!(point_ids, vec![start_id.as_str(), end_id.as_str()]);
+    }
 
-        let mut edits1 = PartialSVGRectangle::empty();
-        edits1.opacity = Some(0.5);
-        doc1.edit_rectangle(rect_id.to_string(), edits1);
+    #[test]
+    fn test_mutations_report_errors() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let rect_id = doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        let path_id = doc.add_path(None, PartialSVGPath::empty()).unwrap();
+        let point_id = doc.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+
+        assert_eq!(
+            doc.edit_circle(rect_id.clone(), PartialSVGCircle::empty()),
+            Err(DocError::WrongKind { id: rect_id.clone(), expected: "CIRCLE" })
+        );
+        assert_eq!(
+            doc.add_circle(Some(rect_id.clone()), PartialSVGCircle::empty()),
+            Err(DocError::WrongKind { id: rect_id.clone(), expected: "GROUP" })
+        );
+        assert_eq!(
+            doc.edit_path_point_handle1(path_id.clone(), point_id.clone(), Vec2 { x: 1.0, y: 1.0 }),
+            Err(DocError::WrongKind { id: point_id.clone(), expected: "BEZIER or BEZIER_QUAD point" })
+        );
+        assert_eq!(
+            doc.remove_path_point(path_id.clone(), "missing".to_string()),
+            Err(DocError::NotFound("missing".to_string()))
+        );
+        assert_eq!(doc.remove_object("missing".to_string(), DeletePolicy::DELETE_SUBTREE), Err(DocError::NotFound("missing".to_string())));
+        assert!(matches!(doc.merge("not json".to_string()), Err(DocError::DecodeError(_))));
+        // A failed load keeps the current document.
+        assert!(matches!(doc.load("not json".to_string()), Err(DocError::DecodeError(_))));
+        assert_eq!(doc.tree().children.len(), 2);
+    }
 
-        let mut edits2 = PartialSVGRectangle::empty();
-        edits2.width = Some(100);
+    #[test]
+    fn test_remove_group_deletes_subtree() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let inner_id = doc.add_group(Some(group_id.clone()), PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(Some(inner_id.clone()), PartialSVGCircle::empty()).unwrap();
+        doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+
+        doc.remove_group(group_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        let tree = doc.tree();
+        assert_eq!(tree.children.len(), 1);
+        assert!(matches!(tree.children[0], SVGObject::Rectangle(_)));
+        assert!(doc.get_group(inner_id).is_none());
+        assert!(doc.get_circle(circle_id).is_none());
+    }
 
-        doc2.edit_rectangle(rect_id.to_string(), edits2);
-        merge_docs(&mut doc1, &mut doc2);
+    #[test]
+    fn test_remove_group_reparents_children() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let first_id = doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let last_id = doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        let circle1_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let circle2_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+
+        doc.remove_group(group_id.clone(), DeletePolicy::REPARENT_CHILDREN).unwrap();
+        let tree = doc.tree();
+        let ids = tree.children.iter().map(|o| o.get_id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![first_id.as_str(), circle1_id.as_str(), circle2_id.as_str(), last_id.as_str()]);
+        assert!(doc.get_group(group_id).is_none());
+    }
 
-        let tree = doc1.tree();
-        match tree.children.get(0) {
-            Some(SVGObject::Rectangle(c)) => {
-                assert_eq!(c.opacity, 0.5);
-                assert_eq!(c.width, 100);
-            },
-            _ => panic!("Rectangle should exist")
-        };
+    #[test]
+    fn test_remove_object_follows_policy() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+
+        doc.remove_object(group_id.clone(), DeletePolicy::REPARENT_CHILDREN).unwrap();
+        let tree = doc.tree();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].get_id(), circle_id);
+        assert!(doc.get_group(group_id).is_none());
+
+        // Objects that are not groups have no children to keep.
+        doc.remove_object(circle_id.clone(), DeletePolicy::REPARENT_CHILDREN).unwrap();
+        assert!(doc.tree().children.is_empty());
+
+        // A locked child keeps every child where it is.
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle1_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let circle2_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        doc.set_locked(circle2_id.clone(), true).unwrap();
+        assert_eq!(doc.remove_object(group_id.clone(), DeletePolicy::REPARENT_CHILDREN), Err(DocError::Locked(circle2_id)));
+        let tree = doc.tree();
+        let [SVGObject::Group(group)] = tree.children.as_slice() else { panic!("expected only the group") };
+        assert_eq!(group.children[0].get_id(), circle1_id);
+        assert_eq!(group.children.len(), 2);
     }
 
     #[test]
-    fn test_case_1_edit_different_attributes_path() {
+    fn test_concurrent_move_into_removed_group() {
         let r1 = "r1".to_string();
         let r2 = "r2".to_string();
 
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_path(None, PartialSVGPath::empty());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let rect_id = doc1.add_rectangle(Some(group_id.clone()), PartialSVGRectangle::empty()).unwrap();
         merge_docs(&mut doc1, &mut doc2);
 
-        let t1 = doc1.tree();
+        // Whatever the policy, objects moved into the group concurrently
+        // are removed with it on every replica.
+        doc1.remove_group(group_id.clone(), DeletePolicy::REPARENT_CHILDREN).unwrap();
+        doc2.move_object(Some(group_id.clone()), circle_id.clone(), None).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
 
-        let path_id = t1.children.get(0)
-            .map(|it| { let SVGObject::Path(o) = it else { return None; };
-                Some(&o.id)
-            })
-            .flatten()
-            .expect("Path should exist");
-        
-        let mut edits1 = PartialSVGPath::empty();
-        edits1.opacity = Some(0.5);
-        doc1.edit_path(path_id.to_string(), edits1);
+        for doc in [&doc1, &doc2] {
+            let tree = doc.tree();
+            assert_eq!(tree.children.len(), 1);
+            assert_eq!(tree.children[0].get_id(), rect_id);
+            assert!(doc.get_circle(circle_id.clone()).is_none());
+        }
+        assert_eq!(
+            doc2.edit_circle(circle_id.clone(), PartialSVGCircle::empty()),
+            Err(DocError::NotFound(circle_id))
+        );
+    }
 
-        let mut edits2 = PartialSVGPath::empty();
-        edits2.stroke_width = Some(100);
-        doc2.edit_path(path_id.to_string(), edits2);
+    #[test]
+    fn test_compact_drops_stable_tombstones_and_moves() {
+        let r1 = "r1".to_string();
+        let r2 = "r2".to_string();
+
+        let mut doc1 = SVGDocCrdt2::new(r1.clone());
+        let mut doc2 = SVGDocCrdt2::new(r2.clone());
 
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.move_object(Some(group_id.clone()), circle_id.clone(), None).unwrap();
         merge_docs(&mut doc1, &mut doc2);
+        let removed_id = doc2.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        doc2.remove_object(removed_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        // The move that first placed the circle at the root.
+        let (_, old_moves) = doc2.broadcast_since_aux(&HashMap::new());
+        let old_moves = old_moves.into_iter()
+            .filter(|log| log.object_id == circle_id)
+            .take(1)
+            .collect();
+
+        // Every replica that wrote has to acknowledge, whatever the caller
+        // knows about.
+        assert_eq!(doc1.compact(), Err(DocError::NotFound(r2.clone())));
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        assert_eq!(doc3.compact(), Err(DocError::NoAcks));
+        doc3.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        assert_eq!(doc3.compact(), Err(DocError::NoAcks));
+        let ack1 = doc1.ack();
+        let ack2 = doc2.ack();
+        doc1.receive_ack(ack2).unwrap();
+        doc2.receive_ack(ack1).unwrap();
+        // Removed after the acknowledgements, so not yet stable.
+        let rect_id = doc1.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        doc1.remove_object(rect_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        for doc in [&mut doc1, &mut doc2] {
+            doc.compact().unwrap();
+            assert!(doc.node_map.vtime(&removed_id).is_none());
+            assert!(doc.node_map.vtime(&rect_id).is_some());
+            assert!(doc.move_history.iter().all(|log| log.object_id == rect_id));
+        }
 
+        // Moves a peer sends again are not replayed over newer ones.
+        doc1.merge_aux(UWMap::new(), old_moves);
         let tree = doc1.tree();
-        let path = tree.children.get(0)
-            .map(|it| {
-                let SVGObject::Path(o) = it else { return None; };
-                Some(o)  
-            })
-            .flatten()
-            .expect("Path should exist");
-        assert_eq!(path.opacity, 0.5);
-        assert_eq!(path.stroke_width, 100);
+        assert_eq!(tree.children.len(), 1);
+        let group = tree.find_group(&group_id).expect("Group should exist");
+        assert_eq!(group.children[0].get_id(), circle_id);
     }
 
     #[test]
-    fn test_case_1_edit_different_attributes_group() {
+    fn test_compact_drops_tombstones_inside_objects() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let path_id = doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let point_id = doc1.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        let polygon_id = doc1.add_polygon(None, PartialSVGPolygon::empty()).unwrap();
+        let vertex_id = doc1.add_vertex(polygon_id.clone(), Vec2 { x: 1.0, y: 1.0 }).unwrap();
+        let gradient_id = doc1.add_gradient(PartialSVGGradient::empty()).unwrap();
+        let stop_id = doc1.add_gradient_stop(gradient_id.clone(), 0.5, Color::black()).unwrap();
+        let text_id = doc1.add_text(None, PartialSVGText::empty()).unwrap();
+        doc1.insert_text(text_id.clone(), 0, "abc".to_string()).unwrap();
+        let late_path_id = doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        let late_point_id = doc1.add_point_to_path(late_path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        doc2.remove_path_point(path_id.clone(), point_id.clone()).unwrap();
+        doc2.remove_vertex(polygon_id.clone(), vertex_id.clone()).unwrap();
+        doc2.remove_gradient_stop(gradient_id.clone(), stop_id.clone()).unwrap();
+        doc2.delete_text(text_id.clone(), 1, 1).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        let ack1 = doc1.ack();
+        let ack2 = doc2.ack();
+        doc1.receive_ack(ack2).unwrap();
+        doc2.receive_ack(ack1).unwrap();
+        // Removed after the acknowledgements, so not yet stable.
+        doc1.remove_path_point(late_path_id.clone(), late_point_id.clone()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        for doc in [&mut doc1, &mut doc2] {
+            doc.compact().unwrap();
+            let object = |id: &NodeID| doc.node_map.get(id).unwrap().object.value().clone();
+            let LWWSVGObject::Path(path) = object(&path_id) else { panic!("expected a path") };
+            assert!(path.points.vtime(&point_id).is_none());
+            let LWWSVGObject::Polygon(polygon) = object(&polygon_id) else { panic!("expected a polygon") };
+            assert!(polygon.points.vertices.vtime(&vertex_id).is_none());
+            let LWWSVGObject::Gradient(gradient) = object(&gradient_id) else { panic!("expected a gradient") };
+            assert!(gradient.stops.stops.vtime(&stop_id).is_none());
+            let LWWSVGObject::Path(late_path) = object(&late_path_id) else { panic!("expected a path") };
+            assert!(late_path.points.vtime(&late_point_id).is_some());
+            // Deleted characters are kept, see `LWWSVGObject::compact`.
+            let LWWSVGObject::Text(text) = object(&text_id) else { panic!("expected a text") };
+            assert_eq!(text.content.value(), "ac");
+            assert_eq!(serde_json::to_string(&text.content).unwrap().matches("\"deleted\"").count(), 3);
+        }
+        merge_docs(&mut doc1, &mut doc2);
+    }
+
+    #[test]
+    fn test_undo_redo_local_edits() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let radius = doc.get_circle(circle_id.clone()).unwrap().radius;
+        doc.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(radius + 10.0), ..PartialSVGCircle::empty() }).unwrap();
+        doc.move_object(Some(group_id.clone()), circle_id.clone(), None).unwrap();
+        doc.remove_object(group_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        assert!(doc.tree().children.is_empty());
+
+        assert!(doc.undo());
+        let group = doc.tree().find_group(&group_id).cloned().expect("Group should be restored");
+        assert_eq!(group.children[0].get_id(), circle_id);
+        assert!(doc.undo());
+        assert_eq!(doc.tree().children.len(), 2);
+        assert!(doc.undo());
+        assert_eq!(doc.get_circle(circle_id.clone()).unwrap().radius, radius);
+        assert!(doc.undo());
+        assert!(doc.get_circle(circle_id.clone()).is_none());
+
+        assert!(doc.redo());
+        assert!(doc.redo());
+        assert_eq!(doc.get_circle(circle_id.clone()).unwrap().radius, radius + 10.0);
+        // A new edit drops what is left to redo.
+        doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        assert!(!doc.can_redo());
+        assert!(!doc.redo());
+    }
+
+    #[test]
+    fn test_undo_keeps_later_remote_edits() {
         let r1 = "r1".to_string();
         let r2 = "r2".to_string();
 
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_group(None, PartialSVGGroup::empty());
+        let circle_id = doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        let edits = PartialSVGCircle { 
+            radius: Some(10.0), 
+            fill: Some(Color(255, 0, 0, 1.0)), 
+            ..PartialSVGCircle::empty() 
+        };
+        doc1.edit_circle(circle_id.clone(), edits).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        doc2.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(20.0), ..PartialSVGCircle::empty() }).unwrap();
         merge_docs(&mut doc1, &mut doc2);
 
-        let t1 = doc1.tree();
+        assert!(doc1.undo());
+        merge_docs(&mut doc1, &mut doc2);
+        let circle = doc2.get_circle(circle_id).expect("Circle should exist");
+        assert_eq!(circle.radius, 20.0);
+        assert_eq!(circle.fill.0, SVGCircle::default().fill.0);
+    }
 
-        let group_id = t1.children.get(0)
-            .map(|it| {
-                let SVGObject::Group(o) = it else { return None; };
-                Some(&o.id)
-            })
-            .flatten()
-            .expect("Group should exist");
-        
-        let mut edits1 = PartialSVGGroup::empty();
-        edits1.opacity = Some(JSNullable::Some { item: 0.5 });
-        doc1.edit_group(group_id.to_string(), edits1);
+    #[test]
+    fn test_undo_repeated_edits() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let circle_id = doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let radius = doc.get_circle(circle_id.clone()).unwrap().radius;
+        for r in [10.0, 20.0, 30.0] {
+            doc.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(r), ..PartialSVGCircle::empty() }).unwrap();
+        }
 
-        let mut edits2 = PartialSVGGroup::empty();
-        edits2.stroke_width = Some(JSNullable::Some { item: 100 });
-        doc2.edit_group(group_id.to_string(), edits2);
+        for r in [20.0, 10.0, radius] {
+            assert!(doc.undo());
+            assert_eq!(doc.get_circle(circle_id.clone()).unwrap().radius, r);
+        }
+        for r in [10.0, 20.0, 30.0] {
+            assert!(doc.redo());
+            assert_eq!(doc.get_circle(circle_id.clone()).unwrap().radius, r);
+        }
+    }
 
-        merge_docs(&mut doc1, &mut doc2);
+    #[test]
+    fn test_undo_keeps_later_edits_of_the_same_replica() {
+        // Another session writing as the same replica, e.g. a second tab.
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r1".to_string());
+
+        let circle_id = doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        doc1.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(10.0), ..PartialSVGCircle::empty() }).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+        doc2.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(20.0), ..PartialSVGCircle::empty() }).unwrap();
+        doc1.merge(doc2.broadcast()).unwrap();
+
+        assert!(doc1.undo());
+        assert_eq!(doc1.get_circle(circle_id).unwrap().radius, 20.0);
+    }
 
-        let tree = doc1.tree();
-        let group = tree.children.get(0)
-            .map(|it| {
-                let SVGObject::Group(o) = it else { return None; };
-                Some(o)  
-            })
-            .flatten()
-            .expect("Group should exist");
-        assert_eq!(group.opacity, Some(0.5));
-        assert_eq!(group.stroke_width, Some(100));
+    #[test]
+    fn test_undo_history_is_limited() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        for _ in 0..MAX_STEPS + 10 {
+            doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        }
+        for _ in 0..MAX_STEPS {
+            assert!(doc.undo());
+        }
+        assert!(!doc.can_undo());
+        assert_eq!(doc.tree().children.len(), 10);
+    }
+
+    #[test]
+    fn test_failed_step_is_rolled_back() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let rect_id = doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        doc.broadcast();
+        let moves = doc.move_history.len();
+
+        // Writes made before the error are taken back.
+        let mut circle_id = String::new();
+        let res = doc.step(|doc| {
+            circle_id = doc.add_circle(None, PartialSVGCircle::empty())?;
+            doc.edit_rectangle(rect_id.clone(), PartialSVGRectangle { width: Some(5.0), ..PartialSVGRectangle::empty() })?;
+            doc.move_object(Some("missing".to_string()), circle_id.clone(), None)
+        });
+        assert_eq!(res, Err(DocError::NotFound("missing".to_string())));
+        assert!(doc.node_map.vtime(&circle_id).is_none());
+        assert_ne!(doc.get_rectangle(rect_id.clone()).unwrap().width, 5.0);
+        assert_eq!(doc.move_history.len(), moves);
+        assert!(doc.send_buffer.is_empty() && doc.dirty.is_empty());
+
+        // The next undo is that of the last edit that went through.
+        assert!(doc.undo());
+        assert!(doc.tree().children.is_empty());
+        assert!(!doc.can_undo());
+    }
+
+    #[test]
+    fn test_undo_grouped_step() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        doc.begin_step();
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let path_id = doc.add_path(Some(group_id.clone()), PartialSVGPath::empty()).unwrap();
+        doc.end_step();
+        let point_id = doc.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc.edit_path_point_pos(path_id.clone(), point_id, Vec2 { x: 5.0, y: 5.0 }).unwrap();
+
+        assert!(doc.undo());
+        match &doc.get_path(path_id.clone()).unwrap().points[0] {
+            SVGPathCommand::Start { pos, .. } => assert_eq!((pos.x, pos.y), (0.0, 0.0)),
+            _ => panic!("Start point should exist")
+        };
+        assert!(doc.undo());
+        assert!(doc.get_path(path_id.clone()).unwrap().points.is_empty());
+        assert!(doc.undo());
+        assert!(doc.tree().children.is_empty());
+        assert!(!doc.can_undo());
+
+        assert!(doc.redo());
+        let tree = doc.tree();
+        let group = tree.find_group(&group_id).expect("Group should be restored");
+        assert_eq!(group.children.len(), 2);
     }
 
     #[test]
@@ -1464,7 +3631,7 @@ mod tests {
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1482,10 +3649,10 @@ mod tests {
         // Client 1 is editing the circle
         let mut edits = PartialSVGCircle::empty();
         edits.opacity = Some(0.5);
-        doc1.edit_circle(circle_id.clone(), edits);
+        doc1.edit_circle(circle_id.clone(), edits).unwrap();
 
         // Client 2 is removing the circle
-        doc2.remove_object(circle_id.clone());
+        doc2.remove_object(circle_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1509,8 +3676,8 @@ mod tests {
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_group(None, PartialSVGGroup::empty());
-        doc1.add_circle(None, PartialSVGCircle::empty());
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1535,10 +3702,10 @@ mod tests {
             .expect("Circle should exist");
 
         // client 1 is moving the object into a group
-        doc1.move_object(Some(group_id.to_string()), circle_id.to_string(), None);
+        doc1.move_object(Some(group_id.to_string()), circle_id.to_string(), None).unwrap();
 
         // client 2 is deleting the circle
-        doc2.remove_object(circle_id.to_string());
+        doc2.remove_object(circle_id.to_string(), DeletePolicy::DELETE_SUBTREE).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1573,9 +3740,9 @@ mod tests {
         let mut doc1 = SVGDocCrdt2::new(r1);
         let mut doc2 = SVGDocCrdt2::new(r2);
 
-        doc1.add_group(None, PartialSVGGroup::empty());
-        doc1.add_group(None, PartialSVGGroup::empty());
-        doc1.add_rectangle(None, PartialSVGRectangle::empty());
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc1.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1607,8 +3774,8 @@ mod tests {
             .flatten()
             .expect("Rectangle should exist");
 
-        doc1.move_object(Some(group1_id.to_string()), rect_id.to_string(), None);
-        doc2.move_object(Some(group2_id.to_string()), rect_id.to_string(), None);
+        doc1.move_object(Some(group1_id.to_string()), rect_id.to_string(), None).unwrap();
+        doc2.move_object(Some(group2_id.to_string()), rect_id.to_string(), None).unwrap();
 
         merge_docs(&mut doc1, &mut doc2);
 
@@ -1647,4 +3814,1152 @@ mod tests {
         assert_eq!(tree.children.len(), 2);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_export_svg() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group_with_id(None, "g1".to_string(), PartialSVGGroup {
+            fill: Some(JSNullable::Some { item: Color(255, 0, 0, 0.5) }),
+            opacity: Some(JSNullable::Some { item: 0.8 }),
+            ..PartialSVGGroup::empty()
+        }).unwrap();
+        doc.add_circle_with_id(Some(group_id.clone()), "c1".to_string(), PartialSVGCircle::empty()).unwrap();
+        let path_id = doc.add_path_with_id(None, "p1".to_string(), PartialSVGPath::empty()).unwrap();
+        doc.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 1.0, y: 2.0 }).unwrap();
+        doc.add_point_to_path(path_id.clone(), SVGPathCommandType::BEZIER_QUAD, Vec2 { x: 5.0, y: 6.0 }).unwrap();
+        doc.add_point_to_path(path_id.clone(), SVGPathCommandType::CLOSE, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+
+        let svg = doc.export_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\">"));
+        assert!(svg.contains("<g id=\"g1\" fill=\"rgb(255, 0, 0)\" fill-opacity=\"0.5\" opacity=\"0.8\">"));
+        assert!(svg.contains("<circle id=\"c1\" cx=\"0\" cy=\"0\" r=\"10\" fill=\"rgb(255, 255, 255)\" stroke=\"rgb(0, 0, 0)\" stroke-width=\"2\" opacity=\"1\"/>"));
+        assert!(svg.contains("d=\"M 1 2 Q 5 26 5 6 Z\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_binary_save_and_merge() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let path_id = doc1.add_path(None, PartialSVGPath::empty()).unwrap();
+        doc1.add_point_to_path(path_id, SVGPathCommandType::START, Vec2 { x: 1.0, y: 2.0 }).unwrap();
+        doc1.remove_object(circle_id, DeletePolicy::DELETE_SUBTREE).unwrap();
+
+        doc2.merge_binary(&doc1.broadcast_binary().unwrap()).unwrap();
+        assert_eq!(doc1.export_svg(), doc2.export_svg());
+
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        doc3.load_binary(&doc1.save_binary().unwrap()).unwrap();
+        assert_eq!(doc1.export_svg(), doc3.export_svg());
+        assert!(doc1.save_binary().unwrap().len() < doc1.save().unwrap().len() / 2);
+
+        doc2.edit_group(group_id, PartialSVGGroup {
+            opacity: Some(JSNullable::Some { item: 0.5 }),
+            ..PartialSVGGroup::empty()
+        }).unwrap();
+        doc1.merge_binary(&doc2.broadcast_delta_binary().unwrap()).unwrap();
+        assert_eq!(doc1.export_svg(), doc2.export_svg());
+
+        // A bad save leaves the document as it was.
+        let before = doc3.export_svg();
+        assert!(doc3.load_binary(&[1, 2, 3]).is_err());
+        assert_eq!(before, doc3.export_svg());
+    }
+
+    #[test]
+    fn test_merge_rejects_unreadable_broadcasts() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let err = doc.merge("not a broadcast".to_string()).unwrap_err();
+        assert!(matches!(&err, DocError::DecodeError(msg) if msg.starts_with("not a broadcast or a save")));
+        let err = doc.merge_binary(&[1, 2, 3]).unwrap_err();
+        assert!(matches!(&err, DocError::DecodeError(msg) if msg.starts_with("not a broadcast or a save")));
+
+        // A save from a newer version still says so.
+        let newer = doc.save().unwrap()
+            .replacen(&format!("\"version\":{}", format::FORMAT_VERSION), "\"version\":99", 1);
+        assert!(matches!(doc.merge(newer), Err(DocError::VersionMismatch { found: 99, .. })));
+    }
+
+    // A path with four points as saved by the first version of this
+    // document, when a path kept all its points in one register.
+    const TUPLE_WITH_PATH: &str = r#"[{"removed":{},"updated":{"IsWqPPwwS46fuFp1":{"r1":6}},"kv":{"IsWqPPwwS46fuFp1":{"object":{"val":{"Path":{"id":{"val":"IsWqPPwwS46fuFp1","time":1792304795048750577},"fill":{"val":[255,255,255,1.0],"time":1792304795048750648},"stroke_width":{"val":2,"time":1792304795048750705},"stroke":{"val":[0,0,0,1.0],"time":1792304795048750758},"points":{"val":[{"type":"START","id":"QsBmyRe6Pl09HOPg","pos":{"x":10,"y":20}},{"type":"LINE","id":"vC2WjxcqAXInP3JO","pos":{"x":30,"y":40}},{"type":"BEZIER","id":"VwKTcpKFoMTHFSRt","handle1":{"x":70,"y":80},"handle2":{"x":70,"y":40},"pos":{"x":50,"y":60}},{"type":"CLOSE","id":"0hECZfshIwdftHYv"}],"time":1792304795048750812},"opacity":{"val":1.0,"time":1792304795048750865}}},"time":1792304795048750948},"parent_id":{"val":null,"time":1792304795048751022},"index":{"val":[128],"time":1792304795048751074}}}},[{"old_group_id":"NEW_NODES_ROOT_ID","new_group_id":null,"object_id":"IsWqPPwwS46fuFp1","index":[128],"timestamp":1792304795048631840}]]"#;
+
+    #[test]
+    fn test_load_migrates_path_points_of_tuple_saves() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        doc.load(TUPLE_WITH_PATH.to_string()).unwrap();
+        let path_id = "IsWqPPwwS46fuFp1".to_string();
+        let points = doc.get_path(path_id.clone()).unwrap().points;
+        let ids = points.iter().map(|point| point.get_id()).collect::<Vec<_>>();
+        assert_eq!(ids, ["QsBmyRe6Pl09HOPg", "vC2WjxcqAXInP3JO", "VwKTcpKFoMTHFSRt", "0hECZfshIwdftHYv"]);
+        let SVGPathCommand::Bezier { handle1, pos, .. } = &points[2] else { panic!("expected a bezier") };
+        assert_eq!((handle1.x, handle1.y, pos.x, pos.y), (70.0, 80.0, 50.0, 60.0));
+
+        // Broadcasts had the same shape, and both replicas end up with the
+        // same points.
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.merge(TUPLE_WITH_PATH.to_string()).unwrap();
+        merge_docs(&mut doc, &mut doc2);
+        doc.add_point_to_path(path_id.clone(), SVGPathCommandType::LINE, Vec2 { x: 1.0, y: 2.0 }).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_path(path_id).unwrap().points.len(), 5);
+    }
+
+    #[test]
+    fn test_load_migrates_older_saves() {
+        let mut legacy = SVGDocCrdt::new();
+        legacy.add_group(None, PartialSVGGroup::empty());
+        let group_id = legacy.children().children[0].get_id().to_string();
+        legacy.add_circle(Some(group_id), PartialSVGCircle::empty());
+        legacy.add_path(None, PartialSVGPath::empty());
+        let path_id = legacy.children().children[1].get_id().to_string();
+        legacy.add_point_to_path(path_id.clone(), SVGPathCommandType::START, Vec2 { x: 1.0, y: 2.0 });
+        legacy.add_point_to_path(path_id, SVGPathCommandType::LINE, Vec2 { x: 3.0, y: 4.0 });
+
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        doc.load(legacy.save_oplog().unwrap()).unwrap();
+        assert_eq!(doc.export_svg(), legacy.children().export_svg());
+
+        // Headerless tuple from before the format was versioned.
+        let tuple = serde_json::to_string(&(&doc.node_map, &doc.move_history)).unwrap();
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(tuple).unwrap();
+        assert_eq!(doc2.export_svg(), doc.export_svg());
+
+        doc.compacted_until = Some(Stamp::new(42, "r1".to_string()));
+        doc2.load(doc.save().unwrap()).unwrap();
+        assert_eq!(doc2.compacted_until, doc.compacted_until);
+        doc2.load_binary(&doc.save_binary().unwrap()).unwrap();
+        assert_eq!(doc2.compacted_until, doc.compacted_until);
+
+        // Version 2, before the asset store and transforms.
+        let v2 = format!(
+            "{{\"version\":2,\"doc\":{{\"node_map\":{},\"move_history\":{},\"compacted_until\":null}}}}",
+            serde_json::to_string(&doc.node_map).unwrap(),
+            serde_json::to_string(&doc.move_history).unwrap()
+        );
+        doc2.load(v2).unwrap();
+        assert_eq!(doc2.export_svg(), doc.export_svg());
+        // Version 3 wrote geometry as integers.
+        let v3 = doc.save().unwrap()
+            .replacen(&format!("\"version\":{}", format::FORMAT_VERSION), "\"version\":3", 1)
+            .replace(".0,", ",")
+            .replace(".0}", "}");
+        assert!(v3.contains("\"val\":10,"));
+        doc2.load(v3).unwrap();
+        assert_eq!(doc2.export_svg(), doc.export_svg());
+
+        // Sub-pixel geometry survives both encodings.
+        let circle_id = doc.add_circle(None, PartialSVGCircle {
+            pos: Some(Vec2 { x: 0.25, y: -1.5 }),
+            radius: Some(0.125),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc2.load(doc.save().unwrap()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().radius, 0.125);
+        doc2.load_binary(&doc.save_binary().unwrap()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().pos.y, -1.5);
+        assert!(doc.export_svg().contains("cx=\"0.25\" cy=\"-1.5\" r=\"0.125\""));
+
+        // Binary saves of another codec version are rejected rather than
+        // misread.
+        let mut other_binary = doc.save_binary().unwrap();
+        other_binary[4] = 2;
+        assert_eq!(
+            doc2.load_binary(&other_binary),
+            Err(DocError::VersionMismatch { expected: codec::BINARY_FORMAT_VERSION, found: 2 })
+        );
+
+        let newer = doc.save().unwrap().replacen(&format!("\"version\":{}", format::FORMAT_VERSION), "\"version\":99", 1);
+        assert_eq!(doc2.load(newer), Err(DocError::VersionMismatch { expected: format::FORMAT_VERSION, found: 99 }));
+        assert!(matches!(doc2.load("42".to_string()), Err(DocError::DecodeError(_))));
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_gradients() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let circle_id = doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let line_id = doc.add_line(None, PartialSVGLine::empty()).unwrap();
+        let v4 = downgrade(doc.save().unwrap(), 4, &["fill_gradient", "stroke_gradient"]);
+        assert!(!v4.contains("_gradient"));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v4).unwrap();
+        assert!(doc2.get_circle(circle_id.clone()).unwrap().fill_gradient.is_none());
+        assert!(doc2.get_line(line_id).unwrap().stroke_gradient.is_none());
+
+        // Filling with a gradient afterwards wins over the migrated none.
+        let gradient_id = doc.add_gradient(PartialSVGGradient::empty()).unwrap();
+        doc.edit_circle(circle_id.clone(), PartialSVGCircle {
+            fill_gradient: Some(JSNullable::Some { item: gradient_id.clone() }),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id).unwrap().fill_gradient, Some(gradient_id));
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_stroke_style() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let style = ["stroke_style", "stroke_dasharray", "stroke_dashoffset", "stroke_linecap", "stroke_linejoin", "stroke_miterlimit"];
+        let v5 = downgrade(doc.save().unwrap(), 5, &style);
+        assert!(!v5.contains("stroke_miterlimit"));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v5).unwrap();
+        let circle = doc2.get_circle(circle_id).unwrap();
+        assert_eq!(circle.stroke_style, StrokeStyle::default());
+        let group = doc2.get_group(group_id).unwrap();
+        assert!(group.stroke_dasharray.is_none() && group.stroke_miterlimit.is_none());
+        assert_eq!(doc2.export_svg(), doc.export_svg());
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_fill_rule() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let path_id = doc.add_path(None, PartialSVGPath::empty()).unwrap();
+        let v6 = downgrade(doc.save().unwrap(), 6, &["fill_rule"]);
+        assert!(!v6.contains("fill_rule"));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v6).unwrap();
+        assert_eq!(doc2.get_path(path_id.clone()).unwrap().fill_rule, FillRule::Nonzero);
+
+        doc.edit_path(path_id.clone(), PartialSVGPath {
+            fill_rule: Some(FillRule::Evenodd),
+            ..PartialSVGPath::empty()
+        }).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_path(path_id).unwrap().fill_rule, FillRule::Evenodd);
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_clip_paths() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let circle_id = doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let source_id = doc.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        let v7 = downgrade(doc.save().unwrap(), 7, &["clip_path", "mask"]);
+        assert!(!v7.contains("clip_path") && !v7.contains("mask"));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v7).unwrap();
+        let circle = doc2.get_circle(circle_id.clone()).unwrap();
+        assert!(circle.clip_path.is_none() && circle.mask.is_none());
+
+        doc.set_clip_path(circle_id.clone(), Some(source_id.clone())).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id).unwrap().clip_path, Some(source_id));
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_layer_fields() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let circle_id = doc.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        let symbol_id = doc.add_symbol(PartialSVGGroup::empty()).unwrap();
+        let instance_id = doc.add_instance(None, PartialSVGInstance {
+            symbol_id: Some(symbol_id),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        let v8 = downgrade(doc.save().unwrap(), 8, &["layer"]);
+        assert!(!v8.contains("\"locked\""));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v8).unwrap();
+        let circle = doc2.get_circle(circle_id.clone()).unwrap();
+        assert_eq!(circle.layer, Layer::default());
+        assert!(!doc2.get_instance(instance_id).unwrap().layer.locked);
+        doc2.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(5.0), ..PartialSVGCircle::empty() }).unwrap();
+
+        doc.set_locked(circle_id.clone(), true).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert!(doc2.get_circle(circle_id).unwrap().layer.locked);
+    }
+
+    #[test]
+    fn test_load_nests_stroke_style() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let line_id = doc.add_line(None, PartialSVGLine {
+            stroke_style: PartialStrokeStyle {
+                stroke_dasharray: Some(vec![5.0, 3.0]),
+                stroke_miterlimit: Some(10.0),
+                ..PartialStrokeStyle::empty()
+            },
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        let v9 = downgrade(unnest(doc.save().unwrap(), "stroke_style"), 9, &[]);
+        assert!(v9.contains("\"stroke_miterlimit\"") && !v9.contains("\"stroke_style\""));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v9).unwrap();
+        assert_eq!(doc2.get_line(line_id.clone()).unwrap().stroke_style, doc.get_line(line_id.clone()).unwrap().stroke_style);
+
+        // The registers keep their stamps, so an older edit still loses.
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        doc3.merge(doc2.broadcast()).unwrap();
+        doc.edit_line(line_id.clone(), PartialSVGLine {
+            stroke_style: PartialStrokeStyle { stroke_linecap: Some(StrokeLinecap::Round), ..PartialStrokeStyle::empty() },
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        doc3.merge(doc.broadcast()).unwrap();
+        let style = doc3.get_line(line_id).unwrap().stroke_style;
+        assert_eq!((style.stroke_linecap, style.stroke_miterlimit), (StrokeLinecap::Round, 10.0));
+    }
+
+    #[test]
+    fn test_load_nests_layer_fields() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        doc.set_name(circle_id.clone(), "Sun".to_string()).unwrap();
+        doc.set_locked(group_id.clone(), true).unwrap();
+        let v10 = downgrade(unnest(doc.save().unwrap(), "layer"), 10, &[]);
+        assert!(v10.contains("\"locked\"") && !v10.contains("\"layer\""));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v10).unwrap();
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().layer.name, "Sun");
+        assert!(doc2.get_group(group_id.clone()).unwrap().layer.locked);
+
+        // The registers keep their stamps, so the later write still wins.
+        doc.set_hidden(circle_id.clone(), true).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id).unwrap().layer, Layer { name: "Sun".to_string(), hidden: true, locked: false });
+    }
+
+    #[test]
+    fn test_load_migrates_integer_font_sizes() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let text_id = doc.add_text(None, PartialSVGText { font_size: Some(24.0), ..PartialSVGText::empty() }).unwrap();
+        let v11 = downgrade(doc.save().unwrap(), 11, &[]).replace("\"font_size\":{\"val\":24.0,", "\"font_size\":{\"val\":24,");
+        assert!(v11.contains("\"font_size\":{\"val\":24,"));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v11).unwrap();
+        assert_eq!(doc2.get_text(text_id.clone()).unwrap().font_size, 24.0);
+        assert!(doc2.save().unwrap().contains("\"font_size\":{\"val\":24.0,"));
+
+        // Sizes between whole numbers survive a round trip.
+        doc2.edit_text(text_id.clone(), PartialSVGText { font_size: Some(10.5), ..PartialSVGText::empty() }).unwrap();
+        doc.merge(doc2.broadcast()).unwrap();
+        assert_eq!(doc.get_text(text_id).unwrap().font_size, 10.5);
+    }
+
+    #[test]
+    fn test_load_keeps_precision_of_f32_transforms() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let circle_id = doc.add_circle(None, PartialSVGCircle {
+            pos: Some(Vec2 { x: 10.0, y: 20.0 }),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc.scale_object(circle_id.clone(), 0.1, 2.0, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+
+        // Version 3 wrote transforms as f32.
+        let scale_x = serde_json::to_string(&0.1f32).unwrap();
+        let v3 = downgrade(doc.save().unwrap(), 3, &[])
+            .replace("\"val\":0.1,", &format!("\"val\":{},", scale_x));
+        assert!(v3.contains(&format!("\"val\":{},", scale_x)));
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v3).unwrap();
+        let transform = doc2.get_circle(circle_id).unwrap().transform;
+        assert_eq!(transform.scale_x, 0.1);
+        assert_ne!(transform.scale_x, 0.1f32 as f64);
+        assert_eq!(transform.scale_y, 2.0);
+    }
+
+    #[test]
+    fn test_load_binary_migrates_older_saves() {
+        #[derive(Serialize)]
+        struct Header<T> {
+            version: u32,
+            doc: T,
+        }
+        #[derive(Serialize)]
+        struct DocV2<'a> {
+            node_map: &'a UWMap<NodeID, LWWNodeMapItem>,
+            move_history: &'a Vec<MoveLog>,
+            compacted_until: Option<Stamp>,
+        }
+
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        doc.add_circle(Some(group_id), PartialSVGCircle { radius: Some(10.0), ..PartialSVGCircle::empty() }).unwrap();
+        let hash = doc.add_asset(vec![1, 2, 3], "image/png".to_string());
+        doc.add_image(None, PartialSVGImage {
+            href: Some(format!("asset:{}", hash)),
+            ..PartialSVGImage::empty()
+        }).unwrap();
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+
+        // Version 2, before the asset store.
+        let v2 = codec::to_bytes(&Header {
+            version: 2,
+            doc: DocV2 { node_map: &doc.node_map, move_history: &doc.move_history, compacted_until: None },
+        }).unwrap();
+        doc2.load_binary(&v2).unwrap();
+        assert_eq!(doc2.tree().children.len(), 2);
+        assert!(doc2.get_asset(&hash).is_none());
+
+        // Version 3, with assets, which JSON holds as base64.
+        let v3 = codec::to_bytes(&Header { version: 3, doc: doc.to_saved() }).unwrap();
+        doc2.load_binary(&v3).unwrap();
+        assert_eq!(doc2.export_svg(), doc.export_svg());
+        assert_eq!(doc2.get_asset(&hash).unwrap().data, vec![1, 2, 3]);
+
+        let newer = codec::to_bytes(&Header { version: 99, doc: doc.to_saved() }).unwrap();
+        assert_eq!(doc2.load_binary(&newer), Err(DocError::VersionMismatch { expected: format::FORMAT_VERSION, found: 99 }));
+    }
+
+    #[test]
+    fn test_load_migrates_saves_from_before_transforms() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let group_id = doc.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc.add_circle(Some(group_id), PartialSVGCircle::empty()).unwrap();
+        let v3 = downgrade(doc.save().unwrap(), 3, &["transform"]);
+        assert!(!v3.contains("\"transform\""));
+
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        doc2.load(v3).unwrap();
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().transform, Transform::identity());
+        assert!(doc2.save().unwrap().contains("\"transform\""));
+
+        // The identity is stamped before any real edit.
+        doc.rotate_object(circle_id.clone(), 90.0, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc2.merge(doc.broadcast()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id).unwrap().transform.rotate, 90.0);
+    }
+
+    #[test]
+    fn test_import_svg() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <g id="g1" fill="red" style="stroke: #0000ff; stroke-width: 3">
+                <circle id="c1" cx="10.4" cy="20" r="5" fill-opacity="0.5"/>
+                <rect x="1" y="2" width="30px" height="40" fill="none"/>
+            </g>
+            <path id="p1" d="m 10 10 h 5 v 5 s 10 10 20 0 a 5 5 0 0 1 10 0 z"/>
+            <ellipse id="e1" cx="0" cy="0" rx="1" ry="2"/>
+            <polygon id="pg1" points="0,0 10,0 10 10.6 5"/>
+            <text id="t1" x="5" y="6" font-size="12.5" font-weight="bold">Hi <tspan>there</tspan></text>
+            <foreignObject/>
+        </svg>"#;
+        let ids = doc1.import_svg(svg, None).unwrap();
+        assert_eq!(ids, vec!["g1".to_string(), "p1".to_string(), "e1".to_string(), "pg1".to_string(), "t1".to_string()]);
+
+        merge_docs(&mut doc1, &mut doc2);
+        let tree = doc2.tree();
+        let group = tree.find_group("g1").unwrap();
+        assert_eq!(group.children.len(), 2);
+        let circle = doc2.get_circle("c1".to_string()).unwrap();
+        assert_eq!((circle.pos.x, circle.pos.y, circle.radius), (10.4, 20.0, 5.0));
+        assert_eq!((circle.fill.0, circle.fill.1, circle.fill.2, circle.fill.3), (255, 0, 0, 1.0));
+        assert_eq!((circle.stroke.2, circle.stroke_width), (255, 3.0));
+        let SVGObject::Rectangle(rect) = &group.children[1] else { panic!("expected a rectangle"); };
+        assert_eq!((rect.width, rect.height, rect.fill.3), (30.0, 40.0, 0.0));
+
+        let path = doc2.get_path("p1".to_string()).unwrap();
+        let data = path.path_data();
+        assert!(data.starts_with("M 10 10 L 15 10 L 15 15 C 15 15 25 25 35 15 C"), "{}", data);
+        // The arc ends where it should, give or take the float error of
+        // converting it to curves.
+        let SVGPathCommand::Bezier { pos, .. } = &path.points[path.points.len() - 2] else { panic!("expected a curve"); };
+        assert!((pos.x - 45.0).abs() < 1e-9 && (pos.y - 15.0).abs() < 1e-9, "{}", data);
+        assert!(data.ends_with(" Z"), "{}", data);
+        let ellipse = doc2.get_ellipse("e1".to_string()).unwrap();
+        assert_eq!((ellipse.radius_x, ellipse.radius_y), (1.0, 2.0));
+        let polygon = doc2.get_polygon("pg1".to_string()).unwrap();
+        assert_eq!(SVGVertex::points_attr(&polygon.points), "0,0 10,0 10,10.6");
+        let text = doc2.get_text("t1".to_string()).unwrap();
+        assert_eq!((text.content.as_str(), text.font_size, text.font_weight), ("Hi there", 12.5, 700));
+
+        // Ids already in use are replaced, the import lands in the group.
+        let ids = doc1.import_svg(r#"<svg><circle id="c1" r="1"/></svg>"#, Some("g1".to_string())).unwrap();
+        assert_ne!(ids[0], "c1");
+        assert_eq!(doc1.tree().find_group("g1").unwrap().children.len(), 3);
+        assert!(matches!(doc1.import_svg("<svg>", None), Err(DocError::DecodeError(_))));
+        assert!(matches!(doc1.import_svg("<svg/>", Some("c1".to_string())), Err(DocError::WrongKind { .. })));
+
+        assert!(doc1.undo());
+        assert_eq!(doc1.tree().find_group("g1").unwrap().children.len(), 2);
+    }
+
+    #[test]
+    fn test_ellipse_and_line() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let ellipse_id = doc1.add_ellipse_with_id(None, "e1".to_string(), PartialSVGEllipse {
+            radius_x: Some(20.0),
+            ..PartialSVGEllipse::empty()
+        }).unwrap();
+        let line_id = doc1.add_line_with_id(None, "l1".to_string(), PartialSVGLine {
+            end: Some(Vec2 { x: 5.0, y: 6.0 }),
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        doc1.edit_ellipse(ellipse_id.clone(), PartialSVGEllipse {
+            radius_x: Some(30.0),
+            ..PartialSVGEllipse::empty()
+        }).unwrap();
+        doc2.edit_ellipse(ellipse_id.clone(), PartialSVGEllipse {
+            radius_y: Some(40.0),
+            ..PartialSVGEllipse::empty()
+        }).unwrap();
+        doc2.edit_line(line_id.clone(), PartialSVGLine {
+            start: Some(Vec2 { x: 1.0, y: 1.0 }),
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        for doc in [&doc1, &doc2] {
+            let ellipse = doc.get_ellipse(ellipse_id.clone()).unwrap();
+            assert_eq!((ellipse.radius_x, ellipse.radius_y), (30.0, 40.0));
+            let line = doc.get_line(line_id.clone()).unwrap();
+            assert_eq!((line.start.x, line.end.y), (1.0, 6.0));
+        }
+        assert!(doc1.get_line(ellipse_id.clone()).is_none());
+        assert!(matches!(
+            doc1.edit_line(ellipse_id, PartialSVGLine::empty()),
+            Err(DocError::WrongKind { .. })
+        ));
+
+        let svg = doc1.export_svg();
+        assert!(svg.contains("<ellipse id=\"e1\" cx=\"0\" cy=\"0\" rx=\"30\" ry=\"40\""), "{}", svg);
+        assert!(svg.contains("<line id=\"l1\" x1=\"1\" y1=\"1\" x2=\"5\" y2=\"6\""), "{}", svg);
+    }
+
+    #[test]
+    fn test_concurrent_vertex_edits() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let polygon_id = doc1.add_polygon(None, PartialSVGPolygon {
+            points: Some(vec![Vec2 { x: 0.0, y: 0.0 }, Vec2 { x: 10.0, y: 0.0 }, Vec2 { x: 10.0, y: 10.0 }]),
+            ..PartialSVGPolygon::empty()
+        }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        let vertices = doc1.get_polygon(polygon_id.clone()).unwrap().points;
+
+        doc1.edit_vertex_pos(polygon_id.clone(), vertices[0].id.clone(), Vec2 { x: -1.0, y: -1.0 }).unwrap();
+        doc1.insert_vertex_with_id(polygon_id.clone(), Some(vertices[0].id.clone()), "a".to_string(), Vec2 { x: 5.0, y: -5.0 }).unwrap();
+        doc2.edit_vertex_pos(polygon_id.clone(), vertices[2].id.clone(), Vec2 { x: 20.0, y: 20.0 }).unwrap();
+        doc2.remove_vertex(polygon_id.clone(), vertices[1].id.clone()).unwrap();
+        doc2.add_vertex_with_id(polygon_id.clone(), "b".to_string(), Vec2 { x: 0.0, y: 20.0 }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        for doc in [&doc1, &doc2] {
+            let polygon = doc.get_polygon(polygon_id.clone()).unwrap();
+            assert_eq!(SVGVertex::points_attr(&polygon.points), "-1,-1 5,-5 20,20 0,20");
+        }
+
+        // A single vertex edit is undone without touching the other replica's edits.
+        doc1.edit_vertex_pos(polygon_id.clone(), "a".to_string(), Vec2 { x: 7.0, y: 7.0 }).unwrap();
+        assert!(doc1.undo());
+        let polygon = doc1.get_polygon(polygon_id.clone()).unwrap();
+        assert_eq!(SVGVertex::points_attr(&polygon.points), "-1,-1 5,-5 20,20 0,20");
+
+        assert!(matches!(
+            doc1.edit_vertex_pos(polygon_id.clone(), "missing".to_string(), Vec2 { x: 0.0, y: 0.0 }),
+            Err(DocError::NotFound(_))
+        ));
+        let circle_id = doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+        assert!(matches!(
+            doc1.add_vertex(circle_id, Vec2 { x: 0.0, y: 0.0 }),
+            Err(DocError::WrongKind { .. })
+        ));
+        assert!(doc1.export_svg().contains("points=\"-1,-1 5,-5 20,20 0,20\""));
+    }
+
+    #[test]
+    fn test_concurrent_text_editing() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let text_id = doc1.add_text(None, PartialSVGText {
+            content: Some("Hello world".to_string()),
+            ..PartialSVGText::empty()
+        }).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        // Both type at the same place, each run stays in one piece.
+        doc1.insert_text(text_id.clone(), 5, ",".to_string()).unwrap();
+        doc1.insert_text(text_id.clone(), 6, " dear".to_string()).unwrap();
+        doc2.insert_text(text_id.clone(), 5, " there".to_string()).unwrap();
+        doc2.delete_text(text_id.clone(), 0, 1).unwrap();
+        doc2.insert_text(text_id.clone(), 0, "J".to_string()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+
+        let content = doc1.get_text(text_id.clone()).unwrap().content;
+        assert_eq!(content, doc2.get_text(text_id.clone()).unwrap().content);
+        assert!(content == "Jello there, dear world" || content == "Jello, dear there world", "{}", content);
+
+        // Undo only removes the local insertion.
+        let before = doc1.get_text(text_id.clone()).unwrap().content;
+        doc1.insert_text(text_id.clone(), 0, "Oh ".to_string()).unwrap();
+        doc2.insert_text(text_id.clone(), 0, "> ".to_string()).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        assert!(doc1.undo());
+        assert_eq!(doc1.get_text(text_id.clone()).unwrap().content, format!("> {}", before));
+
+        assert_eq!(
+            doc1.insert_text(text_id.clone(), 100, "x".to_string()),
+            Err(DocError::OutOfRange { index: 100, len: before.chars().count() + 2 })
+        );
+        assert!(doc1.delete_text(text_id.clone(), 1, 100).is_err());
+        // Lengths from JS can be anything, the range end must not overflow.
+        assert_eq!(
+            doc1.delete_text(text_id.clone(), 1, usize::MAX),
+            Err(DocError::OutOfRange { index: usize::MAX, len: before.chars().count() + 2 })
+        );
+        doc1.edit_text(text_id.clone(), PartialSVGText {
+            content: Some("a < b".to_string()),
+            align: Some(TextAlign::Middle),
+            ..PartialSVGText::empty()
+        }).unwrap();
+        let svg = doc1.export_svg();
+        assert!(svg.contains("text-anchor=\"middle\""), "{}", svg);
+        assert!(svg.contains(">a &lt; b</text>"), "{}", svg);
+    }
+
+    #[test]
+    fn test_image_assets() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        let bytes = vec![0x89, b'P', b'N', b'G', 1, 2, 3];
+        let hash = doc1.add_asset(bytes.clone(), "image/png".to_string());
+        assert_eq!(doc1.add_asset(bytes.clone(), "image/png".to_string()), hash);
+        let image_id = doc1.add_image(None, PartialSVGImage {
+            href: Some(format!("asset:{}", hash)),
+            ..PartialSVGImage::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        assert_eq!(doc2.get_asset(&hash).unwrap().data, bytes);
+
+        // The asset went out with the first delta, a peer that missed it
+        // asks for it.
+        doc1.edit_image(image_id.clone(), PartialSVGImage {
+            width: Some(50.0),
+            ..PartialSVGImage::empty()
+        }).unwrap();
+        doc3.merge(doc1.broadcast_delta()).unwrap();
+        assert_eq!(doc3.get_image(image_id.clone()).unwrap().width, 50.0);
+        assert_eq!(doc3.missing_assets(), vec![hash.clone()]);
+        doc3.merge(doc2.broadcast_assets(doc3.missing_assets())).unwrap();
+        assert!(doc3.missing_assets().is_empty());
+        assert!(doc3.export_svg().contains("href=\"data:image/png;base64,iVBORwECAw==\""));
+
+        // An asset no image uses yet is left out of a broadcast but still
+        // goes out once one does.
+        let later = doc1.add_asset(vec![4, 5], "image/png".to_string());
+        doc2.merge(doc1.broadcast()).unwrap();
+        assert!(doc2.get_asset(&later).is_none());
+        doc1.edit_image(image_id.clone(), PartialSVGImage {
+            href: Some(format!("asset:{}", later)),
+            ..PartialSVGImage::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        assert!(doc2.missing_assets().is_empty());
+        assert_eq!(doc2.get_asset(&later).unwrap().data, vec![4, 5]);
+        doc1.undo();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+
+        // Unreferenced assets are not saved, referenced ones survive a reload.
+        let unused = doc3.add_asset(vec![1], "image/png".to_string());
+        let mut doc4 = SVGDocCrdt2::new("r4".to_string());
+        doc4.load(doc3.save().unwrap()).unwrap();
+        assert!(doc4.get_asset(&hash).is_some());
+        assert!(doc4.get_asset(&unused).is_none());
+        doc4.load_binary(&doc3.save_binary().unwrap()).unwrap();
+        assert!(doc4.get_asset(&hash).is_some());
+
+        // Removed images keep their asset until compaction drops them.
+        doc4.remove_object(image_id, DeletePolicy::DELETE_SUBTREE).unwrap();
+        assert!(doc4.get_asset(&hash).is_some());
+        doc1.merge(doc4.broadcast()).unwrap();
+        doc4.receive_ack(doc1.ack()).unwrap();
+        doc4.compact().unwrap();
+        assert!(doc4.get_asset(&hash).is_none());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let close = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| (x1 - x2).abs() < 1e-3 && (y1 - y2).abs() < 1e-3;
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(Some(group_id.clone()), PartialSVGCircle {
+            pos: Some(Vec2 { x: 10.0, y: 10.0 }),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+
+        // Concurrent rotate and scale about the origin both survive.
+        doc1.rotate_object(circle_id.clone(), 90.0, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc2.scale_object(circle_id.clone(), 2.0, 3.0, Vec2 { x: 0.0, y: 0.0 }).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        let transform = doc1.get_circle(circle_id.clone()).unwrap().transform;
+        assert_eq!(transform, doc2.get_circle(circle_id.clone()).unwrap().transform);
+        assert_eq!((transform.rotate, transform.scale_x, transform.scale_y), (90.0, 2.0, 3.0));
+        assert!(doc1.undo());
+        assert_eq!(doc1.get_circle(circle_id.clone()).unwrap().transform.rotate, 0.0);
+        assert_eq!(doc1.get_circle(circle_id.clone()).unwrap().transform.scale_x, 2.0);
+
+        // The pivot stays where it was, and the group's transform composes
+        // with the circle's.
+        doc1.rotate_object(circle_id.clone(), 45.0, Vec2 { x: 10.0, y: 10.0 }).unwrap();
+        let before = doc1.world_transform(circle_id.clone()).unwrap();
+        doc1.rotate_object(circle_id.clone(), 30.0, Vec2 { x: 10.0, y: 10.0 }).unwrap();
+        let after = doc1.world_transform(circle_id.clone()).unwrap();
+        assert!(close(before.apply(10.0, 10.0), after.apply(10.0, 10.0)));
+        doc1.edit_group(group_id.clone(), PartialSVGGroup {
+            transform: Some(Transform { translate_x: 5.0, translate_y: -5.0, ..Transform::identity() }),
+            ..PartialSVGGroup::empty()
+        }).unwrap();
+        let (x, y) = after.apply(10.0, 10.0);
+        assert!(close(doc1.world_transform(circle_id.clone()).unwrap().apply(10.0, 10.0), (x + 5.0, y - 5.0)));
+        assert!(doc1.export_svg().contains(&format!("<g id=\"{}\" transform=\"translate(5 -5)\"", group_id)));
+        assert_eq!(doc1.world_transform("missing".to_string()), Err(DocError::NotFound("missing".to_string())));
+
+        // Imported transform lists collapse into the same placement.
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <rect id="r" width="4" height="4" transform="translate(10 20) rotate(30) scale(2)"/>
+        </svg>"#;
+        doc2.import_svg(svg, None).unwrap();
+        let expected = Matrix::translate(10.0, 20.0)
+            .multiply(&Matrix::rotate(30.0))
+            .multiply(&Matrix::scale(2.0, 2.0));
+        let imported = doc2.world_transform("r".to_string()).unwrap();
+        assert!(close(imported.apply(4.0, 4.0), expected.apply(4.0, 4.0)));
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        doc3.import_svg(&doc2.export_svg(), None).unwrap();
+        assert!(close(doc3.world_transform("r".to_string()).unwrap().apply(4.0, 4.0), expected.apply(4.0, 4.0)));
+    }
+
+    #[test]
+    fn test_gradients() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let gradient_id = doc1.add_gradient(PartialSVGGradient::empty()).unwrap();
+        let circle_id = doc1.add_circle(None, PartialSVGCircle {
+            fill_gradient: Some(JSNullable::Some { item: gradient_id.clone() }),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+        assert_eq!(doc2.tree().children.len(), 1);
+        assert_eq!(doc2.tree().gradients.len(), 1);
+
+        // Edits to different stops of the same gradient both survive.
+        let stops = doc1.get_gradient(gradient_id.clone()).unwrap().stops;
+        let (first, last) = (stops[0].id.clone(), stops[1].id.clone());
+        doc1.edit_gradient_stop(gradient_id.clone(), first.clone(), PartialSVGGradientStop {
+            color: Some(Color(255, 0, 0, 1.0)),
+            ..PartialSVGGradientStop::empty()
+        }).unwrap();
+        doc2.edit_gradient_stop(gradient_id.clone(), last.clone(), PartialSVGGradientStop {
+            offset: Some(0.8),
+            ..PartialSVGGradientStop::empty()
+        }).unwrap();
+        let middle = doc2.add_gradient_stop(gradient_id.clone(), 0.5, Color::white()).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        let stops = doc1.get_gradient(gradient_id.clone()).unwrap().stops;
+        assert_eq!(stops.iter().map(|stop| stop.id.clone()).collect::<Vec<_>>(), vec![first.clone(), middle, last]);
+        assert_eq!(stops[0].color.0, 255);
+        assert_eq!(stops[2].offset, 0.8);
+        assert_eq!(
+            serde_json::to_string(&doc1.tree()).unwrap(),
+            serde_json::to_string(&doc2.tree()).unwrap()
+        );
+
+        let svg = doc1.export_svg();
+        assert!(svg.contains(&format!("<defs>\n    <linearGradient id=\"{}\"", gradient_id)));
+        assert!(svg.contains(&format!("fill=\"url(#{}) rgb(", gradient_id)));
+
+        // Gradient edits undo like object edits, and gradients stay out of
+        // the tree.
+        doc1.edit_gradient(gradient_id.clone(), PartialSVGGradient {
+            kind: Some(GradientKind::Radial),
+            ..PartialSVGGradient::empty()
+        }).unwrap();
+        assert!(doc1.export_svg().contains("<radialGradient"));
+        assert!(doc1.undo());
+        assert_eq!(doc1.get_gradient(gradient_id.clone()).unwrap().kind, GradientKind::Linear);
+        assert!(matches!(
+            doc1.move_object(None, gradient_id.clone(), None),
+            Err(DocError::WrongKind { .. })
+        ));
+        assert!(matches!(
+            doc1.edit_gradient(circle_id.clone(), PartialSVGGradient::empty()),
+            Err(DocError::WrongKind { .. })
+        ));
+
+        // Without its gradient the circle falls back to its color.
+        doc1.remove_object(gradient_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        assert!(doc2.get_gradient(gradient_id.clone()).is_none());
+        assert!(!doc2.export_svg().contains("<defs>"));
+        assert_eq!(doc2.get_circle(circle_id).unwrap().fill_gradient, Some(gradient_id));
+    }
+
+    #[test]
+    fn test_stroke_styles() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let line_id = doc1.add_line(Some(group_id.clone()), PartialSVGLine::empty()).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+
+        // Each property is its own register, concurrent edits to different
+        // ones both survive.
+        doc1.edit_line(line_id.clone(), PartialSVGLine {
+            stroke_style: PartialStrokeStyle {
+                stroke_dasharray: Some(vec![5.0, 3.0]),
+                stroke_dashoffset: Some(2.0),
+                ..PartialStrokeStyle::empty()
+            },
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        doc2.edit_line(line_id.clone(), PartialSVGLine {
+            stroke_style: PartialStrokeStyle {
+                stroke_linecap: Some(StrokeLinecap::Round),
+                stroke_miterlimit: Some(10.0),
+                ..PartialStrokeStyle::empty()
+            },
+            ..PartialSVGLine::empty()
+        }).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        let style = doc2.get_line(line_id.clone()).unwrap().stroke_style;
+        assert_eq!(style.stroke_dasharray, vec![5.0, 3.0]);
+        assert_eq!(style.stroke_dashoffset, 2.0);
+        assert_eq!(style.stroke_linecap, StrokeLinecap::Round);
+        assert_eq!(style.stroke_linejoin, StrokeLinejoin::Miter);
+        assert_eq!(style.stroke_miterlimit, 10.0);
+        assert!(doc1.undo());
+        assert!(doc1.get_line(line_id.clone()).unwrap().stroke_style.stroke_dasharray.is_empty());
+        assert_eq!(doc1.get_line(line_id.clone()).unwrap().stroke_style.stroke_linecap, StrokeLinecap::Round);
+
+        doc1.edit_group(group_id.clone(), PartialSVGGroup {
+            stroke_linejoin: Some(JSNullable::Some { item: StrokeLinejoin::Bevel }),
+            stroke_dasharray: Some(JSNullable::Some { item: vec![1.0] }),
+            ..PartialSVGGroup::empty()
+        }).unwrap();
+        let svg = doc1.export_svg();
+        assert!(svg.contains(&format!("<g id=\"{}\" stroke-dasharray=\"1\" stroke-linejoin=\"bevel\"", group_id)));
+        assert!(svg.contains("stroke-linecap=\"round\" stroke-miterlimit=\"10\""));
+        assert!(!svg.contains("stroke-linejoin=\"miter\""));
+
+        // Imported shapes take the styling their groups pass down.
+        let mut doc3 = SVGDocCrdt2::new("r3".to_string());
+        doc3.import_svg(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <g stroke-dasharray="4 2" stroke-linecap="square">
+                <line id="l" x2="10" style="stroke-linecap: round"/>
+                <line id="m" x2="10" stroke-dasharray="none"/>
+            </g>
+        </svg>"#, None).unwrap();
+        let imported = doc3.get_line("l".to_string()).unwrap();
+        assert_eq!(imported.stroke_style.stroke_dasharray, vec![4.0, 2.0]);
+        assert_eq!(imported.stroke_style.stroke_linecap, StrokeLinecap::Round);
+        assert!(doc3.get_line("m".to_string()).unwrap().stroke_style.stroke_dasharray.is_empty());
+    }
+
+    #[test]
+    fn test_subpaths() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        // A square with a square hole and a separate triangle.
+        let path_id = doc1.import_svg(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <path fill-rule="evenodd" d="M 0 0 L 10 0 L 10 10 Z M 2 2 C 3 2 4 3 4 4 L 2 4 Z M 20 0 L 30 0"/>
+        </svg>"#, None).unwrap().remove(0);
+        doc2.merge(doc1.broadcast()).unwrap();
+        let subpaths = doc1.get_path_subpaths(path_id.clone()).unwrap();
+        assert_eq!(subpaths.iter().map(|subpath| subpath.points.len()).collect::<Vec<_>>(), vec![4, 4, 2]);
+        assert_eq!(subpaths.iter().map(|subpath| subpath.closed).collect::<Vec<_>>(), vec![true, true, false]);
+        let (outer, hole, open) = (subpaths[0].id.clone(), subpaths[1].id.clone(), subpaths[2].id.clone());
+        assert!(doc1.export_svg().contains("fill-rule=\"evenodd\""));
+
+        // Reversing swaps the curve's handles and keeps the CLOSE at the end.
+        doc1.reverse_subpath(path_id.clone(), hole.clone()).unwrap();
+        let path = doc1.get_path(path_id.clone()).unwrap();
+        assert_eq!(
+            path.subpaths()[1].points.iter().map(|point| point.to_path_data()).collect::<Vec<_>>(),
+            vec!["M 2 4", "L 4 4", "C 4 3 3 2 2 2", "Z"]
+        );
+        assert_eq!(path.subpaths()[1].id, hole);
+
+        // A reorder on one replica and a removal on the other both apply.
+        doc1.move_subpath(path_id.clone(), open.clone(), 0).unwrap();
+        doc2.remove_subpath(path_id.clone(), outer.clone()).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        let ids = |doc: &SVGDocCrdt2| doc.get_path_subpaths(path_id.clone()).unwrap()
+            .into_iter()
+            .map(|subpath| subpath.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids(&doc1), vec![open.clone(), hole.clone()]);
+        assert_eq!(ids(&doc2), ids(&doc1));
+
+        doc1.edit_path(path_id.clone(), PartialSVGPath {
+            fill_rule: Some(FillRule::Nonzero),
+            ..PartialSVGPath::empty()
+        }).unwrap();
+        assert!(!doc1.export_svg().contains("fill-rule"));
+        assert!(doc1.undo());
+        assert!(doc1.undo());
+        assert_eq!(ids(&doc1), vec![hole.clone(), open.clone()]);
+        assert_eq!(
+            doc1.move_subpath(path_id.clone(), "missing".to_string(), 0),
+            Err(DocError::NotFound("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_clip_paths_and_masks() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let group_id = doc1.add_group(None, PartialSVGGroup {
+            transform: Some(Transform { translate_x: 10.0, ..Transform::identity() }),
+            ..PartialSVGGroup::empty()
+        }).unwrap();
+        let circle_id = doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let clip_id = doc1.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        let mask_id = doc1.add_ellipse(None, PartialSVGEllipse::empty()).unwrap();
+        doc1.set_clip_path(group_id.clone(), Some(clip_id.clone())).unwrap();
+        doc1.set_mask(circle_id.clone(), Some(mask_id.clone())).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().mask, Some(mask_id.clone()));
+
+        // The sources are only drawn through the objects using them, placed
+        // in those objects' coordinates.
+        let svg = doc2.export_svg();
+        assert!(svg.contains(&format!(
+            "<clipPath id=\"{}-clip\" clipPathUnits=\"userSpaceOnUse\">\n      <use href=\"#{}\" transform=\"translate(-10 0)\"/>",
+            group_id, clip_id
+        )));
+        assert!(svg.contains(&format!("<mask id=\"{}-mask\">\n      <use href=\"#{}\" transform=\"translate(-10 0)\"/>", circle_id, mask_id)));
+        assert!(svg.contains(&format!("<g id=\"{}\" transform=\"translate(10 0)\" clip-path=\"url(#{}-clip)\"", group_id, group_id)));
+        assert!(svg.contains(&format!("<circle id=\"{}\" mask=\"url(#{}-mask)\"", circle_id, circle_id)));
+        assert_eq!(svg.matches(&format!("<rect id=\"{}\"", clip_id)).count(), 1);
+        let defs_end = svg.find("</defs>").unwrap();
+        assert!(svg.find(&format!("<rect id=\"{}\"", clip_id)).unwrap() < defs_end);
+
+        // Sources that would contain the object, or the object itself, are
+        // rejected, and so are gradients.
+        assert_eq!(
+            doc1.set_clip_path(circle_id.clone(), Some(group_id.clone())),
+            Err(DocError::ClipCycle { object_id: circle_id.clone(), source_id: group_id.clone() })
+        );
+        assert!(matches!(doc1.set_mask(clip_id.clone(), Some(clip_id.clone())), Err(DocError::ClipCycle { .. })));
+        let gradient_id = doc1.add_gradient(PartialSVGGradient::empty()).unwrap();
+        assert!(matches!(doc1.set_mask(circle_id.clone(), Some(gradient_id.clone())), Err(DocError::WrongKind { .. })));
+        assert!(matches!(doc1.set_mask(gradient_id.clone(), Some(circle_id.clone())), Err(DocError::WrongKind { .. })));
+
+        // An edit made while another replica removes the source keeps it,
+        // like for any other object.
+        doc1.remove_object(clip_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        doc2.edit_rectangle(clip_id.clone(), PartialSVGRectangle {
+            width: Some(50.0),
+            ..PartialSVGRectangle::empty()
+        }).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        assert!(doc1.export_svg().contains(&format!("<clipPath id=\"{}-clip\"", group_id)));
+        assert_eq!(doc1.export_svg(), doc2.export_svg());
+
+        // Masking with a source another replica removes at the same time
+        // keeps the reference, but nothing is masked by it.
+        doc1.remove_object(mask_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        doc2.set_mask(group_id.clone(), Some(mask_id.clone())).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        let SVGObject::Group(group) = &doc2.tree().children[0] else { panic!("expected the group") };
+        assert_eq!(group.mask, Some(mask_id.clone()));
+        for doc in [&doc1, &doc2] {
+            let svg = doc.export_svg();
+            assert!(!svg.contains("<mask") && !svg.contains("mask="));
+            assert!(svg.contains(&format!("clip-path=\"url(#{}-clip)\"", group_id)));
+        }
+
+        // Removing the clipped group draws its source again, and undoing
+        // brings the clip back.
+        doc2.remove_object(group_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        let svg = doc2.export_svg();
+        assert!(!svg.contains("<clipPath"));
+        assert!(svg.find(&format!("<rect id=\"{}\"", clip_id)).unwrap() > svg.find("</defs>").unwrap());
+        assert!(doc2.undo());
+        assert!(doc2.export_svg().contains(&format!("<clipPath id=\"{}-clip\"", group_id)));
+        doc2.set_clip_path(group_id.clone(), None).unwrap();
+        let svg = doc2.export_svg();
+        assert!(!svg.contains("<clipPath") && !svg.contains("clip-path="));
+    }
+
+    #[test]
+    fn test_export_embeds_assets_in_symbols() {
+        let mut doc = SVGDocCrdt2::new("r1".to_string());
+        let hash = doc.add_asset(vec![0x89, b'P', b'N', b'G', 1, 2, 3], "image/png".to_string());
+        let symbol_id = doc.add_symbol(PartialSVGGroup::empty()).unwrap();
+        doc.add_image(Some(symbol_id.clone()), PartialSVGImage {
+            href: Some(format!("asset:{}", hash)),
+            ..PartialSVGImage::empty()
+        }).unwrap();
+        doc.add_instance(None, PartialSVGInstance {
+            symbol_id: Some(symbol_id),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        let svg = doc.export_svg();
+        assert!(svg.contains("href=\"data:image/png;base64,iVBORwECAw==\""));
+        assert!(!svg.contains("asset:"));
+    }
+
+    #[test]
+    fn test_symbols_and_instances() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let symbol_id = doc1.add_symbol(PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(Some(symbol_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let instance_id = doc1.add_instance(None, PartialSVGInstance {
+            symbol_id: Some(symbol_id.clone()),
+            transform: Some(Transform { translate_x: 10.0, ..Transform::identity() }),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        let other_id = doc1.add_instance(None, PartialSVGInstance {
+            symbol_id: Some(symbol_id.clone()),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+
+        // The symbol is kept out of the tree and written once to the defs.
+        assert_eq!(doc2.tree().children.len(), 2);
+        assert_eq!(doc2.get_symbol(symbol_id.clone()).unwrap().children.len(), 1);
+        let svg = doc2.export_svg();
+        assert!(svg.contains(&format!("<symbol id=\"{}\" overflow=\"visible\">", symbol_id)));
+        assert!(svg.contains(&format!("<use id=\"{}\" transform=\"translate(10 0)\" href=\"#{}\"", instance_id, symbol_id)));
+        assert_eq!(svg.matches(&format!("<circle id=\"{}\"", circle_id)).count(), 1);
+
+        // Editing the master on one replica changes every instance on both.
+        doc2.edit_circle(circle_id.clone(), PartialSVGCircle {
+            radius: Some(42.0),
+            ..PartialSVGCircle::empty()
+        }).unwrap();
+        doc1.merge(doc2.broadcast_delta()).unwrap();
+        assert!(doc1.export_svg().contains("r=\"42\""));
+        assert_eq!(doc1.export_svg(), doc2.export_svg());
+
+        // Overrides draw a copy of the content with ids of its own.
+        doc1.edit_instance(other_id.clone(), PartialSVGInstance {
+            fill: Some(JSNullable::Some { item: Color(255, 0, 0, 1.0) }),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        assert!(doc2.get_instance(other_id.clone()).unwrap().fill.is_some());
+        let svg = doc2.export_svg();
+        assert!(svg.contains(&format!("<g id=\"{}\"", other_id)));
+        assert!(svg.contains(&format!("<circle id=\"{}-{}\"", other_id, circle_id)));
+        assert_eq!(svg.matches(&format!("href=\"#{}\"", symbol_id)).count(), 1);
+
+        // A symbol may not end up drawing itself, directly or through
+        // another symbol.
+        let nested_id = doc1.add_symbol(PartialSVGGroup::empty()).unwrap();
+        let inner_id = doc1.add_instance(Some(nested_id.clone()), PartialSVGInstance {
+            symbol_id: Some(symbol_id.clone()),
+            ..PartialSVGInstance::empty()
+        }).unwrap();
+        let looping = |symbol_id: &NodeID| PartialSVGInstance {
+            symbol_id: Some(symbol_id.clone()),
+            ..PartialSVGInstance::empty()
+        };
+        assert!(matches!(
+            doc1.add_instance(Some(symbol_id.clone()), looping(&symbol_id)),
+            Err(DocError::CycleRejected { .. })
+        ));
+        assert!(matches!(
+            doc1.add_instance(Some(symbol_id.clone()), looping(&nested_id)),
+            Err(DocError::CycleRejected { .. })
+        ));
+        assert!(matches!(doc1.move_object(Some(symbol_id.clone()), instance_id.clone(), None), Err(DocError::CycleRejected { .. })));
+        assert_eq!(
+            doc1.edit_instance(inner_id.clone(), looping(&nested_id)),
+            Err(DocError::CycleRejected { object_id: inner_id.clone(), group_id: nested_id.clone() })
+        );
+        assert!(matches!(doc1.add_instance(None, looping(&circle_id)), Err(DocError::WrongKind { .. })));
+        assert!(matches!(doc1.move_object(None, symbol_id.clone(), None), Err(DocError::WrongKind { .. })));
+        assert_eq!(doc1.get_symbol(symbol_id.clone()).unwrap().children.len(), 1);
+
+        // Instances of a removed symbol are not drawn.
+        doc2.merge(doc1.broadcast_delta()).unwrap();
+        doc2.remove_object(symbol_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        let svg = doc2.export_svg();
+        assert!(!svg.contains(&format!("id=\"{}\"", instance_id)) && !svg.contains(&format!("id=\"{}\"", other_id)));
+        assert!(svg.contains(&format!("<symbol id=\"{}\"", nested_id)));
+        assert_eq!(doc2.tree().children.len(), 2);
+    }
+
+    #[test]
+    fn test_layer_metadata() {
+        let mut doc1 = SVGDocCrdt2::new("r1".to_string());
+        let mut doc2 = SVGDocCrdt2::new("r2".to_string());
+        let group_id = doc1.add_group(None, PartialSVGGroup::empty()).unwrap();
+        let circle_id = doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).unwrap();
+        let rect_id = doc1.add_rectangle(None, PartialSVGRectangle::empty()).unwrap();
+        doc2.merge(doc1.broadcast()).unwrap();
+
+        // Each field merges on its own.
+        doc1.set_name(circle_id.clone(), "Sun".to_string()).unwrap();
+        doc2.set_hidden(circle_id.clone(), true).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        for doc in [&doc1, &doc2] {
+            let circle = doc.get_circle(circle_id.clone()).unwrap();
+            assert_eq!((circle.layer.name.as_str(), circle.layer.hidden, circle.layer.locked), ("Sun", true, false));
+        }
+        let gradient_id = doc1.add_gradient(PartialSVGGradient::empty()).unwrap();
+        assert!(matches!(doc1.set_locked(gradient_id, true), Err(DocError::WrongKind { .. })));
+
+        // Hidden objects stay in the tree unless asked otherwise.
+        let SVGObject::Group(group) = &doc2.tree().children[0] else { panic!("expected the group") };
+        assert_eq!(group.children.len(), 1);
+        let SVGObject::Group(group) = &doc2.visible_tree().children[0] else { panic!("expected the group") };
+        assert!(group.children.is_empty());
+        let svg = doc2.export_svg();
+        let line = |svg: &str, id: &str| svg.lines().find(|line| line.contains(&format!(" id=\"{}\"", id))).map(String::from);
+        assert!(line(&svg, &circle_id).unwrap().contains(" visibility=\"hidden\""));
+        assert!(!line(&svg, &group_id).unwrap().contains("visibility"));
+        assert!(line(&doc2.export_visible_svg(), &circle_id).is_none());
+        doc2.set_hidden(group_id.clone(), true).unwrap();
+        assert_eq!(doc2.visible_tree().children.len(), 1);
+        assert!(line(&doc2.export_svg(), &group_id).unwrap().contains(" visibility=\"hidden\""));
+
+        // Locked objects, and everything in a locked group, reject edits.
+        doc1.set_locked(rect_id.clone(), true).unwrap();
+        doc1.set_locked(group_id.clone(), true).unwrap();
+        let locked = Err(DocError::Locked(rect_id.clone()));
+        assert_eq!(doc1.edit_rectangle(rect_id.clone(), PartialSVGRectangle { width: Some(5.0), ..PartialSVGRectangle::empty() }), locked);
+        assert_eq!(doc1.rotate_object(rect_id.clone(), 90.0, Vec2 { x: 0.0, y: 0.0 }), locked);
+        assert_eq!(doc1.move_object(Some(group_id.clone()), rect_id.clone(), None), locked);
+        assert_eq!(doc1.remove_object(rect_id.clone(), DeletePolicy::DELETE_SUBTREE), locked);
+        let locked = Err(DocError::Locked(group_id.clone()));
+        assert_eq!(doc1.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(5.0), ..PartialSVGCircle::empty() }), locked);
+        assert_eq!(doc1.set_mask(circle_id.clone(), Some(rect_id.clone())), locked);
+        assert_eq!(doc1.add_circle(Some(group_id.clone()), PartialSVGCircle::empty()).map(|_| ()), locked);
+        assert_eq!(doc1.remove_group(group_id.clone(), DeletePolicy::REPARENT_CHILDREN), locked);
+
+        // A locked object inside keeps its group from being removed.
+        doc1.set_locked(group_id.clone(), false).unwrap();
+        doc1.set_locked(circle_id.clone(), true).unwrap();
+        assert_eq!(doc1.remove_object(group_id.clone(), DeletePolicy::DELETE_SUBTREE), Err(DocError::Locked(circle_id.clone())));
+        assert!(doc1.get_circle(circle_id.clone()).is_some());
+
+        // Undo is not held back, and unlocking allows edits again.
+        assert!(doc1.undo());
+        assert!(!doc1.get_circle(circle_id.clone()).unwrap().layer.locked);
+        doc1.edit_circle(circle_id.clone(), PartialSVGCircle { radius: Some(5.0), ..PartialSVGCircle::empty() }).unwrap();
+        doc1.set_locked(rect_id.clone(), false).unwrap();
+        doc1.remove_object(rect_id.clone(), DeletePolicy::DELETE_SUBTREE).unwrap();
+        merge_docs(&mut doc1, &mut doc2);
+        assert_eq!(doc2.get_circle(circle_id.clone()).unwrap().radius, 5.0);
+        assert!(doc2.get_rectangle(rect_id).is_none());
+    }
+
+}
@@ -0,0 +1,67 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocError {
+    // No live object or path point has this id.
+    NotFound(String),
+    // The object exists but is not of the expected kind, e.g. adding a
+    // circle into a rectangle or moving the handle of a line point.
+    WrongKind { id: String, expected: &'static str },
+    // Moving the object into the group would make it its own ancestor.
+    CycleRejected { object_id: NodeID, group_id: NodeID },
+    // An object cannot be clipped or masked by itself or a group holding it.
+    ClipCycle { object_id: NodeID, source_id: NodeID },
+    // The object, or a group holding it, is locked against edits.
+    Locked(NodeID),
+    // A caller supplied id is already taken.
+    DuplicateId(String),
+    // Saved or received data could not be read.
+    DecodeError(String),
+    // The document could not be written out.
+    EncodeError(String),
+    // Data was written by a format version this build cannot read.
+    VersionMismatch { expected: u32, found: u32 },
+    // A character range reaches past the end of a text.
+    OutOfRange { index: usize, len: usize },
+    // Compaction needs acknowledgements from other replicas and has none.
+    NoAcks,
+}
+
+impl std::fmt::Display for DocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocError::NotFound(id) => write!(f, "NotFound: `{}` does not exist", id),
+            DocError::WrongKind { id, expected } => write!(f, "WrongKind: `{}` is not a {}", id, expected),
+            DocError::CycleRejected { object_id, group_id } => {
+                write!(f, "CycleRejected: `{}` cannot be moved into its descendant `{}`", object_id, group_id)
+            },
+            DocError::ClipCycle { object_id, source_id } => {
+                write!(f, "ClipCycle: `{}` cannot be clipped or masked by `{}`, which contains it", object_id, source_id)
+            },
+            DocError::Locked(id) => write!(f, "Locked: `{}` is locked", id),
+            DocError::DuplicateId(id) => write!(f, "DuplicateId: `{}` is already in use", id),
+            DocError::DecodeError(msg) => write!(f, "DecodeError: {}", msg),
+            DocError::EncodeError(msg) => write!(f, "EncodeError: {}", msg),
+            DocError::VersionMismatch { expected, found } => {
+                write!(f, "VersionMismatch: expected format version {}, found {}", expected, found)
+            },
+            DocError::OutOfRange { index, len } => write!(f, "OutOfRange: {} is past the end of a text of length {}", index, len),
+            DocError::NoAcks => write!(f, "NoAcks: no other replica has acknowledged the document"),
+        }
+    }
+}
+
+impl std::error::Error for DocError {}
+
+// Thrown as a JS `Error` when returned from `SVGDoc`.
+impl From<DocError> for JsValue {
+    fn from(err: DocError) -> Self {
+        JsError::from(err).into()
+    }
+}
+
+impl From<serde_json::Error> for DocError {
+    fn from(err: serde_json::Error) -> Self {
+        DocError::DecodeError(err.to_string())
+    }
+}
@@ -0,0 +1,435 @@
+// Versioned save format. Saves are written as `{"version": .., "doc": ..}`
+// and older saves are brought up to date one version at a time:
+//
+// 0: oplog of the legacy op based `SVGDocCrdt`, no header.
+// 1: `(node_map, move_history)` tuple, no header. Early ones keep all the
+//    points of a path in one register.
+// 2: header around `SavedDoc`, adds `compacted_until`.
+// 3: adds the asset store.
+// 4: adds element transforms.
+// 5: adds fill and stroke gradients.
+// 6: adds stroke dashes, caps, joins and miter limits.
+// 7: adds the path fill rule.
+// 8: adds clip paths and masks.
+// 9: adds object names, hidden and locked flags.
+// 10: moves stroke styling into a struct of its own.
+// 11: moves names, hidden and locked flags into a struct of their own.
+// 12: font sizes are f64 rather than whole numbers.
+//
+// Geometry went from integers to f64 without a new version, as did the f32
+// transforms of some version 3 saves. JSON numbers read as f64 as written,
+// and an f32 was written as its shortest decimal, so it reads back as the
+// nearest f64 rather than the widened f32.
+//
+// Binary saves carry the same header. Older ones are turned into JSON and
+// go through the same migrations.
+use crate::prelude::*;
+use crate::crdt::codec;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::de::{ IgnoredAny, MapAccess, SeqAccess, Visitor };
+use serde::ser::{ SerializeMap, SerializeSeq };
+use serde_json::value::RawValue;
+
+pub const FORMAT_VERSION: u32 = 12;
+
+// Stamps the objects recreated from a legacy oplog.
+const LEGACY_REPLICA_ID: &str = "legacy";
+
+// The object kinds saved before version 4, which the later steps add fields
+// to.
+const DRAWN_KINDS: [&str; 10] = [
+    "Circle", "Rectangle", "Path", "Group", "Ellipse", "Line", "Polyline", "Polygon", "Text", "Image"
+];
+
+// The shapes with a `LWWStrokeStyle`. Groups keep optional overrides of
+// their own.
+const STROKE_STYLE_KINDS: [&str; 7] = ["Circle", "Rectangle", "Path", "Ellipse", "Line", "Polyline", "Polygon"];
+
+// The kinds with a `LWWLayer`. Instances were saved by version 8 too.
+const LAYER_KINDS: [&str; 11] = [
+    "Circle", "Rectangle", "Path", "Group", "Ellipse", "Line", "Polyline", "Polygon", "Text", "Image", "Instance"
+];
+
+type Migration = fn(&str) -> Result<String, DocError>;
+type Fields = HashMap<String, Box<RawValue>>;
+
+// `MIGRATIONS[v]` upgrades a version `v` payload to version `v + 1`. They
+// work on JSON text rather than `serde_json::Value`, which cannot hold the
+// u128 timestamps.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    migrate_legacy_oplog,
+    migrate_tuple,
+    migrate_add_assets,
+    migrate_add_transforms,
+    migrate_add_gradients,
+    migrate_add_stroke_style,
+    migrate_add_fill_rule,
+    migrate_add_clip_refs,
+    migrate_add_layer_fields,
+    migrate_nest_stroke_style,
+    migrate_nest_layer_fields,
+    migrate_float_font_size,
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedDoc {
+    pub node_map: UWMap<NodeID, LWWNodeMapItem>,
+    pub move_history: Vec<MoveLog>,
+    pub compacted_until: Option<Stamp>,
+    pub assets: AssetStore,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header<T> {
+    version: u32,
+    doc: T,
+}
+
+pub fn encode(doc: &SavedDoc) -> Result<String, DocError> {
+    serde_json::to_string(&Header { version: FORMAT_VERSION, doc })
+        .map_err(|err| DocError::EncodeError(err.to_string()))
+}
+
+pub fn encode_binary(doc: &SavedDoc) -> Result<Vec<u8>, DocError> {
+    Ok(codec::to_bytes(&Header { version: FORMAT_VERSION, doc })?)
+}
+
+pub fn decode(data: &str) -> Result<SavedDoc, DocError> {
+    let (version, doc) = match data.trim_start().chars().next() {
+        Some('{') => {
+            let Header { version, doc } = serde_json::from_str::<Header<Box<RawValue>>>(data)?;
+            (version, doc.get().to_string())
+        },
+        Some('[') if starts_with_node_map(data)? => (1, data.to_string()),
+        Some('[') => (0, data.to_string()),
+        _ => return Err(DocError::DecodeError("unrecognized save format".to_string())),
+    };
+    migrate(version, doc)
+}
+
+pub fn decode_binary(data: &[u8]) -> Result<SavedDoc, DocError> {
+    let Header { version, .. } = codec::from_bytes::<Header<IgnoredAny>>(data)?;
+    if version == FORMAT_VERSION {
+        return Ok(codec::from_bytes::<Header<SavedDoc>>(data)?.doc);
+    }
+    let Header { doc, .. } = codec::from_bytes::<Header<Value>>(data)?;
+    migrate(version, serde_json::to_string(&doc)?)
+}
+
+fn migrate(version: u32, mut doc: String) -> Result<SavedDoc, DocError> {
+    if version > FORMAT_VERSION {
+        return Err(DocError::VersionMismatch { expected: FORMAT_VERSION, found: version });
+    }
+    for migrate in MIGRATIONS[version as usize..].iter() {
+        doc = migrate(&doc)?;
+    }
+    Ok(serde_json::from_str::<SavedDoc>(&doc)?)
+}
+
+// Any value of the binary encoding, to write an older binary save out as
+// JSON for the migrations. Byte strings only hold asset data, which JSON
+// saves have as base64.
+enum Value {
+    Null,
+    Bool(bool),
+    UInt(u128),
+    Int(i128),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::UInt(v) => serializer.serialize_u128(*v),
+            Value::Int(v) => serializer.serialize_i128(*v),
+            Value::F32(v) => serializer.serialize_f32(*v),
+            Value::F64(v) => serializer.serialize_f64(*v),
+            Value::Str(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_str(&BASE64.encode(v)),
+            Value::Seq(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values.iter() {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            },
+            Value::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> { Ok(Value::Null) }
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> { Ok(Value::Bool(v)) }
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> { Ok(Value::UInt(v as u128)) }
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E> { Ok(Value::UInt(v)) }
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> { Ok(Value::Int(v as i128)) }
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> { Ok(Value::Int(v)) }
+    fn visit_f32<E>(self, v: f32) -> Result<Value, E> { Ok(Value::F32(v)) }
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> { Ok(Value::F64(v)) }
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> { Ok(Value::Str(v.to_string())) }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> { Ok(Value::Bytes(v.to_vec())) }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Seq(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+// Headerless saves are either a legacy oplog or a tuple starting with the
+// node map.
+fn starts_with_node_map(data: &str) -> Result<bool, DocError> {
+    let items = serde_json::from_str::<Vec<Box<RawValue>>>(data)?;
+    let Some(first) = items.first() else { return Ok(false); };
+    let fields = serde_json::from_str::<HashMap<String, Box<RawValue>>>(first.get()).unwrap_or_default();
+    Ok(fields.contains_key("kv"))
+}
+
+// Replays the oplog and adds the resulting objects to a fresh document,
+// keeping their ids.
+fn migrate_legacy_oplog(oplog: &str) -> Result<String, DocError> {
+    let oplog = serde_json::from_str::<Vec<SVGCrdtOps>>(oplog)?;
+    let mut legacy = SVGDocCrdt::new();
+    legacy.merge(oplog);
+    let mut doc = SVGDocCrdt2::new(LEGACY_REPLICA_ID.to_string());
+    for object in legacy.children().children {
+        doc.import_object(None, object)?;
+    }
+    let SavedDoc { node_map, move_history, .. } = doc.to_saved();
+    Ok(serde_json::to_string(&(node_map, move_history))?)
+}
+
+fn migrate_tuple(tuple: &str) -> Result<String, DocError> {
+    let (node_map, move_history) = serde_json::from_str::<(Box<RawValue>, Box<RawValue>)>(tuple)?;
+    let doc = format!(
+        "{{\"node_map\":{},\"move_history\":{},\"compacted_until\":null}}",
+        node_map.get(),
+        move_history.get()
+    );
+    migrate_path_points(&doc)
+}
+
+// Tuples from before points were keyed by id keep all of a path's points
+// in one register. Each point takes the stamp of that register, so every
+// replica migrating the same save gets the same points.
+fn migrate_path_points(doc: &str) -> Result<String, DocError> {
+    edit_objects(doc, |kind, object| {
+        if kind != "Path" { return Ok(()); }
+        let Some(points) = object.get("points") else { return Ok(()); };
+        let Ok(register) = serde_json::from_str::<LWWReg<Vec<SVGPathCommand>>>(points.get()) else { return Ok(()); };
+        let stamp = register.stamp();
+        let mut map = UWMap::<String, LWWSVGPathPoint>::new();
+        let mut index = FractionalIndex::default();
+        for point in register.val {
+            let item = LWWSVGPathPoint::new(&point, index.clone(), &stamp);
+            map.insert(stamp.replica_id.clone(), point.get_id().to_string(), item);
+            index = FractionalIndex::new_after(&index);
+        }
+        object.insert("points".to_string(), RawValue::from_string(serde_json::to_string(&map)?)?);
+        Ok(())
+    })
+}
+
+fn migrate_add_assets(doc: &str) -> Result<String, DocError> {
+    let mut fields = serde_json::from_str::<HashMap<String, Box<RawValue>>>(doc)?;
+    fields.insert("assets".to_string(), RawValue::from_string(serde_json::to_string(&AssetStore::new())?)?);
+    Ok(serde_json::to_string(&fields)?)
+}
+
+// Older objects get the identity.
+fn migrate_add_transforms(doc: &str) -> Result<String, DocError> {
+    let transform = serde_json::to_string(&LWWTransform::default())?;
+    add_fields(doc, &DRAWN_KINDS, &[("transform", transform)])
+}
+
+fn migrate_add_gradients(doc: &str) -> Result<String, DocError> {
+    let no_gradient = register::<Option<NodeID>>(None)?;
+    let doc = add_fields(
+        doc,
+        &["Circle", "Rectangle", "Path", "Group", "Ellipse", "Polyline", "Polygon", "Text"],
+        &[("fill_gradient", no_gradient.clone())]
+    )?;
+    add_fields(
+        &doc,
+        &["Circle", "Rectangle", "Path", "Group", "Ellipse", "Line", "Polyline", "Polygon"],
+        &[("stroke_gradient", no_gradient)]
+    )
+}
+
+// Groups leave all of them to their children.
+fn migrate_add_stroke_style(doc: &str) -> Result<String, DocError> {
+    let doc = add_fields(
+        doc,
+        &STROKE_STYLE_KINDS,
+        &[
+            ("stroke_dasharray", register::<Vec<f64>>(Vec::new())?),
+            ("stroke_dashoffset", register(0.0)?),
+            ("stroke_linecap", register(StrokeLinecap::default())?),
+            ("stroke_linejoin", register(StrokeLinejoin::default())?),
+            ("stroke_miterlimit", register(DEFAULT_MITERLIMIT)?),
+        ]
+    )?;
+    let inherited = register::<Option<f64>>(None)?;
+    add_fields(&doc, &["Group"], &[
+        ("stroke_dasharray", inherited.clone()),
+        ("stroke_dashoffset", inherited.clone()),
+        ("stroke_linecap", inherited.clone()),
+        ("stroke_linejoin", inherited.clone()),
+        ("stroke_miterlimit", inherited),
+    ])
+}
+
+fn migrate_add_fill_rule(doc: &str) -> Result<String, DocError> {
+    add_fields(doc, &["Path"], &[("fill_rule", register(FillRule::default())?)])
+}
+
+fn migrate_add_clip_refs(doc: &str) -> Result<String, DocError> {
+    let no_source = register::<Option<NodeID>>(None)?;
+    add_fields(doc, &DRAWN_KINDS, &[("clip_path", no_source.clone()), ("mask", no_source)])
+}
+
+fn migrate_add_layer_fields(doc: &str) -> Result<String, DocError> {
+    add_fields(doc, &LAYER_KINDS, &[
+        ("name", register(String::new())?),
+        ("hidden", register(false)?),
+        ("locked", register(false)?),
+    ])
+}
+
+fn migrate_nest_stroke_style(doc: &str) -> Result<String, DocError> {
+    edit_objects(doc, |kind, object| {
+        if !STROKE_STYLE_KINDS.contains(&kind) { return Ok(()); }
+        nest_fields(object, "stroke_style", &[
+            "stroke_dasharray",
+            "stroke_dashoffset",
+            "stroke_linecap",
+            "stroke_linejoin",
+            "stroke_miterlimit",
+        ])
+    })
+}
+
+fn migrate_nest_layer_fields(doc: &str) -> Result<String, DocError> {
+    edit_objects(doc, |kind, object| {
+        if !LAYER_KINDS.contains(&kind) { return Ok(()); }
+        nest_fields(object, "layer", &["name", "hidden", "locked"])
+    })
+}
+
+// Whole sizes would read as f64 anyway. The new version is so that older
+// builds reject a fractional size as too new rather than as malformed. The
+// register keeps its stamp, only the size is rewritten as a float.
+fn migrate_float_font_size(doc: &str) -> Result<String, DocError> {
+    edit_objects(doc, |kind, object| {
+        if kind != "Text" { return Ok(()); }
+        let Some(font_size) = object.get("font_size") else { return Ok(()); };
+        let register = serde_json::from_str::<LWWReg<f64>>(font_size.get())?;
+        object.insert("font_size".to_string(), RawValue::from_string(serde_json::to_string(&register)?)?);
+        Ok(())
+    })
+}
+
+// Moves `fields` of an object into a struct under `key`.
+fn nest_fields(object: &mut Fields, key: &str, fields: &[&str]) -> Result<(), DocError> {
+    if object.contains_key(key) { return Ok(()); }
+    let mut nested = Fields::new();
+    for field in fields.iter() {
+        let Some(value) = object.remove(*field) else { continue; };
+        nested.insert(field.to_string(), value);
+    }
+    object.insert(key.to_string(), RawValue::from_string(serde_json::to_string(&nested)?)?);
+    Ok(())
+}
+
+// Gives the objects of `kinds` the `fields` they lack, as JSON. Registers
+// come from `register` so that any real write wins over them.
+fn add_fields(doc: &str, kinds: &[&str], fields: &[(&str, String)]) -> Result<String, DocError> {
+    edit_objects(doc, |kind, object| {
+        if !kinds.contains(&kind) { return Ok(()); }
+        for (field, value) in fields.iter() {
+            if !object.contains_key(*field) {
+                object.insert(field.to_string(), RawValue::from_string(value.clone())?);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn register<T: Clone + Serialize>(val: T) -> Result<String, DocError> {
+    Ok(serde_json::to_string(&LWWReg::new_at(val, &Stamp::default()))?)
+}
+
+// Calls `edit` with the kind and fields of every object in a saved
+// document.
+pub(crate) fn edit_objects(
+    doc: &str,
+    mut edit: impl FnMut(&str, &mut Fields) -> Result<(), DocError>
+) -> Result<String, DocError> {
+    edit_field(doc, "node_map", |node_map| edit_field(node_map, "kv", |kv| {
+        let mut items = serde_json::from_str::<Fields>(kv)?;
+        for item in items.values_mut() {
+            let edited = edit_field(item.get(), "object", |object| edit_field(object, "val", |val| {
+                let mut kinds = serde_json::from_str::<Fields>(val)?;
+                for (kind, object) in kinds.iter_mut() {
+                    let mut fields = serde_json::from_str::<Fields>(object.get())?;
+                    edit(kind, &mut fields)?;
+                    *object = RawValue::from_string(serde_json::to_string(&fields)?)?;
+                }
+                Ok(serde_json::to_string(&kinds)?)
+            }))?;
+            *item = RawValue::from_string(edited)?;
+        }
+        Ok(serde_json::to_string(&items)?)
+    }))
+}
+
+fn edit_field(
+    json: &str,
+    key: &str,
+    edit: impl FnOnce(&str) -> Result<String, DocError>
+) -> Result<String, DocError> {
+    let mut fields = serde_json::from_str::<Fields>(json)?;
+    if let Some(value) = fields.get_mut(key) {
+        *value = RawValue::from_string(edit(value.get())?)?;
+    }
+    Ok(serde_json::to_string(&fields)?)
+}
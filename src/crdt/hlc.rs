@@ -0,0 +1,76 @@
+use crate::prelude::*;
+
+// Hybrid logical clock timestamp. The physical component (nanoseconds since
+// the unix epoch) sits above a 32 bit logical counter, so timestamps compare
+// as plain integers.
+pub type HLCTimestamp = u128;
+
+const LOGICAL_BITS: u32 = 32;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct HybridLogicalClock {
+    physical: UnixEpochTimeNanos,
+    logical: u32,
+}
+
+impl HybridLogicalClock {
+    pub fn new() -> Self {
+        Self { physical: 0, logical: 0 }
+    }
+
+    pub fn pack(physical: UnixEpochTimeNanos, logical: u32) -> HLCTimestamp {
+        (physical << LOGICAL_BITS) | logical as HLCTimestamp
+    }
+
+    pub fn unpack(timestamp: HLCTimestamp) -> (UnixEpochTimeNanos, u32) {
+        (timestamp >> LOGICAL_BITS, timestamp as u32)
+    }
+
+    // Last timestamp handed out or observed.
+    pub fn timestamp(&self) -> HLCTimestamp {
+        Self::pack(self.physical, self.logical)
+    }
+
+    fn tick(&mut self, physical: UnixEpochTimeNanos, logical: u32) {
+        match logical.checked_add(1) {
+            Some(logical) => {
+                self.physical = physical;
+                self.logical = logical;
+            },
+            None => {
+                self.physical = physical + 1;
+                self.logical = 0;
+            }
+        }
+    }
+
+    // Timestamp for a local event. Always greater than every timestamp
+    // previously issued or observed by this clock.
+    pub fn now(&mut self) -> HLCTimestamp {
+        let wall = epoch_now_nanos();
+        if wall > self.physical {
+            self.physical = wall;
+            self.logical = 0;
+        } else {
+            self.tick(self.physical, self.logical);
+        }
+        self.timestamp()
+    }
+
+    // Advance past a timestamp received from another replica.
+    pub fn update(&mut self, remote: HLCTimestamp) {
+        let (remote_physical, remote_logical) = Self::unpack(remote);
+        let wall = epoch_now_nanos();
+        let physical = wall.max(self.physical).max(remote_physical);
+        if physical == self.physical && physical == remote_physical {
+            self.tick(physical, self.logical.max(remote_logical));
+        } else if physical == self.physical {
+            self.tick(physical, self.logical);
+        } else if physical == remote_physical {
+            self.tick(physical, remote_logical);
+        } else {
+            self.physical = physical;
+            self.logical = 0;
+        }
+    }
+}
@@ -1,30 +1,75 @@
 use crate::prelude::*;
 
+// Identifies a write by when it happened and which replica made it. Stamps
+// order by time first and replica id second, so writes from different
+// replicas never tie.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Stamp {
+    pub time: HLCTimestamp,
+    pub replica_id: ReplicaId
+}
+
+impl Stamp {
+    pub fn new(time: HLCTimestamp, replica_id: ReplicaId) -> Self {
+        Self { time, replica_id }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LWWReg<T> where T: Clone {
     pub val: T,
-    pub time: UnixEpochTimeNanos
+    pub time: HLCTimestamp,
+    #[serde(default)]
+    pub replica_id: ReplicaId
+}
+
+// Fields added to a saved type after the fact read as written at the
+// start of time, so any real write wins over them.
+impl<T> Default for LWWReg<T> where T: Clone + Default {
+    fn default() -> Self {
+        Self::new_at(T::default(), &Stamp::default())
+    }
 }
 
 impl<T> LWWReg<T> where T: Clone {
     pub fn new(val: T) -> LWWReg<T> {
         let time = epoch_now_nanos();
-        Self { val, time }
+        Self { val, time, replica_id: ReplicaId::new() }
     }
+
+    pub fn new_at(val: T, stamp: &Stamp) -> LWWReg<T> {
+        Self { val, time: stamp.time, replica_id: stamp.replica_id.clone() }
+    }
+
     pub fn value(&self) -> &T {
         &self.val
     }
 
-    pub fn set(&mut self, val: T) {
+    pub fn stamp(&self) -> Stamp {
+        Stamp::new(self.time, self.replica_id.clone())
+    }
+
+    pub fn set(&mut self, val: T, stamp: &Stamp) {
         self.val = val;
-        self.time = epoch_now_nanos();
+        self.time = stamp.time;
+        self.replica_id = stamp.replica_id.clone();
+    }
+
+    // Writes back the value from before a local edit, unless the edit did
+    // not touch the register or anything, this replica included, has
+    // written it since.
+    pub fn revert(&mut self, before: &LWWReg<T>, after: &LWWReg<T>, stamp: &Stamp) -> bool {
+        if before.stamp() == after.stamp() { return false; }
+        if self.stamp() != after.stamp() { return false; }
+        self.set(before.val.clone(), stamp);
+        true
     }
 
     pub fn merge(a: &LWWReg<T>, b: &LWWReg<T>) -> LWWReg<T> {
-        if a.time < b.time {
-            return LWWReg { val: b.val.clone(), time: b.time.clone() };
+        if (a.time, &a.replica_id) < (b.time, &b.replica_id) {
+            return b.clone();
         }
-        return LWWReg { val: a.val.clone(), time: a.time.clone() };
+        return a.clone();
     }
 }
 
@@ -32,4 +77,4 @@ impl<T> Mergeable for LWWReg<T> where T: Clone {
     fn merge(&self, other: &Self) -> Self {
         Self::merge(self, other)
     }
-}
\ No newline at end of file
+}
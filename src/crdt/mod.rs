@@ -1,5 +1,12 @@
 pub mod core;
 pub mod vtime;
 pub mod uw_map;
+pub mod sequence;
+pub mod assets;
 pub mod lww_reg;
-pub mod core2;
\ No newline at end of file
+pub mod hlc;
+pub mod error;
+pub mod undo;
+pub mod codec;
+pub mod format;
+pub mod core2;
@@ -0,0 +1,170 @@
+use crate::prelude::*;
+
+// Identifies a character by the insertion that created it. All characters of
+// one insertion share its stamp and are told apart by their offset.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CharId {
+    pub time: HLCTimestamp,
+    pub replica_id: ReplicaId,
+    pub offset: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SeqChar {
+    pub id: CharId,
+    // Character this one was typed after, `None` at the start of the text.
+    pub origin: Option<CharId>,
+    pub value: char,
+    pub deleted: LWWReg<bool>,
+}
+
+// Replicated growable array of characters. Characters typed after the same
+// character are ordered newest first, so text typed concurrently at the
+// same place ends up as two runs instead of interleaving. Deleted characters
+// stay as tombstones since later insertions may refer to them.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TextSequence {
+    // Document order, tombstones included.
+    chars: Vec<SeqChar>,
+}
+
+impl TextSequence {
+    pub fn new(text: &str, stamp: &Stamp) -> Self {
+        let mut res = Self { chars: Vec::new() };
+        res.insert_at(0, text, stamp);
+        res
+    }
+
+    pub fn time(&self) -> Option<HLCTimestamp> {
+        self.chars.iter()
+            .map(|c| c.id.time.max(c.deleted.time))
+            .max()
+    }
+
+    pub fn value(&self) -> String {
+        self.visible().map(|c| c.value).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.visible().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn visible(&self) -> impl Iterator<Item = &SeqChar> {
+        self.chars.iter().filter(|c| !c.deleted.value())
+    }
+
+    // Position in `chars` of the `index`th visible character.
+    fn position_of(&self, index: usize) -> Option<usize> {
+        self.chars.iter()
+            .enumerate()
+            .filter(|(_, c)| !c.deleted.value())
+            .nth(index)
+            .map(|(i, _)| i)
+    }
+
+    // Inserts before the `index`th visible character, or at the end when
+    // `index` is the length of the text.
+    pub fn insert(&mut self, index: usize, text: &str, stamp: &Stamp) -> Result<(), DocError> {
+        let len = self.len();
+        if index > len { return Err(DocError::OutOfRange { index, len }); }
+        self.insert_at(index, text, stamp);
+        Ok(())
+    }
+
+    fn insert_at(&mut self, index: usize, text: &str, stamp: &Stamp) {
+        let mut origin = index.checked_sub(1)
+            .and_then(|i| self.position_of(i))
+            .map(|i| self.chars[i].id.clone());
+        let mut inserted = Vec::new();
+        for (offset, value) in text.chars().enumerate() {
+            let id = CharId { time: stamp.time, replica_id: stamp.replica_id.clone(), offset: offset as u32 };
+            inserted.push(SeqChar {
+                id: id.clone(),
+                origin: origin.replace(id),
+                value,
+                deleted: LWWReg::new_at(false, stamp)
+            });
+        }
+        let chars = std::mem::take(&mut self.chars);
+        self.chars = Self::ordered(chars.into_iter().chain(inserted));
+    }
+
+    pub fn delete(&mut self, index: usize, length: usize, stamp: &Stamp) -> Result<(), DocError> {
+        let len = self.len();
+        let end = index.checked_add(length)
+            .filter(|end| *end <= len)
+            .ok_or(DocError::OutOfRange { index: index.saturating_add(length), len })?;
+        let positions = (index..end)
+            .filter_map(|i| self.position_of(i))
+            .collect::<Vec<_>>();
+        for position in positions {
+            self.chars[position].deleted.set(true, stamp);
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, text: &str, stamp: &Stamp) {
+        for c in self.chars.iter_mut().filter(|c| !c.deleted.value()) {
+            c.deleted.set(true, stamp);
+        }
+        self.insert_at(0, text, stamp);
+    }
+
+    // Tree walk over the origins, siblings newest first.
+    fn ordered(chars: impl Iterator<Item = SeqChar>) -> Vec<SeqChar> {
+        let mut children = HashMap::<Option<CharId>, Vec<SeqChar>>::new();
+        for c in chars {
+            children.entry(c.origin.clone()).or_default().push(c);
+        }
+        let mut stack = children.remove(&None).unwrap_or_default();
+        stack.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut res = Vec::new();
+        while let Some(c) = stack.pop() {
+            if let Some(mut following) = children.remove(&Some(c.id.clone())) {
+                following.sort_by(|a, b| a.id.cmp(&b.id));
+                stack.extend(following);
+            }
+            res.push(c);
+        }
+        res
+    }
+
+    // Deletes the characters a local edit typed and restores the ones it
+    // deleted, leaving characters other replicas touched since alone.
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        let before_chars = before.chars.iter().map(|c| (&c.id, c)).collect::<HashMap<_, _>>();
+        let after_chars = after.chars.iter().map(|c| (&c.id, c)).collect::<HashMap<_, _>>();
+        let mut reverted = false;
+        for c in self.chars.iter_mut() {
+            let Some(after) = after_chars.get(&c.id) else { continue; };
+            let char_reverted = match before_chars.get(&c.id) {
+                Some(before) => c.deleted.revert(&before.deleted, &after.deleted, stamp),
+                None => {
+                    let untouched = c.deleted.stamp() == after.deleted.stamp() && !c.deleted.value();
+                    if untouched { c.deleted.set(true, stamp); }
+                    untouched
+                }
+            };
+            reverted = reverted || char_reverted;
+        }
+        reverted
+    }
+}
+
+impl Mergeable for TextSequence {
+    fn merge(&self, other: &Self) -> Self {
+        let mut chars = self.chars.iter()
+            .map(|c| (c.id.clone(), c.clone()))
+            .collect::<HashMap<_, _>>();
+        for c in other.chars.iter() {
+            chars.entry(c.id.clone())
+                .and_modify(|existing| existing.deleted = existing.deleted.merge(&c.deleted))
+                .or_insert_with(|| c.clone());
+        }
+        Self { chars: Self::ordered(chars.into_values()) }
+    }
+}
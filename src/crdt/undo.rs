@@ -0,0 +1,155 @@
+use crate::prelude::*;
+
+// A node as it was before and after a step. `None` means the node did not
+// exist or was removed. The vtimes tell whether anything else wrote the node
+// between two steps.
+#[derive(Clone)]
+pub struct Change {
+    pub node_id: NodeID,
+    pub before: Option<LWWNodeMapItem>,
+    pub after: Option<LWWNodeMapItem>,
+    pub before_vtime: Option<VTime>,
+    pub after_vtime: Option<VTime>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Recording {
+    Edit,
+    Undo,
+    Redo,
+}
+
+// How many steps each stack keeps. Every step holds whole snapshots of the
+// nodes it touched, the oldest ones are dropped past this.
+pub const MAX_STEPS: usize = 100;
+
+// Local changes grouped into the steps a user undoes with one Ctrl+Z.
+// Changes merged in from other replicas never end up here.
+pub struct UndoManager {
+    undo_stack: Vec<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+    pending: Vec<Change>,
+    depth: usize,
+    recording: Recording,
+}
+
+impl Default for UndoManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UndoManager {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            pending: Vec::new(),
+            depth: 0,
+            recording: Recording::Edit,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.pending.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // Steps nest, only the outermost one ends up on the stack.
+    pub fn begin_step(&mut self) {
+        self.depth += 1;
+    }
+
+    pub fn end_step(&mut self) {
+        let Some(depth) = self.depth.checked_sub(1) else { return; };
+        self.depth = depth;
+        if self.depth > 0 || self.pending.is_empty() { return; }
+        let step = std::mem::take(&mut self.pending);
+        let stack = match self.recording {
+            Recording::Edit => {
+                self.redo_stack.clear();
+                &mut self.undo_stack
+            },
+            Recording::Undo => &mut self.redo_stack,
+            Recording::Redo => &mut self.undo_stack,
+        };
+        stack.push(step);
+        if stack.len() > MAX_STEPS {
+            stack.remove(0);
+        }
+    }
+
+    // The changes recorded so far, to go back to with `abort_step`.
+    pub fn pending(&self) -> Vec<Change> {
+        self.pending.clone()
+    }
+
+    // Ends a step whose changes were rolled back. What it recorded is
+    // dropped, so it never reaches a stack.
+    pub fn abort_step(&mut self, pending: Vec<Change>) {
+        self.pending = pending;
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    pub fn record(
+        &mut self,
+        node_id: &NodeID,
+        (before, before_vtime): (Option<LWWNodeMapItem>, Option<VTime>),
+        (after, after_vtime): (Option<LWWNodeMapItem>, Option<VTime>)
+    ) {
+        // Within a step only the first state before and the last state
+        // after matter.
+        if let Some(change) = self.pending.iter_mut().find(|change| change.node_id == *node_id) {
+            change.after = after;
+            change.after_vtime = after_vtime;
+            return;
+        }
+        self.pending.push(Change { node_id: node_id.clone(), before, after, before_vtime, after_vtime });
+    }
+
+    // Replaying a change writes the node with new stamps. The next step on
+    // the stack being replayed that left the node exactly as the replayed
+    // one found it now expects the new state, so it can be replayed too.
+    // Steps that saw the node change in between keep what they had.
+    pub fn rebase(&mut self, node_id: &NodeID, before_vtime: &Option<VTime>, item: LWWNodeMapItem, vtime: Option<VTime>) {
+        let stack = match self.recording {
+            Recording::Undo => &mut self.undo_stack,
+            Recording::Redo => &mut self.redo_stack,
+            Recording::Edit => return,
+        };
+        let Some(change) = stack.iter_mut()
+            .rev()
+            .find_map(|step| step.iter_mut().find(|change| change.node_id == *node_id))
+            else { return; };
+        if change.after.is_none() || change.after_vtime != *before_vtime { return; }
+        change.after = Some(item);
+        change.after_vtime = vtime;
+    }
+
+    // Pops the step to replay. The changes made while replaying it form the
+    // step that goes on the opposite stack.
+    pub fn begin_replay(&mut self, recording: Recording) -> Option<Vec<Change>> {
+        let step = match recording {
+            Recording::Undo => self.undo_stack.pop(),
+            Recording::Redo => self.redo_stack.pop(),
+            Recording::Edit => None,
+        }?;
+        self.recording = recording;
+        self.begin_step();
+        Some(step)
+    }
+
+    pub fn end_replay(&mut self) {
+        self.end_step();
+        self.recording = Recording::Edit;
+    }
+}
@@ -12,6 +12,9 @@ pub trait UWMapItem: Clone {}
 impl<T> UWMapKey for T where T: HashableItem {}
 impl<T> UWMapItem for T where T: Clone {}
 
+// The update vtime, remove vtime and value held for one key.
+pub type UWMapEntry<V> = (Option<VTime>, Option<VTime>, Option<V>);
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct UWMap<K, V> where K: UWMapKey, V: UWMapItem + Mergeable {
     removed: HashMap<K, VTime>,
@@ -61,6 +64,96 @@ impl<K, V> UWMap<K, V> where K: UWMapKey, V: UWMapItem + Mergeable {
         })
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        // Stored entries, including values of tombstoned keys.
+        self.kv.iter()
+    }
+
+    pub fn keys(&self) -> HashSet<K> {
+        // All keys known to the map, including tombstoned ones.
+        self.updated.keys()
+            .chain(self.removed.keys())
+            .cloned()
+            .collect()
+    }
+
+    pub fn vtime(&self, key: &K) -> Option<VTime> {
+        // Latest vtime seen for a key, whether it was updated or removed.
+        match (self.updated.get(key), self.removed.get(key)) {
+            (Some(vu), Some(vr)) => Some(VTime::merge(vu, vr)),
+            (Some(vu), None) => Some(vu.clone()),
+            (None, Some(vr)) => Some(vr.clone()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn version(&self) -> HashMap<K, VTime> {
+        // Per key digest of what this replica has seen. A peer can send
+        // this back to receive only the entries it is missing.
+        self.keys().into_iter().fold(HashMap::new(), |mut acc, key| {
+            if let Some(vtime) = self.vtime(&key) {
+                acc.insert(key, vtime);
+            }
+            acc
+        })
+    }
+
+    pub fn delta(&self, keys: &HashSet<K>) -> UWMap<K, V> {
+        // Sub-state holding only the given keys. Merging it is equivalent
+        // to merging the matching entries of the full map.
+        let mut res = UWMap::new();
+        for key in keys.iter() {
+            if let Some(vtime) = self.updated.get(key) {
+                res.updated.insert(key.clone(), vtime.clone());
+            }
+            if let Some(vtime) = self.removed.get(key) {
+                res.removed.insert(key.clone(), vtime.clone());
+            }
+            if let Some(value) = self.kv.get(key) {
+                res.kv.insert(key.clone(), value.clone());
+            }
+        }
+        res
+    }
+
+    pub fn delta_since(&self, version: &HashMap<K, VTime>) -> UWMap<K, V> {
+        let keys = self.version()
+            .into_iter()
+            .filter(|(key, vtime)| match version.get(key) {
+                Some(known) => !matches!(
+                    vtime.partial_cmp(known), 
+                    Some(Ordering::Less) | Some(Ordering::Equal)
+                ),
+                None => true,
+            })
+            .map(|(key, _)| key)
+            .collect::<HashSet<_>>();
+        self.delta(&keys)
+    }
+
+    // What the map holds for a key, to be put back exactly with
+    // `restore_entry`.
+    pub fn entry(&self, key: &K) -> UWMapEntry<V> {
+        (self.updated.get(key).cloned(), self.removed.get(key).cloned(), self.kv.get(key).cloned())
+    }
+
+    // Unlike an insert or a remove this moves the vtimes back too, so it is
+    // only for local writes no one else has seen yet.
+    pub fn restore_entry(&mut self, key: K, (updated, removed, value): UWMapEntry<V>) {
+        match updated {
+            Some(vtime) => { self.updated.insert(key.clone(), vtime); },
+            None => { self.updated.remove(&key); },
+        }
+        match removed {
+            Some(vtime) => { self.removed.insert(key.clone(), vtime); },
+            None => { self.removed.remove(&key); },
+        }
+        match value {
+            Some(value) => { self.kv.insert(key, value); },
+            None => { self.kv.remove(&key); },
+        }
+    }
+
     pub fn insert_novtime_update(
         &mut self,
         key: K,
@@ -236,4 +329,41 @@ impl<K, V> UWMap<K, V> where K: UWMapKey, V: UWMapItem + Mergeable {
         UWMap { removed, updated, kv }
     }
 
+    pub fn has_seen(&self, version: &HashMap<K, VTime>) -> bool {
+        // Whether this map already contains everything the version covers.
+        version.iter().all(|(key, known)| match self.vtime(key) {
+            Some(vtime) => matches!(known.partial_cmp(&vtime), Some(Ordering::Less) | Some(Ordering::Equal)),
+            None => false,
+        })
+    }
+
+    pub fn compact(&mut self, stable: &HashMap<K, VTime>) {
+        // Drops tombstones every replica has seen. A stable version is one
+        // that all replicas have acknowledged, so no update that the
+        // tombstone should win over can still arrive.
+        let keys = self.removed.iter()
+            .filter(|(key, vtime)| match stable.get(key) {
+                Some(stable) => matches!((*vtime).partial_cmp(stable), Some(Ordering::Less) | Some(Ordering::Equal)),
+                None => false,
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys {
+            let Some(removed) = self.removed.remove(&key) else { continue; };
+            if let Some(updated) = self.updated.get(&key) {
+                if updated.partial_cmp(&removed) == Some(Ordering::Less) {
+                    self.updated.remove(&key);
+                    self.kv.remove(&key);
+                }
+            }
+        }
+    }
+
+    // Drops every tombstone, for maps nested in an object that every
+    // replica has seen as it is here.
+    pub fn compact_all(&mut self) {
+        let stable = self.version();
+        self.compact(&stable);
+    }
+
 }
\ No newline at end of file
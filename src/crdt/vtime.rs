@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VTime(HashMap<ReplicaId, i64>);
 
 impl VTime {
@@ -34,6 +34,25 @@ impl VTime {
         });
         Self(map)
     }
+
+    // Replicas that have written to the entry.
+    pub fn replicas(&self) -> impl Iterator<Item = &ReplicaId> {
+        let Self(map) = self;
+        map.keys()
+    }
+
+    // Greatest vtime that both a and b have seen.
+    pub fn meet(a: &VTime, b: &VTime) -> VTime {
+        let Self(map_a) = a;
+        let Self(map_b) = b;
+        let map = map_a.iter().fold(HashMap::new(), |mut acc, (ka, va)| {
+            if let Some(vb) = map_b.get(ka) {
+                acc.insert(ka.clone(), *va.min(vb));
+            }
+            acc
+        });
+        Self(map)
+    }
 }
 
 impl PartialEq for VTime {
@@ -6,11 +6,25 @@ use crate::prelude::*;
 pub struct SVGCircle {
     pub id: NodeID,
     pub pos: Vec2,
-    pub radius: i32,
+    pub radius: f64,
     pub fill: Color,
-    pub stroke_width: i32,
+    #[serde(default)]
+    pub fill_gradient: Option<NodeID>,
+    pub stroke_width: f64,
     pub stroke: Color,
+    #[serde(default)]
+    pub stroke_gradient: Option<NodeID>,
+    #[serde(flatten)]
+    pub stroke_style: StrokeStyle,
     pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
 }
 
 #[derive(Serialize, Deserialize, Tsify, Clone)]
@@ -19,15 +33,23 @@ pub struct PartialSVGCircle {
     #[tsify(optional)]
     pub pos: Option<Vec2>,
     #[tsify(optional)]
-    pub radius: Option<i32>,
+    pub radius: Option<f64>,
     #[tsify(optional)]
     pub fill: Option<Color>,
     #[tsify(optional)]
-    pub stroke_width: Option<i32>,
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<f64>,
     #[tsify(optional)]
     pub stroke: Option<Color>,
     #[tsify(optional)]
-    pub opacity: Option<f32>
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[serde(flatten)]
+    pub stroke_style: PartialStrokeStyle,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
 }
 
 impl PartialSVGCircle {
@@ -35,10 +57,14 @@ impl PartialSVGCircle {
         PartialSVGCircle { 
             pos: None, 
             radius: None, 
-            fill: None, 
+            fill: None,
+            fill_gradient: None,
             stroke_width: None, 
-            stroke: None, 
-            opacity: None
+            stroke: None,
+            stroke_gradient: None,
+            stroke_style: PartialStrokeStyle::empty(),
+            opacity: None,
+            transform: None
         }
     }
 }
@@ -49,7 +75,11 @@ impl partially::Partial for SVGCircle {
     fn apply_some(&mut self, partial: Self::Item) -> bool {
         let will_apply_some = partial.pos.is_some() || partial.radius.is_some()
             || partial.fill.is_some() || partial.stroke_width.is_some()
-            || partial.stroke.is_some() || partial.opacity.is_some();
+            || partial.stroke.is_some() || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
         if let Some(pos) = partial.pos {
             self.pos = pos.into();
         }
@@ -68,6 +98,16 @@ impl partially::Partial for SVGCircle {
         if let Some(opacity) = partial.opacity {
             self.opacity = opacity.into();
         }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        self.stroke_style.apply_some(partial.stroke_style);
         will_apply_some
     }
 }
@@ -77,20 +117,27 @@ impl SVGCircle {
     pub (crate) fn default() -> Self {
         let id = gen_str_id();
         return SVGCircle { id, 
-            pos: Vec2 { x: 0, y: 0 }, 
-            radius: 10, 
-            fill: Color::white(), 
-            stroke_width: 2, 
+            pos: Vec2 { x: 0.0, y: 0.0 }, 
+            radius: 10.0, 
+            fill: Color::white(),
+            fill_gradient: None,
+            stroke_width: 2.0, 
             stroke: Color::black(),
-            opacity: 1.0
+            stroke_gradient: None,
+            stroke_style: StrokeStyle::default(),
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
         };
     }
 
-    pub fn set_pos(&mut self, x: i32, y: i32) {
+    pub fn set_pos(&mut self, x: f64, y: f64) {
         self.pos = Vec2 { x, y };
     }
 
-    pub fn set_radius(&mut self, radius: i32) {
+    pub fn set_radius(&mut self, radius: f64) {
         self.radius = radius;
     }
 
@@ -98,7 +145,7 @@ impl SVGCircle {
         self.fill = fill;
     }
 
-    pub fn set_stroke_width(&mut self, stroke_width: i32) {
+    pub fn set_stroke_width(&mut self, stroke_width: f64) {
         self.stroke_width = stroke_width;
     }
 
@@ -106,4 +153,23 @@ impl SVGCircle {
         self.stroke = stroke
     }
 
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<circle id=\"{}\"{}{}{} cx=\"{}\" cy=\"{}\" r=\"{}\"{}{} stroke-width=\"{}\"{} opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.pos.x,
+            self.pos.y,
+            self.radius,
+            self.fill.svg_paint_attrs("fill", &self.fill_gradient),
+            self.stroke.svg_paint_attrs("stroke", &self.stroke_gradient),
+            self.stroke_width,
+            self.stroke_style.svg_attrs(),
+            self.opacity
+        ));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+use crate::prelude::*;
+
+// Every object gets its own `<clipPath>` and `<mask>`, since the source has
+// to be placed in the coordinates of the object it is applied to.
+fn clip_def_id(object_id: &str) -> String {
+    format!("{}-clip", object_id)
+}
+
+fn mask_def_id(object_id: &str) -> String {
+    format!("{}-mask", object_id)
+}
+
+// The ` clip-path="..."` and ` mask="..."` attributes, empty when unset.
+pub(crate) fn clip_attrs(object_id: &str, clip_path: &Option<NodeID>, mask: &Option<NodeID>) -> String {
+    let mut attrs = String::new();
+    if clip_path.is_some() {
+        attrs.push_str(&format!(" clip-path=\"url(#{})\"", escape_xml_attr(&clip_def_id(object_id))));
+    }
+    if mask.is_some() {
+        attrs.push_str(&format!(" mask=\"url(#{})\"", escape_xml_attr(&mask_def_id(object_id))));
+    }
+    attrs
+}
+
+struct Placement<'a> {
+    object: &'a SVGObject,
+    // Transforms of the enclosing groups, without the object's own.
+    parent_matrix: Matrix,
+    ancestors: Vec<&'a str>,
+}
+
+impl Placement<'_> {
+    fn matrix(&self) -> Matrix {
+        self.parent_matrix.multiply(&self.object.transform().matrix())
+    }
+}
+
+// The clip paths and masks of a tree that can actually be drawn. A source
+// that was removed, possibly at the same time as it was picked, or that is
+// the object itself or one of its groups, is left out and the object is
+// drawn as is. Sources are only drawn through the objects that use them,
+// and references made from inside a source are dropped so that no clip
+// ends up depending on itself.
+pub(crate) struct ClipRefs<'a> {
+    placements: HashMap<&'a str, Placement<'a>>,
+    // (object, clip path source, mask source) in paint order.
+    refs: Vec<(&'a str, Option<&'a str>, Option<&'a str>)>,
+    // In paint order.
+    sources: Vec<&'a str>,
+}
+
+impl<'a> ClipRefs<'a> {
+    pub(crate) fn resolve(children: &'a [SVGObject]) -> Self {
+        let mut placements = HashMap::new();
+        let mut order = Vec::new();
+        Self::place(children, Matrix::identity(), &[], &mut placements, &mut order);
+        let mut refs = Vec::new();
+        for object_id in order.iter() {
+            let placement = &placements[object_id];
+            let is_valid = |source_id: &&str| {
+                source_id != object_id
+                    && placements.contains_key(source_id)
+                    && !placement.ancestors.contains(source_id)
+            };
+            let (clip_path, mask) = placement.object.clip_refs();
+            let (clip_path, mask) = (clip_path.filter(is_valid), mask.filter(is_valid));
+            if clip_path.is_some() || mask.is_some() {
+                refs.push((*object_id, clip_path, mask));
+            }
+        }
+        let sources = Self::sources_of(&refs, &order);
+        let in_source = |object_id: &str| {
+            sources.contains(&object_id)
+                || placements[object_id].ancestors.iter().any(|ancestor| sources.contains(ancestor))
+        };
+        refs.retain(|(object_id, _, _)| !in_source(object_id));
+        let sources = Self::sources_of(&refs, &order);
+        ClipRefs { placements, refs, sources }
+    }
+
+    fn place(
+        children: &'a [SVGObject],
+        parent_matrix: Matrix,
+        ancestors: &[&'a str],
+        placements: &mut HashMap<&'a str, Placement<'a>>,
+        order: &mut Vec<&'a str>
+    ) {
+        for child in children.iter() {
+            let placement = Placement { object: child, parent_matrix, ancestors: ancestors.to_vec() };
+            if let SVGObject::Group(group) = child {
+                let mut group_ancestors = ancestors.to_vec();
+                group_ancestors.push(&group.id);
+                Self::place(&group.children, placement.matrix(), &group_ancestors, placements, order);
+            }
+            order.push(child.get_id());
+            placements.insert(child.get_id(), placement);
+        }
+    }
+
+    fn sources_of(refs: &[(&'a str, Option<&'a str>, Option<&'a str>)], order: &[&'a str]) -> Vec<&'a str> {
+        let used = refs.iter()
+            .flat_map(|(_, clip_path, mask)| [*clip_path, *mask])
+            .flatten()
+            .collect::<HashSet<_>>();
+        order.iter().filter(|object_id| used.contains(*object_id)).copied().collect()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.refs.is_empty()
+    }
+
+    // A copy of the objects as they are drawn: without the sources, and
+    // with only the references that resolved.
+    pub(crate) fn visible(&self, children: &[SVGObject]) -> Vec<SVGObject> {
+        self.strip(children, true)
+    }
+
+    fn strip(&self, children: &[SVGObject], hide_sources: bool) -> Vec<SVGObject> {
+        children.iter()
+            .filter(|child| !hide_sources || !self.sources.contains(&child.get_id()))
+            .map(|child| {
+                let mut child = child.clone();
+                let (clip_path, mask) = self.refs.iter()
+                    .find(|(object_id, _, _)| *object_id == child.get_id())
+                    .map(|(_, clip_path, mask)| (clip_path.map(String::from), mask.map(String::from)))
+                    .unwrap_or_default();
+                child.set_clip_refs(clip_path, mask);
+                if let SVGObject::Group(group) = &mut child {
+                    group.children = self.strip(&group.children, hide_sources);
+                }
+                child
+            })
+            .collect()
+    }
+
+    // The sources go into the defs once, and each clip path or mask uses
+    // them from there. Clip paths may only use shapes and text directly, so
+    // a group is clipped with each of its leaves.
+    pub(crate) fn write_defs(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for source_id in self.sources.iter() {
+            let placement = &self.placements[source_id];
+            // Already written as part of the enclosing source.
+            if placement.ancestors.iter().any(|ancestor| self.sources.contains(ancestor)) { continue; }
+            for object in self.strip(std::slice::from_ref(placement.object), false) {
+                object.write_svg(out, depth);
+            }
+        }
+        for (object_id, clip_path, mask) in self.refs.iter() {
+            // Sources are drawn in the object's coordinates, so undo the
+            // transforms the object is drawn with.
+            let inverse = self.placements[object_id].matrix().inverse().unwrap_or_else(Matrix::identity);
+            if let Some(source_id) = clip_path {
+                out.push_str(&format!(
+                    "{}<clipPath id=\"{}\" clipPathUnits=\"userSpaceOnUse\">\n",
+                    indent,
+                    escape_xml_attr(&clip_def_id(object_id))
+                ));
+                let mut leaves = Vec::new();
+                self.leaves(self.placements[source_id].object, &mut leaves);
+                for leaf_id in leaves {
+                    self.write_use(out, depth + 1, leaf_id, &inverse);
+                }
+                out.push_str(&format!("{}</clipPath>\n", indent));
+            }
+            if let Some(source_id) = mask {
+                out.push_str(&format!("{}<mask id=\"{}\">\n", indent, escape_xml_attr(&mask_def_id(object_id))));
+                self.write_use(out, depth + 1, source_id, &inverse);
+                out.push_str(&format!("{}</mask>\n", indent));
+            }
+        }
+    }
+
+    fn leaves(&self, object: &'a SVGObject, out: &mut Vec<&'a str>) {
+        match object {
+            SVGObject::Group(group) => group.children.iter().for_each(|child| self.leaves(child, out)),
+            _ => out.push(object.get_id())
+        }
+    }
+
+    fn write_use(&self, out: &mut String, depth: usize, object_id: &str, inverse: &Matrix) {
+        let placement = inverse.multiply(&self.placements[object_id].parent_matrix);
+        out.push_str(&format!(
+            "{}<use href=\"#{}\"{}/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(object_id),
+            Transform::from_matrix(&placement).svg_attr()
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::prelude::*;
+
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum GradientKind {
+    Linear,
+    Radial
+}
+
+// What the gradient paints past its first and last stop.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SpreadMethod {
+    Pad,
+    Reflect,
+    Repeat
+}
+
+impl SpreadMethod {
+    pub fn attr(&self) -> &'static str {
+        match self {
+            SpreadMethod::Pad => "pad",
+            SpreadMethod::Reflect => "reflect",
+            SpreadMethod::Repeat => "repeat"
+        }
+    }
+}
+
+// Coordinates are either fractions of the painted element's bounding box
+// or user units.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GradientUnits {
+    ObjectBoundingBox,
+    UserSpaceOnUse
+}
+
+impl GradientUnits {
+    pub fn attr(&self) -> &'static str {
+        match self {
+            GradientUnits::ObjectBoundingBox => "objectBoundingBox",
+            GradientUnits::UserSpaceOnUse => "userSpaceOnUse"
+        }
+    }
+}
+
+// The color's alpha becomes the `stop-opacity`.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGGradientStop {
+    pub id: String,
+    // From 0 at the start of the gradient to 1 at its end.
+    pub offset: f64,
+    pub color: Color,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGGradientStop {
+    #[tsify(optional)]
+    pub offset: Option<f64>,
+    #[tsify(optional)]
+    pub color: Option<Color>
+}
+
+impl PartialSVGGradientStop {
+    pub fn empty() -> Self {
+        PartialSVGGradientStop {
+            offset: None,
+            color: None
+        }
+    }
+}
+
+// A gradient kept as a document resource, painted by any element that
+// names it in its `fill_gradient` or `stroke_gradient`. Linear gradients
+// run from `start` to `end`, radial ones from `focus` out to the circle
+// around `center`; the fields of the other kind are kept but not written.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGGradient {
+    pub id: NodeID,
+    pub kind: GradientKind,
+    pub start: Vec2,
+    pub end: Vec2,
+    pub center: Vec2,
+    pub radius: f64,
+    pub focus: Vec2,
+    pub spread: SpreadMethod,
+    pub units: GradientUnits,
+    // In offset order.
+    pub stops: Vec<SVGGradientStop>,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGGradient {
+    #[tsify(optional)]
+    pub kind: Option<GradientKind>,
+    #[tsify(optional)]
+    pub start: Option<Vec2>,
+    #[tsify(optional)]
+    pub end: Option<Vec2>,
+    #[tsify(optional)]
+    pub center: Option<Vec2>,
+    #[tsify(optional)]
+    pub radius: Option<f64>,
+    #[tsify(optional)]
+    pub focus: Option<Vec2>,
+    #[tsify(optional)]
+    pub spread: Option<SpreadMethod>,
+    #[tsify(optional)]
+    pub units: Option<GradientUnits>,
+    // Replaces every stop, use the stop methods to edit single ones.
+    #[tsify(optional)]
+    pub stops: Option<Vec<SVGGradientStop>>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGGradient {
+    pub fn empty() -> Self {
+        PartialSVGGradient {
+            kind: None,
+            start: None,
+            end: None,
+            center: None,
+            radius: None,
+            focus: None,
+            spread: None,
+            units: None,
+            stops: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGGradient {
+    type Item = PartialSVGGradient;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.kind.is_some() || partial.start.is_some()
+            || partial.end.is_some() || partial.center.is_some()
+            || partial.radius.is_some() || partial.focus.is_some()
+            || partial.spread.is_some() || partial.units.is_some()
+            || partial.stops.is_some() || partial.transform.is_some();
+        if let Some(kind) = partial.kind {
+            self.kind = kind;
+        }
+        if let Some(start) = partial.start {
+            self.start = start;
+        }
+        if let Some(end) = partial.end {
+            self.end = end;
+        }
+        if let Some(center) = partial.center {
+            self.center = center;
+        }
+        if let Some(radius) = partial.radius {
+            self.radius = radius;
+        }
+        if let Some(focus) = partial.focus {
+            self.focus = focus;
+        }
+        if let Some(spread) = partial.spread {
+            self.spread = spread;
+        }
+        if let Some(units) = partial.units {
+            self.units = units;
+        }
+        if let Some(stops) = partial.stops {
+            self.stops = stops;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        will_apply_some
+    }
+}
+
+impl SVGGradient {
+    // Black to white, left to right across the painted element.
+    pub(crate) fn default() -> Self {
+        SVGGradient {
+            id: gen_str_id(),
+            kind: GradientKind::Linear,
+            start: Vec2 { x: 0.0, y: 0.0 },
+            end: Vec2 { x: 1.0, y: 0.0 },
+            center: Vec2 { x: 0.5, y: 0.5 },
+            radius: 0.5,
+            focus: Vec2 { x: 0.5, y: 0.5 },
+            spread: SpreadMethod::Pad,
+            units: GradientUnits::ObjectBoundingBox,
+            stops: vec![
+                SVGGradientStop { id: gen_str_id(), offset: 0.0, color: Color::black() },
+                SVGGradientStop { id: gen_str_id(), offset: 1.0, color: Color::white() }
+            ],
+            transform: Transform::identity()
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let (tag, geometry) = match self.kind {
+            GradientKind::Linear => ("linearGradient", format!(
+                " x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"",
+                self.start.x, self.start.y, self.end.x, self.end.y
+            )),
+            GradientKind::Radial => ("radialGradient", format!(
+                " cx=\"{}\" cy=\"{}\" r=\"{}\" fx=\"{}\" fy=\"{}\"",
+                self.center.x, self.center.y, self.radius, self.focus.x, self.focus.y
+            )),
+        };
+        out.push_str(&format!(
+            "{}<{} id=\"{}\"{}{} gradientUnits=\"{}\" spreadMethod=\"{}\">\n",
+            indent,
+            tag,
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr_named("gradientTransform"),
+            geometry,
+            self.units.attr(),
+            self.spread.attr()
+        ));
+        for stop in self.stops.iter() {
+            let Color(red, green, blue, alpha) = &stop.color;
+            out.push_str(&format!(
+                "{}  <stop id=\"{}\" offset=\"{}\" stop-color=\"rgb({}, {}, {})\" stop-opacity=\"{}\"/>\n",
+                indent,
+                escape_xml_attr(&stop.id),
+                stop.offset,
+                red,
+                green,
+                blue,
+                alpha
+            ));
+        }
+        out.push_str(&format!("{}</{}>\n", indent, tag));
+    }
+}
@@ -5,9 +5,31 @@ use crate::prelude::*;
 pub struct SVGGroup {
     pub id: NodeID,
     pub fill: Option<Color>,
+    #[serde(default)]
+    pub fill_gradient: Option<NodeID>,
     pub stroke: Option<Color>,
-    pub stroke_width: Option<i32>,
+    #[serde(default)]
+    pub stroke_gradient: Option<NodeID>,
+    #[serde(default)]
+    pub stroke_dasharray: Option<Vec<f64>>,
+    #[serde(default)]
+    pub stroke_dashoffset: Option<f64>,
+    #[serde(default)]
+    pub stroke_linecap: Option<StrokeLinecap>,
+    #[serde(default)]
+    pub stroke_linejoin: Option<StrokeLinejoin>,
+    #[serde(default)]
+    pub stroke_miterlimit: Option<f64>,
+    pub stroke_width: Option<f64>,
     pub opacity: Option<f32>,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
     pub children: Vec<SVGObject>
 }
 
@@ -18,6 +40,15 @@ pub enum JSNullable<T> {
     Some { item: T}, None
 }
 
+impl<T> From<JSNullable<T>> for Option<T> {
+    fn from(value: JSNullable<T>) -> Self {
+        match value {
+            JSNullable::Some { item } => Some(item),
+            JSNullable::None => None
+        }
+    }
+}
+
 
 #[derive(Serialize, Deserialize, Tsify, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -25,18 +56,42 @@ pub struct PartialSVGGroup {
     #[tsify(optional)]
     pub fill: Option<JSNullable<Color>>,
     #[tsify(optional)]
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
     pub stroke: Option<JSNullable<Color>>,
     #[tsify(optional)]
-    pub stroke_width: Option<JSNullable<i32>>,
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub stroke_dasharray: Option<JSNullable<Vec<f64>>>,
+    #[tsify(optional)]
+    pub stroke_dashoffset: Option<JSNullable<f64>>,
+    #[tsify(optional)]
+    pub stroke_linecap: Option<JSNullable<StrokeLinecap>>,
+    #[tsify(optional)]
+    pub stroke_linejoin: Option<JSNullable<StrokeLinejoin>>,
+    #[tsify(optional)]
+    pub stroke_miterlimit: Option<JSNullable<f64>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<JSNullable<f64>>,
     #[tsify(optional)]
-    pub opacity: Option<JSNullable<f32>>
+    pub opacity: Option<JSNullable<f32>>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
 }
 
 impl partially::Partial for SVGGroup {
     type Item = PartialSVGGroup;
     fn apply_some(&mut self, partial: Self::Item) -> bool {
         let will_apply_some = partial.fill.is_some() || partial.stroke.is_some()
-            || partial.stroke_width.is_some();
+            || partial.stroke_width.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || partial.stroke_dasharray.is_some()
+            || partial.stroke_dashoffset.is_some()
+            || partial.stroke_linecap.is_some()
+            || partial.stroke_linejoin.is_some()
+            || partial.stroke_miterlimit.is_some();
         if let Some(fill) = partial.fill {
             match fill {
                 JSNullable::Some { item: val } => {
@@ -77,6 +132,30 @@ impl partially::Partial for SVGGroup {
                 }
             };
         }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        if let Some(stroke_dasharray) = partial.stroke_dasharray {
+            self.stroke_dasharray = stroke_dasharray.into();
+        }
+        if let Some(stroke_dashoffset) = partial.stroke_dashoffset {
+            self.stroke_dashoffset = stroke_dashoffset.into();
+        }
+        if let Some(stroke_linecap) = partial.stroke_linecap {
+            self.stroke_linecap = stroke_linecap.into();
+        }
+        if let Some(stroke_linejoin) = partial.stroke_linejoin {
+            self.stroke_linejoin = stroke_linejoin.into();
+        }
+        if let Some(stroke_miterlimit) = partial.stroke_miterlimit {
+            self.stroke_miterlimit = stroke_miterlimit.into();
+        }
         will_apply_some
     }
 }
@@ -85,9 +164,17 @@ impl PartialSVGGroup {
     pub fn empty() -> Self {
         PartialSVGGroup {
             fill: None,
+            fill_gradient: None,
             stroke: None,
+            stroke_gradient: None,
+            stroke_dasharray: None,
+            stroke_dashoffset: None,
+            stroke_linecap: None,
+            stroke_linejoin: None,
+            stroke_miterlimit: None,
             stroke_width: None,
-            opacity: None
+            opacity: None,
+            transform: None
         }
     }
 }
@@ -98,10 +185,21 @@ impl SVGGroup {
         let id = gen_str_id();
         return SVGGroup {
             id,
-            fill: None, 
+            fill: None,
+            fill_gradient: None,
             stroke: None,
+            stroke_gradient: None,
+            stroke_dasharray: None,
+            stroke_dashoffset: None,
+            stroke_linecap: None,
+            stroke_linejoin: None,
+            stroke_miterlimit: None,
             stroke_width: None,
             opacity: None,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity(),
             children: Vec::new()
         }
     }
@@ -114,7 +212,81 @@ impl SVGGroup {
         self.stroke = Some(Color(red, green, blue, opacity));
     }
 
-    pub fn set_stroke_width(&mut self, width: i32) {
+    pub fn set_stroke_width(&mut self, width: f64) {
         self.stroke_width = Some(width);
     }
-}
\ No newline at end of file
+
+    // Only the attributes the group sets are written, everything else is
+    // inherited from the enclosing group.
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!("{}<g id=\"{}\"", indent, escape_xml_attr(&self.id)));
+        out.push_str(&self.transform.svg_attr());
+        out.push_str(&clip_attrs(&self.id, &self.clip_path, &self.mask));
+        out.push_str(&self.layer.svg_attr());
+        match (&self.fill, &self.fill_gradient) {
+            (Some(fill), _) => out.push_str(&fill.svg_paint_attrs("fill", &self.fill_gradient)),
+            (None, Some(gradient)) => out.push_str(&format!(" fill=\"{}\"", gradient_url(gradient))),
+            (None, None) => {}
+        }
+        match (&self.stroke, &self.stroke_gradient) {
+            (Some(stroke), _) => out.push_str(&stroke.svg_paint_attrs("stroke", &self.stroke_gradient)),
+            (None, Some(gradient)) => out.push_str(&format!(" stroke=\"{}\"", gradient_url(gradient))),
+            (None, None) => {}
+        }
+        if let Some(stroke_width) = self.stroke_width {
+            out.push_str(&format!(" stroke-width=\"{}\"", stroke_width));
+        }
+        if let Some(stroke_dasharray) = &self.stroke_dasharray {
+            out.push_str(&format!(" stroke-dasharray=\"{}\"", dasharray_attr(stroke_dasharray)));
+        }
+        if let Some(stroke_dashoffset) = self.stroke_dashoffset {
+            out.push_str(&format!(" stroke-dashoffset=\"{}\"", stroke_dashoffset));
+        }
+        if let Some(stroke_linecap) = self.stroke_linecap {
+            out.push_str(&format!(" stroke-linecap=\"{}\"", stroke_linecap.attr()));
+        }
+        if let Some(stroke_linejoin) = self.stroke_linejoin {
+            out.push_str(&format!(" stroke-linejoin=\"{}\"", stroke_linejoin.attr()));
+        }
+        if let Some(stroke_miterlimit) = self.stroke_miterlimit {
+            out.push_str(&format!(" stroke-miterlimit=\"{}\"", stroke_miterlimit));
+        }
+        if let Some(opacity) = self.opacity {
+            out.push_str(&format!(" opacity=\"{}\"", opacity));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push_str(">\n");
+        for child in self.children.iter() {
+            child.write_svg(out, depth + 1);
+        }
+        out.push_str(&format!("{}</g>\n", indent));
+    }
+
+    fn clear_clip_refs(&mut self) {
+        (self.clip_path, self.mask) = (None, None);
+        for child in self.children.iter_mut() {
+            child.set_clip_refs(None, None);
+            if let SVGObject::Group(group) = child { group.clear_clip_refs(); }
+        }
+    }
+
+    // Symbols keep their attributes on a group inside, since `<symbol>`
+    // takes no transform. Overflow is visible as there is no viewport to
+    // clip to. Clips and masks are dropped inside symbols.
+    pub(crate) fn write_svg_symbol(&self, out: &mut String, depth: usize, symbols: &[SVGGroup]) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!("{}<symbol id=\"{}\" overflow=\"visible\">\n", indent, escape_xml_attr(&self.id)));
+        let mut content = SVGGroup {
+            id: format!("{}-content", self.id),
+            children: resolve_instances(&self.children, symbols),
+            ..self.clone()
+        };
+        content.clear_clip_refs();
+        content.write_svg(out, depth + 1);
+        out.push_str(&format!("{}</symbol>\n", indent));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::prelude::*;
+
+// `href` is either a URL the image is linked from or `asset:<hash>` for
+// bytes kept in the document's asset store.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGImage {
+    pub id: NodeID,
+    pub pos: Vec2,
+    pub width: f64,
+    pub height: f64,
+    pub preserve_aspect_ratio: String,
+    pub href: String,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGImage {
+    #[tsify(optional)]
+    pub pos: Option<Vec2>,
+    #[tsify(optional)]
+    pub width: Option<f64>,
+    #[tsify(optional)]
+    pub height: Option<f64>,
+    #[tsify(optional)]
+    pub preserve_aspect_ratio: Option<String>,
+    #[tsify(optional)]
+    pub href: Option<String>,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGImage {
+    pub fn empty() -> Self {
+        PartialSVGImage {
+            pos: None,
+            width: None,
+            height: None,
+            preserve_aspect_ratio: None,
+            href: None,
+            opacity: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGImage {
+    type Item = PartialSVGImage;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.pos.is_some() || partial.width.is_some()
+            || partial.height.is_some() || partial.preserve_aspect_ratio.is_some()
+            || partial.href.is_some() || partial.opacity.is_some()
+            || partial.transform.is_some();
+        if let Some(pos) = partial.pos {
+            self.pos = pos;
+        }
+        if let Some(width) = partial.width {
+            self.width = width;
+        }
+        if let Some(height) = partial.height {
+            self.height = height;
+        }
+        if let Some(preserve_aspect_ratio) = partial.preserve_aspect_ratio {
+            self.preserve_aspect_ratio = preserve_aspect_ratio;
+        }
+        if let Some(href) = partial.href {
+            self.href = href;
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        will_apply_some
+    }
+}
+
+impl SVGImage {
+    pub(crate) fn default() -> Self {
+        SVGImage {
+            id: gen_str_id(),
+            pos: Vec2 { x: 0.0, y: 0.0 },
+            width: 100.0,
+            height: 100.0,
+            preserve_aspect_ratio: "xMidYMid meet".to_string(),
+            href: String::new(),
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
+        }
+    }
+
+    pub fn asset_hash(&self) -> Option<&str> {
+        self.href.strip_prefix(ASSET_HREF_PREFIX)
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<image id=\"{}\"{}{}{} x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"{}\" href=\"{}\" opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.pos.x,
+            self.pos.y,
+            self.width,
+            self.height,
+            escape_xml_attr(&self.preserve_aspect_ratio),
+            escape_xml_attr(&self.href),
+            self.opacity
+        ));
+    }
+}
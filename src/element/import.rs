@@ -0,0 +1,376 @@
+use crate::prelude::*;
+use std::str::FromStr;
+
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+// Paint properties that children inherit from enclosing groups. Leaf
+// elements store their own fill and stroke, so the inherited values are
+// resolved while parsing. Starts out with the SVG initial values.
+#[derive(Clone)]
+struct InheritedStyle {
+    fill: Color,
+    stroke: Color,
+    stroke_width: f64,
+    stroke_style: StrokeStyle,
+}
+
+impl InheritedStyle {
+    fn initial() -> Self {
+        Self {
+            fill: Color::black(),
+            stroke: Color(0, 0, 0, 0.0),
+            stroke_width: 1.0,
+            stroke_style: StrokeStyle::default(),
+        }
+    }
+}
+
+// Parses SVG markup into objects that can be added to a document. Elements
+// this crate cannot represent are skipped together with their children.
+pub(crate) fn parse_svg(svg_text: &str) -> Result<Vec<SVGObject>, DocError> {
+    let document = roxmltree::Document::parse(svg_text)
+        .map_err(|err| DocError::DecodeError(err.to_string()))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err(DocError::DecodeError(format!("expected an <svg> root element, found <{}>", root.tag_name().name())));
+    }
+    let style = InheritedStyle::initial();
+    Ok(parse_children(root, &style))
+}
+
+fn parse_children(node: roxmltree::Node, style: &InheritedStyle) -> Vec<SVGObject> {
+    node.children()
+        .filter(|child| child.is_element())
+        .filter_map(|child| parse_element(child, style))
+        .collect()
+}
+
+fn parse_element(node: roxmltree::Node, inherited: &InheritedStyle) -> Option<SVGObject> {
+    let id = node.attribute("id").map(|id| id.to_string()).unwrap_or_else(gen_str_id);
+    let fill = paint_attr(node, "fill");
+    let stroke = paint_attr(node, "stroke");
+    let stroke_width = length_attr(node, "stroke-width");
+    let stroke_dasharray = dasharray_attr(node);
+    let stroke_dashoffset = length_attr(node, "stroke-dashoffset");
+    let stroke_linecap = match style_attr(node, "stroke-linecap") {
+        Some("butt") => Some(StrokeLinecap::Butt),
+        Some("round") => Some(StrokeLinecap::Round),
+        Some("square") => Some(StrokeLinecap::Square),
+        _ => None
+    };
+    let stroke_linejoin = match style_attr(node, "stroke-linejoin") {
+        Some("miter") => Some(StrokeLinejoin::Miter),
+        Some("round") => Some(StrokeLinejoin::Round),
+        Some("bevel") => Some(StrokeLinejoin::Bevel),
+        _ => None
+    };
+    let stroke_miterlimit = number_attr(node, "stroke-miterlimit");
+    let opacity = number_attr(node, "opacity").map(|opacity| opacity as f32);
+    let transform = transform_attr(node);
+    let mut style = inherited.clone();
+    if let Some(fill) = fill.clone() { style.fill = fill; }
+    if let Some(stroke) = stroke.clone() { style.stroke = stroke; }
+    if let Some(stroke_width) = stroke_width { style.stroke_width = stroke_width; }
+    if let Some(stroke_dasharray) = stroke_dasharray.clone() { style.stroke_style.stroke_dasharray = stroke_dasharray; }
+    if let Some(stroke_dashoffset) = stroke_dashoffset { style.stroke_style.stroke_dashoffset = stroke_dashoffset; }
+    if let Some(stroke_linecap) = stroke_linecap { style.stroke_style.stroke_linecap = stroke_linecap; }
+    if let Some(stroke_linejoin) = stroke_linejoin { style.stroke_style.stroke_linejoin = stroke_linejoin; }
+    if let Some(stroke_miterlimit) = stroke_miterlimit { style.stroke_style.stroke_miterlimit = stroke_miterlimit; }
+    let object = match node.tag_name().name() {
+        "g" => SVGObject::Group(SVGGroup {
+            id,
+            fill,
+            fill_gradient: None,
+            stroke,
+            stroke_gradient: None,
+            stroke_width,
+            stroke_dasharray,
+            stroke_dashoffset,
+            stroke_linecap,
+            stroke_linejoin,
+            stroke_miterlimit,
+            opacity,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform,
+            children: parse_children(node, &style)
+        }),
+        "circle" => SVGObject::Circle(SVGCircle {
+            id,
+            pos: Vec2 { x: length_attr_or_zero(node, "cx"), y: length_attr_or_zero(node, "cy") },
+            radius: length_attr_or_zero(node, "r"),
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "rect" => SVGObject::Rectangle(SVGRectangle {
+            id,
+            pos: Vec2 { x: length_attr_or_zero(node, "x"), y: length_attr_or_zero(node, "y") },
+            height: length_attr_or_zero(node, "height"),
+            width: length_attr_or_zero(node, "width"),
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "ellipse" => SVGObject::Ellipse(SVGEllipse {
+            id,
+            pos: Vec2 { x: length_attr_or_zero(node, "cx"), y: length_attr_or_zero(node, "cy") },
+            radius_x: length_attr_or_zero(node, "rx"),
+            radius_y: length_attr_or_zero(node, "ry"),
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "line" => SVGObject::Line(SVGLine {
+            id,
+            start: Vec2 { x: length_attr_or_zero(node, "x1"), y: length_attr_or_zero(node, "y1") },
+            end: Vec2 { x: length_attr_or_zero(node, "x2"), y: length_attr_or_zero(node, "y2") },
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "polyline" => SVGObject::Polyline(SVGPolyline {
+            id,
+            points: parse_points(node.attribute("points").unwrap_or_default()),
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "polygon" => SVGObject::Polygon(SVGPolygon {
+            id,
+            points: parse_points(node.attribute("points").unwrap_or_default()),
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "text" => SVGObject::Text(SVGText {
+            id,
+            pos: Vec2 { x: length_attr_or_zero(node, "x"), y: length_attr_or_zero(node, "y") },
+            content: text_content(node),
+            font_family: style_attr(node, "font-family").unwrap_or("sans-serif").to_string(),
+            font_size: length_attr(node, "font-size").unwrap_or(16.0),
+            font_weight: font_weight_attr(node),
+            align: match style_attr(node, "text-anchor") {
+                Some("middle") => TextAlign::Middle,
+                Some("end") => TextAlign::End,
+                _ => TextAlign::Start
+            },
+            fill: style.fill,
+            fill_gradient: None,
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "image" => SVGObject::Image(SVGImage {
+            id,
+            pos: Vec2 { x: length_attr_or_zero(node, "x"), y: length_attr_or_zero(node, "y") },
+            width: length_attr_or_zero(node, "width"),
+            height: length_attr_or_zero(node, "height"),
+            preserve_aspect_ratio: node.attribute("preserveAspectRatio").unwrap_or("xMidYMid meet").to_string(),
+            href: node.attribute("href")
+                .or_else(|| node.attribute((XLINK_NS, "href")))
+                .unwrap_or_default()
+                .to_string(),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        "path" => SVGObject::Path(SVGPath {
+            id,
+            fill: style.fill,
+            fill_gradient: None,
+            stroke_width: style.stroke_width,
+            stroke: style.stroke,
+            stroke_gradient: None,
+            stroke_style: style.stroke_style.clone(),
+            fill_rule: match style_attr(node, "fill-rule") {
+                Some("evenodd") => FillRule::Evenodd,
+                _ => FillRule::Nonzero
+            },
+            points: parse_path_data(node.attribute("d").unwrap_or_default()),
+            opacity: opacity.unwrap_or(1.0),
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform
+        }),
+        _ => return None,
+    };
+    Some(object)
+}
+
+// Relative commands become absolute ones, `H`/`V` become lines, `S`/`T`
+// become curves with the reflected handle and arcs are approximated with
+// cubic curves. Parsing stops at the first error, as renderers do.
+pub(crate) fn parse_path_data(data: &str) -> Vec<SVGPathCommand> {
+    let point = |x: f64, y: f64| Vec2 { x, y };
+    svgtypes::SimplifyingPathParser::from(data)
+        .map_while(|segment| segment.ok())
+        .map(|segment| match segment {
+            svgtypes::SimplePathSegment::MoveTo { x, y } => SVGPathCommand::Start {
+                id: gen_str_id(),
+                pos: point(x, y)
+            },
+            svgtypes::SimplePathSegment::LineTo { x, y } => SVGPathCommand::Line {
+                id: gen_str_id(),
+                pos: point(x, y)
+            },
+            svgtypes::SimplePathSegment::CurveTo { x1, y1, x2, y2, x, y } => SVGPathCommand::Bezier {
+                id: gen_str_id(),
+                handle1: point(x1, y1),
+                handle2: point(x2, y2),
+                pos: point(x, y)
+            },
+            svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => SVGPathCommand::BezierQuad {
+                id: gen_str_id(),
+                handle: point(x1, y1),
+                pos: point(x, y)
+            },
+            svgtypes::SimplePathSegment::ClosePath => SVGPathCommand::Close { id: gen_str_id() },
+        })
+        .collect()
+}
+
+// A trailing unpaired coordinate is dropped.
+pub(crate) fn parse_points(data: &str) -> Vec<SVGVertex> {
+    let positions = svgtypes::PointsParser::from(data)
+        .map(|(x, y)| Vec2 { x, y })
+        .collect();
+    SVGVertex::from_positions(positions)
+}
+
+// Text of `<tspan>`s and other children is flattened into one run.
+fn text_content(node: roxmltree::Node) -> String {
+    node.descendants()
+        .filter(|descendant| descendant.is_text())
+        .filter_map(|descendant| descendant.text())
+        .collect()
+}
+
+fn font_weight_attr(node: roxmltree::Node) -> i32 {
+    match style_attr(node, "font-weight") {
+        Some("bold") => 700,
+        Some(weight) => weight.parse().unwrap_or(400),
+        None => 400,
+    }
+}
+
+// Presentation attributes can also be set through the `style` attribute,
+// which takes precedence.
+fn style_attr<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    let from_style = node.attribute("style").and_then(|style| {
+        style.rsplit(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(property, _)| property.trim() == name)
+            .map(|(_, value)| value.trim())
+    });
+    from_style.or_else(|| node.attribute(name))
+}
+
+// Any list of transforms collapses into one matrix, which is then split
+// into the components the document stores.
+fn transform_attr(node: roxmltree::Node) -> Transform {
+    let Some(value) = node.attribute("transform") else { return Transform::identity(); };
+    let Ok(t) = svgtypes::Transform::from_str(value) else { return Transform::identity(); };
+    Transform::from_matrix(&Matrix {
+        a: t.a,
+        b: t.b,
+        c: t.c,
+        d: t.d,
+        e: t.e,
+        f: t.f
+    })
+}
+
+// `none` clears the dashes of an enclosing group, an empty list here.
+fn dasharray_attr(node: roxmltree::Node) -> Option<Vec<f64>> {
+    let value = style_attr(node, "stroke-dasharray")?;
+    if value.trim() == "none" { return Some(Vec::new()); }
+    let dashes = svgtypes::LengthListParser::from(value)
+        .map(|length| length.map(|length| length.number))
+        .collect::<Result<Vec<_>, _>>();
+    dashes.ok()
+}
+
+fn number_attr(node: roxmltree::Node, name: &str) -> Option<f64> {
+    let value = style_attr(node, name)?;
+    svgtypes::Number::from_str(value).ok().map(|number| number.0)
+}
+
+// Units are ignored, the document works in user units.
+fn length_attr(node: roxmltree::Node, name: &str) -> Option<f64> {
+    let value = style_attr(node, name)?;
+    svgtypes::Length::from_str(value).ok().map(|length| length.number)
+}
+
+fn length_attr_or_zero(node: roxmltree::Node, name: &str) -> f64 {
+    length_attr(node, name).unwrap_or(0.0)
+}
+
+// `none` is kept as a fully transparent color. Paints the document cannot
+// represent, like gradients, fall back to inheriting.
+fn paint_attr(node: roxmltree::Node, name: &str) -> Option<Color> {
+    let value = style_attr(node, name)?;
+    let paint_opacity = number_attr(node, &format!("{}-opacity", name)).unwrap_or(1.0) as f32;
+    match svgtypes::Paint::from_str(value).ok()? {
+        svgtypes::Paint::None => Some(Color(0, 0, 0, 0.0)),
+        svgtypes::Paint::Color(color) => Some(Color(
+            color.red as i32,
+            color.green as i32,
+            color.blue as i32,
+            color.alpha as f32 / 255.0 * paint_opacity
+        )),
+        _ => None,
+    }
+}
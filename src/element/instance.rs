@@ -0,0 +1,256 @@
+use crate::prelude::*;
+
+// Draws a symbol, a group kept in the document's defs, at the instance's
+// transform. The overrides replace the fill, stroke and stroke width of
+// every shape in the symbol, `None` keeps the symbol's own.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGInstance {
+    pub id: NodeID,
+    pub symbol_id: NodeID,
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: Option<f64>,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGInstance {
+    #[tsify(optional)]
+    pub symbol_id: Option<NodeID>,
+    #[tsify(optional)]
+    pub fill: Option<JSNullable<Color>>,
+    #[tsify(optional)]
+    pub stroke: Option<JSNullable<Color>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<JSNullable<f64>>,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGInstance {
+    pub fn empty() -> Self {
+        PartialSVGInstance {
+            symbol_id: None,
+            fill: None,
+            stroke: None,
+            stroke_width: None,
+            opacity: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGInstance {
+    type Item = PartialSVGInstance;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.symbol_id.is_some() || partial.fill.is_some()
+            || partial.stroke.is_some() || partial.stroke_width.is_some()
+            || partial.opacity.is_some() || partial.transform.is_some();
+        if let Some(symbol_id) = partial.symbol_id {
+            self.symbol_id = symbol_id;
+        }
+        if let Some(fill) = partial.fill {
+            self.fill = fill.into();
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke = stroke.into();
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width = stroke_width.into();
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        will_apply_some
+    }
+}
+
+impl SVGInstance {
+    pub(crate) fn default() -> Self {
+        SVGInstance {
+            id: gen_str_id(),
+            symbol_id: NodeID::new(),
+            fill: None,
+            stroke: None,
+            stroke_width: None,
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
+        }
+    }
+
+    pub(crate) fn has_overrides(&self) -> bool {
+        self.fill.is_some() || self.stroke.is_some() || self.stroke_width.is_some()
+    }
+
+    // A copy of the symbol's content as this instance draws it, wrapped in
+    // a group that carries the instance's own attributes. Ids inside are
+    // prefixed with the instance's so that they stay unique.
+    pub(crate) fn expand(&self, symbol: &SVGGroup) -> SVGGroup {
+        let mut content = symbol.clone();
+        content.id = format!("{}-{}", self.id, symbol.id);
+        content.clip_path = None;
+        content.mask = None;
+        content.children.iter_mut().for_each(|child| self.apply_overrides(child));
+        SVGGroup {
+            id: self.id.clone(),
+            opacity: Some(self.opacity),
+            clip_path: self.clip_path.clone(),
+            mask: self.mask.clone(),
+            transform: self.transform,
+            children: vec![SVGObject::Group(content)],
+            ..SVGGroup::default()
+        }
+    }
+
+    fn apply_overrides(&self, object: &mut SVGObject) {
+        object.set_id(format!("{}-{}", self.id, object.get_id()));
+        object.set_clip_refs(None, None);
+        match object {
+            SVGObject::Circle(circle) => {
+                self.override_fill(&mut circle.fill, &mut circle.fill_gradient);
+                self.override_stroke(&mut circle.stroke, &mut circle.stroke_gradient, &mut circle.stroke_width);
+            },
+            SVGObject::Rectangle(rect) => {
+                self.override_fill(&mut rect.fill, &mut rect.fill_gradient);
+                self.override_stroke(&mut rect.stroke, &mut rect.stroke_gradient, &mut rect.stroke_width);
+            },
+            SVGObject::Path(pth) => {
+                self.override_fill(&mut pth.fill, &mut pth.fill_gradient);
+                self.override_stroke(&mut pth.stroke, &mut pth.stroke_gradient, &mut pth.stroke_width);
+            },
+            SVGObject::Ellipse(ellipse) => {
+                self.override_fill(&mut ellipse.fill, &mut ellipse.fill_gradient);
+                self.override_stroke(&mut ellipse.stroke, &mut ellipse.stroke_gradient, &mut ellipse.stroke_width);
+            },
+            SVGObject::Line(line) => {
+                self.override_stroke(&mut line.stroke, &mut line.stroke_gradient, &mut line.stroke_width);
+            },
+            SVGObject::Polyline(polyline) => {
+                self.override_fill(&mut polyline.fill, &mut polyline.fill_gradient);
+                self.override_stroke(&mut polyline.stroke, &mut polyline.stroke_gradient, &mut polyline.stroke_width);
+            },
+            SVGObject::Polygon(polygon) => {
+                self.override_fill(&mut polygon.fill, &mut polygon.fill_gradient);
+                self.override_stroke(&mut polygon.stroke, &mut polygon.stroke_gradient, &mut polygon.stroke_width);
+            },
+            SVGObject::Text(text) => self.override_fill(&mut text.fill, &mut text.fill_gradient),
+            SVGObject::Group(grp) => grp.children.iter_mut().for_each(|child| self.apply_overrides(child)),
+            // Nested instances take the outer overrides over their own.
+            SVGObject::Instance(instance) => {
+                instance.fill = self.fill.clone().or(instance.fill.clone());
+                instance.stroke = self.stroke.clone().or(instance.stroke.clone());
+                instance.stroke_width = self.stroke_width.or(instance.stroke_width);
+            },
+            SVGObject::Image(_) | SVGObject::Gradient(_) => {}
+        }
+    }
+
+    fn override_fill(&self, fill: &mut Color, fill_gradient: &mut Option<NodeID>) {
+        let Some(override_fill) = &self.fill else { return; };
+        *fill = override_fill.clone();
+        *fill_gradient = None;
+    }
+
+    fn override_stroke(&self, stroke: &mut Color, stroke_gradient: &mut Option<NodeID>, stroke_width: &mut f64) {
+        if let Some(override_stroke) = &self.stroke {
+            *stroke = override_stroke.clone();
+            *stroke_gradient = None;
+        }
+        if let Some(override_width) = self.stroke_width {
+            *stroke_width = override_width;
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<use id=\"{}\"{}{}{} href=\"#{}\" opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            escape_xml_attr(&self.symbol_id),
+            self.opacity
+        ));
+    }
+}
+
+// Replaces instances with what they draw. Instances without overrides stay
+// `<use>` elements, the others are expanded, and those whose symbol is gone
+// or would end up drawing itself are left out.
+pub(crate) fn resolve_instances(children: &[SVGObject], symbols: &[SVGGroup]) -> Vec<SVGObject> {
+    resolve_instances_aux(children, symbols, &mut Vec::new())
+}
+
+fn resolve_instances_aux<'a>(
+    children: &[SVGObject],
+    symbols: &'a [SVGGroup],
+    drawing: &mut Vec<&'a str>
+) -> Vec<SVGObject> {
+    children.iter()
+        .filter_map(|child| match child {
+            SVGObject::Group(group) => {
+                let mut group = group.clone();
+                group.children = resolve_instances_aux(&group.children, symbols, drawing);
+                Some(SVGObject::Group(group))
+            },
+            SVGObject::Instance(instance) => {
+                let symbol = symbols.iter().find(|symbol| symbol.id == instance.symbol_id)?;
+                if drawing.contains(&symbol.id.as_str()) || draws_itself(symbol, symbols, &mut vec![symbol.id.as_str()]) {
+                    return None;
+                }
+                if !instance.has_overrides() {
+                    return Some(child.clone());
+                }
+                drawing.push(&symbol.id);
+                let mut expanded = instance.expand(symbol);
+                expanded.children = resolve_instances_aux(&expanded.children, symbols, drawing);
+                drawing.pop();
+                Some(SVGObject::Group(expanded))
+            },
+            _ => Some(child.clone())
+        })
+        .collect()
+}
+
+// Whether drawing the symbol leads back to one of the symbols on `path`.
+fn draws_itself<'a>(symbol: &'a SVGGroup, symbols: &'a [SVGGroup], path: &mut Vec<&'a str>) -> bool {
+    let mut instances = Vec::new();
+    instances_in(&symbol.children, &mut instances);
+    instances.into_iter().any(|symbol_id| {
+        if path.contains(&symbol_id) { return true; }
+        let Some(used) = symbols.iter().find(|used| used.id == symbol_id) else { return false; };
+        path.push(&used.id);
+        let draws_itself = draws_itself(used, symbols, path);
+        path.pop();
+        draws_itself
+    })
+}
+
+fn instances_in<'a>(children: &'a [SVGObject], out: &mut Vec<&'a str>) {
+    for child in children.iter() {
+        match child {
+            SVGObject::Group(group) => instances_in(&group.children, out),
+            SVGObject::Instance(instance) => out.push(&instance.symbol_id),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::prelude::*;
+
+// How an object shows up in the layers panel. Objects flatten it into
+// their own fields.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug, Default, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Layer {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub locked: bool,
+}
+
+impl Layer {
+    // Hidden objects are still exported, just not drawn. Children don't
+    // set visibility back, so hiding a group hides what it holds.
+    pub(crate) fn svg_attr(&self) -> String {
+        if self.hidden { String::from(" visibility=\"hidden\"") } else { String::new() }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::prelude::*;
+
+// A straight segment. Lines have no inside, so unlike the other shapes
+// there is no fill.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGLine {
+    pub id: NodeID,
+    pub start: Vec2,
+    pub end: Vec2,
+    pub stroke_width: f64,
+    pub stroke: Color,
+    #[serde(default)]
+    pub stroke_gradient: Option<NodeID>,
+    #[serde(flatten)]
+    pub stroke_style: StrokeStyle,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGLine {
+    #[tsify(optional)]
+    pub start: Option<Vec2>,
+    #[tsify(optional)]
+    pub end: Option<Vec2>,
+    #[tsify(optional)]
+    pub stroke_width: Option<f64>,
+    #[tsify(optional)]
+    pub stroke: Option<Color>,
+    #[tsify(optional)]
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[serde(flatten)]
+    pub stroke_style: PartialStrokeStyle,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGLine {
+    pub fn empty() -> Self {
+        PartialSVGLine {
+            start: None,
+            end: None,
+            stroke_width: None,
+            stroke: None,
+            stroke_gradient: None,
+            stroke_style: PartialStrokeStyle::empty(),
+            opacity: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGLine {
+    type Item = PartialSVGLine;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.start.is_some() || partial.end.is_some()
+            || partial.stroke_width.is_some() || partial.stroke.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
+        if let Some(start) = partial.start {
+            self.start = start;
+        }
+        if let Some(end) = partial.end {
+            self.end = end;
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width = stroke_width;
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke = stroke;
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        self.stroke_style.apply_some(partial.stroke_style);
+        will_apply_some
+    }
+}
+
+impl SVGLine {
+    pub(crate) fn default() -> Self {
+        SVGLine {
+            id: gen_str_id(),
+            start: Vec2 { x: 0.0, y: 0.0 },
+            end: Vec2 { x: 20.0, y: 20.0 },
+            stroke_width: 2.0,
+            stroke: Color::black(),
+            stroke_gradient: None,
+            stroke_style: StrokeStyle::default(),
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<line id=\"{}\"{}{}{} x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{} stroke-width=\"{}\"{} opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.start.x,
+            self.start.y,
+            self.end.x,
+            self.end.y,
+            self.stroke.svg_paint_attrs("stroke", &self.stroke_gradient),
+            self.stroke_width,
+            self.stroke_style.svg_attrs(),
+            self.opacity
+        ));
+    }
+}
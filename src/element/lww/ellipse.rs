@@ -0,0 +1,195 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGEllipse {
+    pub id: LWWReg<NodeID>,
+    pub pos: LWWReg<Vec2>,
+    pub radius_x: LWWReg<f64>,
+    pub radius_y: LWWReg<f64>,
+    pub fill: LWWReg<Color>,
+    #[serde(default)]
+    pub fill_gradient: LWWReg<Option<NodeID>>,
+    pub stroke_width: LWWReg<f64>,
+    pub stroke: LWWReg<Color>,
+    #[serde(default)]
+    pub stroke_gradient: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub stroke_style: LWWStrokeStyle,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGEllipse {
+    pub fn new(SVGEllipse {
+        id,
+        pos,
+        radius_x,
+        radius_y,
+        fill,
+        fill_gradient,
+        stroke_width,
+        stroke,
+        stroke_gradient,
+        stroke_style,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGEllipse, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            pos: LWWReg::new_at(pos, stamp),
+            radius_x: LWWReg::new_at(radius_x, stamp),
+            radius_y: LWWReg::new_at(radius_y, stamp),
+            fill: LWWReg::new_at(fill, stamp),
+            fill_gradient: LWWReg::new_at(fill_gradient, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_gradient: LWWReg::new_at(stroke_gradient, stamp),
+            stroke_style: LWWStrokeStyle::new(stroke_style, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.pos.time,
+            self.radius_x.time,
+            self.radius_y.time,
+            self.fill.time,
+            self.fill_gradient.time,
+            self.stroke_width.time,
+            self.stroke.time,
+            self.stroke_gradient.time,
+            self.stroke_style.time(),
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGEllipse {
+        SVGEllipse {
+            id: self.id.value().clone(),
+            pos: self.pos.value().clone(),
+            radius_x: *self.radius_x.value(),
+            radius_y: *self.radius_y.value(),
+            fill: self.fill.value().clone(),
+            fill_gradient: self.fill_gradient.value().clone(),
+            stroke_width: *self.stroke_width.value(),
+            stroke: self.stroke.value().clone(),
+            stroke_gradient: self.stroke_gradient.value().clone(),
+            stroke_style: self.stroke_style.value(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGEllipse {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            pos: self.pos.merge(&other.pos),
+            radius_x: self.radius_x.merge(&other.radius_x),
+            radius_y: self.radius_y.merge(&other.radius_y),
+            fill: self.fill.merge(&other.fill),
+            fill_gradient: self.fill_gradient.merge(&other.fill_gradient),
+            stroke_width: self.stroke_width.merge(&other.stroke_width),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_gradient: self.stroke_gradient.merge(&other.stroke_gradient),
+            stroke_style: self.stroke_style.merge(&other.stroke_style),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGEllipse {
+    pub fn apply_some(&mut self, partial: PartialSVGEllipse, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.pos.is_some()
+            || partial.radius_x.is_some()
+            || partial.radius_y.is_some()
+            || partial.fill.is_some()
+            || partial.stroke_width.is_some()
+            || partial.stroke.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
+        if let Some(pos) = partial.pos {
+            self.pos.set(pos, stamp);
+        }
+        if let Some(radius_x) = partial.radius_x {
+            self.radius_x.set(radius_x, stamp);
+        }
+        if let Some(radius_y) = partial.radius_y {
+            self.radius_y.set(radius_y, stamp);
+        }
+        if let Some(fill) = partial.fill {
+            self.fill.set(fill, stamp);
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width.set(stroke_width, stamp);
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke.set(stroke, stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient.set(fill_gradient.into(), stamp);
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient.set(stroke_gradient.into(), stamp);
+        }
+        self.stroke_style.apply_some(partial.stroke_style, stamp);
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.pos.revert(&before.pos, &after.pos, stamp),
+            self.radius_x.revert(&before.radius_x, &after.radius_x, stamp),
+            self.radius_y.revert(&before.radius_y, &after.radius_y, stamp),
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.fill_gradient.revert(&before.fill_gradient, &after.fill_gradient, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_gradient.revert(&before.stroke_gradient, &after.stroke_gradient, stamp),
+            self.stroke_style.revert(&before.stroke_style, &after.stroke_style, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
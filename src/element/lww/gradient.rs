@@ -0,0 +1,294 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWGradientStop {
+    pub offset: LWWReg<f64>,
+    pub color: LWWReg<Color>,
+}
+
+impl LWWGradientStop {
+    pub fn new(offset: f64, color: Color, stamp: &Stamp) -> Self {
+        Self {
+            offset: LWWReg::new_at(offset, stamp),
+            color: LWWReg::new_at(color, stamp),
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        self.offset.time.max(self.color.time)
+    }
+}
+
+impl Mergeable for LWWGradientStop {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            offset: self.offset.merge(&other.offset),
+            color: self.color.merge(&other.color),
+        }
+    }
+}
+
+// Stops keyed by stop id, so replicas editing different stops of the same
+// gradient merge both edits. The offset doubles as the order, stops on the
+// same offset are ordered by id.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWGradientStops {
+    pub stops: UWMap<String, LWWGradientStop>,
+}
+
+impl LWWGradientStops {
+    pub fn new(stops: Vec<SVGGradientStop>, stamp: &Stamp) -> Self {
+        let mut res = Self { stops: UWMap::new() };
+        res.set(stops, stamp);
+        res
+    }
+
+    pub fn time(&self) -> Option<HLCTimestamp> {
+        self.stops.iter().map(|(_, stop)| stop.time()).max()
+    }
+
+    pub fn value(&self) -> Vec<SVGGradientStop> {
+        let mut stops = self.stops.value()
+            .into_iter()
+            .map(|(id, stop)| SVGGradientStop { id, offset: *stop.offset.value(), color: stop.color.value().clone() })
+            .collect::<Vec<_>>();
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset).then_with(|| a.id.cmp(&b.id)));
+        stops
+    }
+
+    pub fn has(&self, stop_id: &str) -> bool {
+        self.stops.get(&stop_id.to_string()).is_some()
+    }
+
+    pub fn set(&mut self, stops: Vec<SVGGradientStop>, stamp: &Stamp) {
+        for (stop_id, _) in self.stops.value() {
+            self.stops.remove(stamp.replica_id.clone(), stop_id);
+        }
+        for SVGGradientStop { id, offset, color } in stops {
+            self.stops.insert(stamp.replica_id.clone(), id, LWWGradientStop::new(offset, color, stamp));
+        }
+    }
+
+    pub fn insert(&mut self, stop: SVGGradientStop, stamp: &Stamp) -> Result<(), DocError> {
+        if self.has(&stop.id) { return Err(DocError::DuplicateId(stop.id)); }
+        self.stops.insert(stamp.replica_id.clone(), stop.id, LWWGradientStop::new(stop.offset, stop.color, stamp));
+        Ok(())
+    }
+
+    pub fn edit(&mut self, stop_id: &str, partial: PartialSVGGradientStop, stamp: &Stamp) -> Result<(), DocError> {
+        let stop_id = stop_id.to_string();
+        let Some(stop) = self.stops.get(&stop_id) else { return Err(DocError::NotFound(stop_id)); };
+        let mut stop = stop.clone();
+        if let Some(offset) = partial.offset {
+            stop.offset.set(offset, stamp);
+        }
+        if let Some(color) = partial.color {
+            stop.color.set(color, stamp);
+        }
+        self.stops.insert(stamp.replica_id.clone(), stop_id, stop);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, stop_id: &str, stamp: &Stamp) -> Result<(), DocError> {
+        if !self.has(stop_id) { return Err(DocError::NotFound(stop_id.to_string())); }
+        self.stops.remove(stamp.replica_id.clone(), stop_id.to_string());
+        Ok(())
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        let before_stops = before.stops.value();
+        let after_stops = after.stops.value();
+        let stop_ids = before_stops.keys()
+            .chain(after_stops.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        let mut reverted = false;
+        for stop_id in stop_ids {
+            let current = self.stops.get(&stop_id).cloned();
+            match (before_stops.get(&stop_id), after_stops.get(&stop_id), current) {
+                // Added by the edit.
+                (None, Some(_), Some(_)) => {
+                    self.stops.remove(stamp.replica_id.clone(), stop_id);
+                },
+                // Removed by the edit and still gone.
+                (Some(before), None, None) => {
+                    self.stops.insert(stamp.replica_id.clone(), stop_id, before.clone());
+                },
+                (Some(before), Some(after), Some(mut current)) => {
+                    let stop_reverted = [
+                        current.offset.revert(&before.offset, &after.offset, stamp),
+                        current.color.revert(&before.color, &after.color, stamp)
+                    ].into_iter().any(|reverted| reverted);
+                    if !stop_reverted { continue; }
+                    self.stops.insert(stamp.replica_id.clone(), stop_id, current);
+                },
+                _ => continue,
+            }
+            reverted = true;
+        }
+        reverted
+    }
+}
+
+impl Mergeable for LWWGradientStops {
+    fn merge(&self, other: &Self) -> Self {
+        Self { stops: UWMap::merge(&self.stops, &other.stops) }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGGradient {
+    pub id: LWWReg<NodeID>,
+    pub kind: LWWReg<GradientKind>,
+    pub start: LWWReg<Vec2>,
+    pub end: LWWReg<Vec2>,
+    pub center: LWWReg<Vec2>,
+    pub radius: LWWReg<f64>,
+    pub focus: LWWReg<Vec2>,
+    pub spread: LWWReg<SpreadMethod>,
+    pub units: LWWReg<GradientUnits>,
+    pub stops: LWWGradientStops,
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGGradient {
+    pub fn new(SVGGradient {
+        id,
+        kind,
+        start,
+        end,
+        center,
+        radius,
+        focus,
+        spread,
+        units,
+        stops,
+        transform
+    }: SVGGradient, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            kind: LWWReg::new_at(kind, stamp),
+            start: LWWReg::new_at(start, stamp),
+            end: LWWReg::new_at(end, stamp),
+            center: LWWReg::new_at(center, stamp),
+            radius: LWWReg::new_at(radius, stamp),
+            focus: LWWReg::new_at(focus, stamp),
+            spread: LWWReg::new_at(spread, stamp),
+            units: LWWReg::new_at(units, stamp),
+            stops: LWWGradientStops::new(stops, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.kind.time,
+            self.start.time,
+            self.end.time,
+            self.center.time,
+            self.radius.time,
+            self.focus.time,
+            self.spread.time,
+            self.units.time,
+            self.stops.time().unwrap_or_default(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGGradient {
+        SVGGradient {
+            id: self.id.value().clone(),
+            kind: *self.kind.value(),
+            start: self.start.value().clone(),
+            end: self.end.value().clone(),
+            center: self.center.value().clone(),
+            radius: *self.radius.value(),
+            focus: self.focus.value().clone(),
+            spread: *self.spread.value(),
+            units: *self.units.value(),
+            stops: self.stops.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGGradient {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            kind: self.kind.merge(&other.kind),
+            start: self.start.merge(&other.start),
+            end: self.end.merge(&other.end),
+            center: self.center.merge(&other.center),
+            radius: self.radius.merge(&other.radius),
+            focus: self.focus.merge(&other.focus),
+            spread: self.spread.merge(&other.spread),
+            units: self.units.merge(&other.units),
+            stops: self.stops.merge(&other.stops),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGGradient {
+    pub fn apply_some(&mut self, partial: PartialSVGGradient, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.kind.is_some()
+            || partial.start.is_some()
+            || partial.end.is_some()
+            || partial.center.is_some()
+            || partial.radius.is_some()
+            || partial.focus.is_some()
+            || partial.spread.is_some()
+            || partial.units.is_some()
+            || partial.stops.is_some()
+            || partial.transform.is_some();
+        if let Some(kind) = partial.kind {
+            self.kind.set(kind, stamp);
+        }
+        if let Some(start) = partial.start {
+            self.start.set(start, stamp);
+        }
+        if let Some(end) = partial.end {
+            self.end.set(end, stamp);
+        }
+        if let Some(center) = partial.center {
+            self.center.set(center, stamp);
+        }
+        if let Some(radius) = partial.radius {
+            self.radius.set(radius, stamp);
+        }
+        if let Some(focus) = partial.focus {
+            self.focus.set(focus, stamp);
+        }
+        if let Some(spread) = partial.spread {
+            self.spread.set(spread, stamp);
+        }
+        if let Some(units) = partial.units {
+            self.units.set(units, stamp);
+        }
+        if let Some(stops) = partial.stops {
+            self.stops.set(stops, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.kind.revert(&before.kind, &after.kind, stamp),
+            self.start.revert(&before.start, &after.start, stamp),
+            self.end.revert(&before.end, &after.end, stamp),
+            self.center.revert(&before.center, &after.center, stamp),
+            self.radius.revert(&before.radius, &after.radius, stamp),
+            self.focus.revert(&before.focus, &after.focus, stamp),
+            self.spread.revert(&before.spread, &after.spread, stamp),
+            self.units.revert(&before.units, &after.units, stamp),
+            self.stops.revert(&before.stops, &after.stops, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
@@ -4,107 +4,244 @@ use crate::prelude::*;
 pub struct LWWSVGGroup {
     pub id: LWWReg<NodeID>,
     pub fill: LWWReg<Option<Color>>,
+    #[serde(default)]
+    pub fill_gradient: LWWReg<Option<NodeID>>,
     pub stroke: LWWReg<Option<Color>>,
-    pub stroke_width: LWWReg<Option<i32>>,
+    #[serde(default)]
+    pub stroke_gradient: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub stroke_dasharray: LWWReg<Option<Vec<f64>>>,
+    #[serde(default)]
+    pub stroke_dashoffset: LWWReg<Option<f64>>,
+    #[serde(default)]
+    pub stroke_linecap: LWWReg<Option<StrokeLinecap>>,
+    #[serde(default)]
+    pub stroke_linejoin: LWWReg<Option<StrokeLinejoin>>,
+    #[serde(default)]
+    pub stroke_miterlimit: LWWReg<Option<f64>>,
+    pub stroke_width: LWWReg<Option<f64>>,
     pub opacity: LWWReg<Option<f32>>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
     pub children: LWWReg<Vec<SVGObject>>
 }
 
 impl LWWSVGGroup {
+    pub fn new(SVGGroup { 
+        id, 
+        fill,
+        fill_gradient,
+        stroke,
+        stroke_gradient,
+        stroke_dasharray,
+        stroke_dashoffset,
+        stroke_linecap,
+        stroke_linejoin,
+        stroke_miterlimit,
+        stroke_width, 
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform,
+        children 
+    }: SVGGroup, stamp: &Stamp) -> Self {
+        Self { 
+            id: LWWReg::new_at(id, stamp), 
+            fill: LWWReg::new_at(fill, stamp),
+            fill_gradient: LWWReg::new_at(fill_gradient, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_gradient: LWWReg::new_at(stroke_gradient, stamp),
+            stroke_dasharray: LWWReg::new_at(stroke_dasharray, stamp),
+            stroke_dashoffset: LWWReg::new_at(stroke_dashoffset, stamp),
+            stroke_linecap: LWWReg::new_at(stroke_linecap, stamp),
+            stroke_linejoin: LWWReg::new_at(stroke_linejoin, stamp),
+            stroke_miterlimit: LWWReg::new_at(stroke_miterlimit, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp), 
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp),
+            children: LWWReg::new_at(children, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.fill.time,
+            self.fill_gradient.time,
+            self.stroke.time,
+            self.stroke_gradient.time,
+            self.stroke_dasharray.time,
+            self.stroke_dashoffset.time,
+            self.stroke_linecap.time,
+            self.stroke_linejoin.time,
+            self.stroke_miterlimit.time,
+            self.stroke_width.time,
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time(),
+            self.children.time
+        ].into_iter().max().unwrap_or_default()
+    }
+
     pub fn value(&self) -> SVGGroup {
         SVGGroup { 
             id: self.id.value().clone(), 
-            fill: self.fill.value().clone(), 
-            stroke: self.stroke.value().clone(), 
+            fill: self.fill.value().clone(),
+            fill_gradient: self.fill_gradient.value().clone(),
+            stroke: self.stroke.value().clone(),
+            stroke_gradient: self.stroke_gradient.value().clone(),
+            stroke_dasharray: self.stroke_dasharray.value().clone(),
+            stroke_dashoffset: *self.stroke_dashoffset.value(),
+            stroke_linecap: *self.stroke_linecap.value(),
+            stroke_linejoin: *self.stroke_linejoin.value(),
+            stroke_miterlimit: *self.stroke_miterlimit.value(),
             stroke_width: self.stroke_width.value().clone(), 
             opacity: self.opacity.value().clone(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value(),
             children: self.children.value().clone() 
         }
     }
 }
 
-impl From<SVGGroup> for LWWSVGGroup {
-    fn from(SVGGroup { 
-        id, 
-        fill, 
-        stroke, 
-        stroke_width, 
-        opacity,
-        children 
-    }: SVGGroup) -> Self {
-        Self { 
-            id: LWWReg::new(id), 
-            fill: LWWReg::new(fill), 
-            stroke: LWWReg::new(stroke), 
-            stroke_width: LWWReg::new(stroke_width), 
-            opacity: LWWReg::new(opacity),
-            children: LWWReg::new(children)
-        }
-    }
-}
-
 impl Mergeable for LWWSVGGroup {
     fn merge(&self, other: &Self) -> Self {
         Self { 
             id: self.id.merge(&other.id), 
-            fill: self.fill.merge(&other.fill), 
-            stroke: self.stroke.merge(&other.stroke), 
+            fill: self.fill.merge(&other.fill),
+            fill_gradient: self.fill_gradient.merge(&other.fill_gradient),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_gradient: self.stroke_gradient.merge(&other.stroke_gradient),
+            stroke_dasharray: self.stroke_dasharray.merge(&other.stroke_dasharray),
+            stroke_dashoffset: self.stroke_dashoffset.merge(&other.stroke_dashoffset),
+            stroke_linecap: self.stroke_linecap.merge(&other.stroke_linecap),
+            stroke_linejoin: self.stroke_linejoin.merge(&other.stroke_linejoin),
+            stroke_miterlimit: self.stroke_miterlimit.merge(&other.stroke_miterlimit),
             stroke_width: self.stroke_width.merge(&other.stroke_width), 
             opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform),
             children: self.children.merge(&other.children)
         }
     }
 }
 
-impl partially::Partial for LWWSVGGroup {
-    type Item = PartialSVGGroup;
-
-    fn apply_some(&mut self, partial: Self::Item) -> bool {
+impl LWWSVGGroup {
+    pub fn apply_some(&mut self, partial: PartialSVGGroup, stamp: &Stamp) -> bool {
         let will_apply_some = partial.fill.is_some()
             || partial.stroke.is_some()
             || partial.stroke_width.is_some()
-            || partial.opacity.is_some();
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || partial.stroke_dasharray.is_some()
+            || partial.stroke_dashoffset.is_some()
+            || partial.stroke_linecap.is_some()
+            || partial.stroke_linejoin.is_some()
+            || partial.stroke_miterlimit.is_some();
         if let Some(fill) = partial.fill {
             match fill {
                 JSNullable::Some { item: val } => {
-                    self.fill.set(Some(val));
+                    self.fill.set(Some(val), stamp);
                 },
                 JSNullable::None => {
-                    self.fill.set(None);
+                    self.fill.set(None, stamp);
                 }
             }
         }
         if let Some(stroke) = partial.stroke {
             match stroke {
                 JSNullable::Some { item } => {
-                    self.stroke.set(Some(item));
+                    self.stroke.set(Some(item), stamp);
                 },
                 JSNullable::None => {
-                    self.stroke.set(None);
+                    self.stroke.set(None, stamp);
                 }
             };
         }
         if let Some(stroke_width) = partial.stroke_width {
             match stroke_width {
                 JSNullable::Some { item } => {
-                    self.stroke_width.set(Some(item));
+                    self.stroke_width.set(Some(item), stamp);
                 },
                 JSNullable::None => {
-                    self.stroke_width.set(None);
+                    self.stroke_width.set(None, stamp);
                 }
             };
         }
         if let Some(opacity) = partial.opacity {
             match opacity {
                 JSNullable::Some { item } => {
-                    self.opacity.set(Some(item));
+                    self.opacity.set(Some(item), stamp);
                 },
                 JSNullable::None => {
-                    self.opacity.set(None);
+                    self.opacity.set(None, stamp);
                 }
             };
         }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient.set(fill_gradient.into(), stamp);
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient.set(stroke_gradient.into(), stamp);
+        }
+        if let Some(stroke_dasharray) = partial.stroke_dasharray {
+            self.stroke_dasharray.set(stroke_dasharray.into(), stamp);
+        }
+        if let Some(stroke_dashoffset) = partial.stroke_dashoffset {
+            self.stroke_dashoffset.set(stroke_dashoffset.into(), stamp);
+        }
+        if let Some(stroke_linecap) = partial.stroke_linecap {
+            self.stroke_linecap.set(stroke_linecap.into(), stamp);
+        }
+        if let Some(stroke_linejoin) = partial.stroke_linejoin {
+            self.stroke_linejoin.set(stroke_linejoin.into(), stamp);
+        }
+        if let Some(stroke_miterlimit) = partial.stroke_miterlimit {
+            self.stroke_miterlimit.set(stroke_miterlimit.into(), stamp);
+        }
         will_apply_some
     }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.fill_gradient.revert(&before.fill_gradient, &after.fill_gradient, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_gradient.revert(&before.stroke_gradient, &after.stroke_gradient, stamp),
+            self.stroke_dasharray.revert(&before.stroke_dasharray, &after.stroke_dasharray, stamp),
+            self.stroke_dashoffset.revert(&before.stroke_dashoffset, &after.stroke_dashoffset, stamp),
+            self.stroke_linecap.revert(&before.stroke_linecap, &after.stroke_linecap, stamp),
+            self.stroke_linejoin.revert(&before.stroke_linejoin, &after.stroke_linejoin, stamp),
+            self.stroke_miterlimit.revert(&before.stroke_miterlimit, &after.stroke_miterlimit, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
 }
 
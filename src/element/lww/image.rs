@@ -0,0 +1,150 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGImage {
+    pub id: LWWReg<NodeID>,
+    pub pos: LWWReg<Vec2>,
+    pub width: LWWReg<f64>,
+    pub height: LWWReg<f64>,
+    pub preserve_aspect_ratio: LWWReg<String>,
+    pub href: LWWReg<String>,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGImage {
+    pub fn new(SVGImage {
+        id,
+        pos,
+        width,
+        height,
+        preserve_aspect_ratio,
+        href,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGImage, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            pos: LWWReg::new_at(pos, stamp),
+            width: LWWReg::new_at(width, stamp),
+            height: LWWReg::new_at(height, stamp),
+            preserve_aspect_ratio: LWWReg::new_at(preserve_aspect_ratio, stamp),
+            href: LWWReg::new_at(href, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.pos.time,
+            self.width.time,
+            self.height.time,
+            self.preserve_aspect_ratio.time,
+            self.href.time,
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGImage {
+        SVGImage {
+            id: self.id.value().clone(),
+            pos: self.pos.value().clone(),
+            width: *self.width.value(),
+            height: *self.height.value(),
+            preserve_aspect_ratio: self.preserve_aspect_ratio.value().clone(),
+            href: self.href.value().clone(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGImage {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            pos: self.pos.merge(&other.pos),
+            width: self.width.merge(&other.width),
+            height: self.height.merge(&other.height),
+            preserve_aspect_ratio: self.preserve_aspect_ratio.merge(&other.preserve_aspect_ratio),
+            href: self.href.merge(&other.href),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGImage {
+    pub fn apply_some(&mut self, partial: PartialSVGImage, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.pos.is_some()
+            || partial.width.is_some()
+            || partial.height.is_some()
+            || partial.preserve_aspect_ratio.is_some()
+            || partial.href.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some();
+        if let Some(pos) = partial.pos {
+            self.pos.set(pos, stamp);
+        }
+        if let Some(width) = partial.width {
+            self.width.set(width, stamp);
+        }
+        if let Some(height) = partial.height {
+            self.height.set(height, stamp);
+        }
+        if let Some(preserve_aspect_ratio) = partial.preserve_aspect_ratio {
+            self.preserve_aspect_ratio.set(preserve_aspect_ratio, stamp);
+        }
+        if let Some(href) = partial.href {
+            self.href.set(href, stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.pos.revert(&before.pos, &after.pos, stamp),
+            self.width.revert(&before.width, &after.width, stamp),
+            self.height.revert(&before.height, &after.height, stamp),
+            self.preserve_aspect_ratio.revert(&before.preserve_aspect_ratio, &after.preserve_aspect_ratio, stamp),
+            self.href.revert(&before.href, &after.href, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
@@ -0,0 +1,139 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGInstance {
+    pub id: LWWReg<NodeID>,
+    pub symbol_id: LWWReg<NodeID>,
+    pub fill: LWWReg<Option<Color>>,
+    pub stroke: LWWReg<Option<Color>>,
+    pub stroke_width: LWWReg<Option<f64>>,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGInstance {
+    pub fn new(SVGInstance {
+        id,
+        symbol_id,
+        fill,
+        stroke,
+        stroke_width,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGInstance, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            symbol_id: LWWReg::new_at(symbol_id, stamp),
+            fill: LWWReg::new_at(fill, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.symbol_id.time,
+            self.fill.time,
+            self.stroke.time,
+            self.stroke_width.time,
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGInstance {
+        SVGInstance {
+            id: self.id.value().clone(),
+            symbol_id: self.symbol_id.value().clone(),
+            fill: self.fill.value().clone(),
+            stroke: self.stroke.value().clone(),
+            stroke_width: *self.stroke_width.value(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGInstance {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            symbol_id: self.symbol_id.merge(&other.symbol_id),
+            fill: self.fill.merge(&other.fill),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_width: self.stroke_width.merge(&other.stroke_width),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGInstance {
+    pub fn apply_some(&mut self, partial: PartialSVGInstance, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.symbol_id.is_some()
+            || partial.fill.is_some()
+            || partial.stroke.is_some()
+            || partial.stroke_width.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some();
+        if let Some(symbol_id) = partial.symbol_id {
+            self.symbol_id.set(symbol_id, stamp);
+        }
+        if let Some(fill) = partial.fill {
+            self.fill.set(fill.into(), stamp);
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke.set(stroke.into(), stamp);
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width.set(stroke_width.into(), stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.symbol_id.revert(&before.symbol_id, &after.symbol_id, stamp),
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::prelude::*;
+
+// One register per property, see `Layer`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWLayer {
+    pub name: LWWReg<String>,
+    pub hidden: LWWReg<bool>,
+    pub locked: LWWReg<bool>,
+}
+
+// Unnamed, visible and unlocked, stamped before any real write.
+impl Default for LWWLayer {
+    fn default() -> Self {
+        LWWLayer::new(Layer::default(), &Stamp::default())
+    }
+}
+
+impl LWWLayer {
+    pub fn new(Layer {
+        name,
+        hidden,
+        locked
+    }: Layer, stamp: &Stamp) -> Self {
+        Self {
+            name: LWWReg::new_at(name, stamp),
+            hidden: LWWReg::new_at(hidden, stamp),
+            locked: LWWReg::new_at(locked, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        [
+            self.name.time,
+            self.hidden.time,
+            self.locked.time
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> Layer {
+        Layer {
+            name: self.name.value().clone(),
+            hidden: *self.hidden.value(),
+            locked: *self.locked.value()
+        }
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.name.revert(&before.name, &after.name, stamp),
+            self.hidden.revert(&before.hidden, &after.hidden, stamp),
+            self.locked.revert(&before.locked, &after.locked, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
+
+impl Mergeable for LWWLayer {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            name: self.name.merge(&other.name),
+            hidden: self.hidden.merge(&other.hidden),
+            locked: self.locked.merge(&other.locked)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGLine {
+    pub id: LWWReg<NodeID>,
+    pub start: LWWReg<Vec2>,
+    pub end: LWWReg<Vec2>,
+    pub stroke_width: LWWReg<f64>,
+    pub stroke: LWWReg<Color>,
+    #[serde(default)]
+    pub stroke_gradient: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub stroke_style: LWWStrokeStyle,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGLine {
+    pub fn new(SVGLine {
+        id,
+        start,
+        end,
+        stroke_width,
+        stroke,
+        stroke_gradient,
+        stroke_style,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGLine, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            start: LWWReg::new_at(start, stamp),
+            end: LWWReg::new_at(end, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_gradient: LWWReg::new_at(stroke_gradient, stamp),
+            stroke_style: LWWStrokeStyle::new(stroke_style, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.start.time,
+            self.end.time,
+            self.stroke_width.time,
+            self.stroke.time,
+            self.stroke_gradient.time,
+            self.stroke_style.time(),
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGLine {
+        SVGLine {
+            id: self.id.value().clone(),
+            start: self.start.value().clone(),
+            end: self.end.value().clone(),
+            stroke_width: *self.stroke_width.value(),
+            stroke: self.stroke.value().clone(),
+            stroke_gradient: self.stroke_gradient.value().clone(),
+            stroke_style: self.stroke_style.value(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGLine {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            start: self.start.merge(&other.start),
+            end: self.end.merge(&other.end),
+            stroke_width: self.stroke_width.merge(&other.stroke_width),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_gradient: self.stroke_gradient.merge(&other.stroke_gradient),
+            stroke_style: self.stroke_style.merge(&other.stroke_style),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGLine {
+    pub fn apply_some(&mut self, partial: PartialSVGLine, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.start.is_some()
+            || partial.end.is_some()
+            || partial.stroke_width.is_some()
+            || partial.stroke.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
+        if let Some(start) = partial.start {
+            self.start.set(start, stamp);
+        }
+        if let Some(end) = partial.end {
+            self.end.set(end, stamp);
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width.set(stroke_width, stamp);
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke.set(stroke, stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient.set(stroke_gradient.into(), stamp);
+        }
+        self.stroke_style.apply_some(partial.stroke_style, stamp);
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.start.revert(&before.start, &after.start, stamp),
+            self.end.revert(&before.end, &after.end, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_gradient.revert(&before.stroke_gradient, &after.stroke_gradient, stamp),
+            self.stroke_style.revert(&before.stroke_style, &after.stroke_style, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
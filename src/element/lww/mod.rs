@@ -1,35 +1,286 @@
 use crate::prelude::*;
 pub mod circle;
+pub mod ellipse;
+pub mod line;
+pub mod polyline;
+pub mod polygon;
+pub mod text;
+pub mod image;
+pub mod transform;
+pub mod gradient;
+pub mod instance;
+pub mod stroke;
+pub mod layer;
 pub mod rectangle;
 pub mod group;
 pub mod path;
 
+// Index for an item inserted between two neighbours in a list ordered by
+// (index, id), as used for path points and vertices.
+pub fn index_between(lower: Option<&FractionalIndex>, upper: Option<&FractionalIndex>) -> FractionalIndex {
+    match (lower, upper) {
+        (Some(lower), Some(upper)) => FractionalIndex::new_between(lower, upper)
+            // Neighbours that share an index leave no room in between, the
+            // id then decides the order.
+            .unwrap_or_else(|| lower.clone()),
+        (Some(lower), None) => FractionalIndex::new_after(lower),
+        (None, Some(upper)) => FractionalIndex::new_before(upper),
+        (None, None) => FractionalIndex::default(),
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum LWWSVGObject {
     Circle(LWWSVGCircle),
     Rectangle(LWWSVGRectangle),
     Path(LWWSVGPath),
-    Group(LWWSVGGroup)
+    Group(LWWSVGGroup),
+    Ellipse(LWWSVGEllipse),
+    Line(LWWSVGLine),
+    Polyline(LWWSVGPolyline),
+    Polygon(LWWSVGPolygon),
+    Text(LWWSVGText),
+    Image(LWWSVGImage),
+    Gradient(LWWSVGGradient),
+    Instance(LWWSVGInstance)
 }
 
 impl LWWSVGObject {
+    pub fn new(value: SVGObject, stamp: &Stamp) -> Self {
+        match value {
+            SVGObject::Circle(circle) => LWWSVGObject::Circle(LWWSVGCircle::new(circle, stamp)),
+            SVGObject::Group(group) => LWWSVGObject::Group(LWWSVGGroup::new(group, stamp)),
+            SVGObject::Path(path) => LWWSVGObject::Path(LWWSVGPath::new(path, stamp)),
+            SVGObject::Rectangle(rectangle) => LWWSVGObject::Rectangle(LWWSVGRectangle::new(rectangle, stamp)),
+            SVGObject::Ellipse(ellipse) => LWWSVGObject::Ellipse(LWWSVGEllipse::new(ellipse, stamp)),
+            SVGObject::Line(line) => LWWSVGObject::Line(LWWSVGLine::new(line, stamp)),
+            SVGObject::Polyline(polyline) => LWWSVGObject::Polyline(LWWSVGPolyline::new(polyline, stamp)),
+            SVGObject::Polygon(polygon) => LWWSVGObject::Polygon(LWWSVGPolygon::new(polygon, stamp)),
+            SVGObject::Text(text) => LWWSVGObject::Text(LWWSVGText::new(text, stamp)),
+            SVGObject::Image(image) => LWWSVGObject::Image(LWWSVGImage::new(image, stamp)),
+            SVGObject::Instance(instance) => LWWSVGObject::Instance(LWWSVGInstance::new(instance, stamp)),
+            SVGObject::Gradient(gradient) => LWWSVGObject::Gradient(LWWSVGGradient::new(gradient, stamp)),
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        match *self {
+            LWWSVGObject::Circle(ref circle) => circle.time(),
+            LWWSVGObject::Rectangle(ref rectangle) => rectangle.time(),
+            LWWSVGObject::Group(ref group) => group.time(),
+            LWWSVGObject::Path(ref path) => path.time(),
+            LWWSVGObject::Ellipse(ref ellipse) => ellipse.time(),
+            LWWSVGObject::Line(ref line) => line.time(),
+            LWWSVGObject::Polyline(ref polyline) => polyline.time(),
+            LWWSVGObject::Polygon(ref polygon) => polygon.time(),
+            LWWSVGObject::Text(ref text) => text.time(),
+            LWWSVGObject::Image(ref image) => image.time(),
+            LWWSVGObject::Instance(ref instance) => instance.time(),
+            LWWSVGObject::Gradient(ref gradient) => gradient.time()
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            LWWSVGObject::Circle(_) => "CIRCLE",
+            LWWSVGObject::Rectangle(_) => "RECTANGLE",
+            LWWSVGObject::Group(_) => "GROUP",
+            LWWSVGObject::Path(_) => "PATH",
+            LWWSVGObject::Ellipse(_) => "ELLIPSE",
+            LWWSVGObject::Line(_) => "LINE",
+            LWWSVGObject::Polyline(_) => "POLYLINE",
+            LWWSVGObject::Polygon(_) => "POLYGON",
+            LWWSVGObject::Text(_) => "TEXT",
+            LWWSVGObject::Image(_) => "IMAGE",
+            LWWSVGObject::Instance(_) => "INSTANCE",
+            LWWSVGObject::Gradient(_) => "GRADIENT"
+        }
+    }
+
+    pub fn transform(&self) -> &LWWTransform {
+        match *self {
+            LWWSVGObject::Circle(ref circle) => &circle.transform,
+            LWWSVGObject::Rectangle(ref rectangle) => &rectangle.transform,
+            LWWSVGObject::Group(ref group) => &group.transform,
+            LWWSVGObject::Path(ref path) => &path.transform,
+            LWWSVGObject::Ellipse(ref ellipse) => &ellipse.transform,
+            LWWSVGObject::Line(ref line) => &line.transform,
+            LWWSVGObject::Polyline(ref polyline) => &polyline.transform,
+            LWWSVGObject::Polygon(ref polygon) => &polygon.transform,
+            LWWSVGObject::Text(ref text) => &text.transform,
+            LWWSVGObject::Image(ref image) => &image.transform,
+            LWWSVGObject::Instance(ref instance) => &instance.transform,
+            LWWSVGObject::Gradient(ref gradient) => &gradient.transform
+        }
+    }
+
+    pub fn transform_mut(&mut self) -> &mut LWWTransform {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => &mut circle.transform,
+            LWWSVGObject::Rectangle(ref mut rectangle) => &mut rectangle.transform,
+            LWWSVGObject::Group(ref mut group) => &mut group.transform,
+            LWWSVGObject::Path(ref mut path) => &mut path.transform,
+            LWWSVGObject::Ellipse(ref mut ellipse) => &mut ellipse.transform,
+            LWWSVGObject::Line(ref mut line) => &mut line.transform,
+            LWWSVGObject::Polyline(ref mut polyline) => &mut polyline.transform,
+            LWWSVGObject::Polygon(ref mut polygon) => &mut polygon.transform,
+            LWWSVGObject::Text(ref mut text) => &mut text.transform,
+            LWWSVGObject::Image(ref mut image) => &mut image.transform,
+            LWWSVGObject::Instance(ref mut instance) => &mut instance.transform,
+            LWWSVGObject::Gradient(ref mut gradient) => &mut gradient.transform
+        }
+    }
+
+    // Gradients cannot be clipped or masked.
+    pub fn clip_path_mut(&mut self) -> Option<&mut LWWReg<Option<NodeID>>> {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => Some(&mut circle.clip_path),
+            LWWSVGObject::Rectangle(ref mut rectangle) => Some(&mut rectangle.clip_path),
+            LWWSVGObject::Group(ref mut group) => Some(&mut group.clip_path),
+            LWWSVGObject::Path(ref mut path) => Some(&mut path.clip_path),
+            LWWSVGObject::Ellipse(ref mut ellipse) => Some(&mut ellipse.clip_path),
+            LWWSVGObject::Line(ref mut line) => Some(&mut line.clip_path),
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.clip_path),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.clip_path),
+            LWWSVGObject::Text(ref mut text) => Some(&mut text.clip_path),
+            LWWSVGObject::Image(ref mut image) => Some(&mut image.clip_path),
+            LWWSVGObject::Instance(ref mut instance) => Some(&mut instance.clip_path),
+            LWWSVGObject::Gradient(_) => None
+        }
+    }
+
+    pub fn mask_mut(&mut self) -> Option<&mut LWWReg<Option<NodeID>>> {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => Some(&mut circle.mask),
+            LWWSVGObject::Rectangle(ref mut rectangle) => Some(&mut rectangle.mask),
+            LWWSVGObject::Group(ref mut group) => Some(&mut group.mask),
+            LWWSVGObject::Path(ref mut path) => Some(&mut path.mask),
+            LWWSVGObject::Ellipse(ref mut ellipse) => Some(&mut ellipse.mask),
+            LWWSVGObject::Line(ref mut line) => Some(&mut line.mask),
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.mask),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.mask),
+            LWWSVGObject::Text(ref mut text) => Some(&mut text.mask),
+            LWWSVGObject::Image(ref mut image) => Some(&mut image.mask),
+            LWWSVGObject::Instance(ref mut instance) => Some(&mut instance.mask),
+            LWWSVGObject::Gradient(_) => None
+        }
+    }
+
+    // Gradients are resources rather than layers and carry no name,
+    // visibility or lock.
+    pub fn name_mut(&mut self) -> Option<&mut LWWReg<String>> {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => Some(&mut circle.layer.name),
+            LWWSVGObject::Rectangle(ref mut rectangle) => Some(&mut rectangle.layer.name),
+            LWWSVGObject::Group(ref mut group) => Some(&mut group.layer.name),
+            LWWSVGObject::Path(ref mut path) => Some(&mut path.layer.name),
+            LWWSVGObject::Ellipse(ref mut ellipse) => Some(&mut ellipse.layer.name),
+            LWWSVGObject::Line(ref mut line) => Some(&mut line.layer.name),
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.layer.name),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.layer.name),
+            LWWSVGObject::Text(ref mut text) => Some(&mut text.layer.name),
+            LWWSVGObject::Image(ref mut image) => Some(&mut image.layer.name),
+            LWWSVGObject::Instance(ref mut instance) => Some(&mut instance.layer.name),
+            LWWSVGObject::Gradient(_) => None
+        }
+    }
+
+    pub fn hidden_mut(&mut self) -> Option<&mut LWWReg<bool>> {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => Some(&mut circle.layer.hidden),
+            LWWSVGObject::Rectangle(ref mut rectangle) => Some(&mut rectangle.layer.hidden),
+            LWWSVGObject::Group(ref mut group) => Some(&mut group.layer.hidden),
+            LWWSVGObject::Path(ref mut path) => Some(&mut path.layer.hidden),
+            LWWSVGObject::Ellipse(ref mut ellipse) => Some(&mut ellipse.layer.hidden),
+            LWWSVGObject::Line(ref mut line) => Some(&mut line.layer.hidden),
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.layer.hidden),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.layer.hidden),
+            LWWSVGObject::Text(ref mut text) => Some(&mut text.layer.hidden),
+            LWWSVGObject::Image(ref mut image) => Some(&mut image.layer.hidden),
+            LWWSVGObject::Instance(ref mut instance) => Some(&mut instance.layer.hidden),
+            LWWSVGObject::Gradient(_) => None
+        }
+    }
+
+    pub fn locked_mut(&mut self) -> Option<&mut LWWReg<bool>> {
+        match *self {
+            LWWSVGObject::Circle(ref mut circle) => Some(&mut circle.layer.locked),
+            LWWSVGObject::Rectangle(ref mut rectangle) => Some(&mut rectangle.layer.locked),
+            LWWSVGObject::Group(ref mut group) => Some(&mut group.layer.locked),
+            LWWSVGObject::Path(ref mut path) => Some(&mut path.layer.locked),
+            LWWSVGObject::Ellipse(ref mut ellipse) => Some(&mut ellipse.layer.locked),
+            LWWSVGObject::Line(ref mut line) => Some(&mut line.layer.locked),
+            LWWSVGObject::Polyline(ref mut polyline) => Some(&mut polyline.layer.locked),
+            LWWSVGObject::Polygon(ref mut polygon) => Some(&mut polygon.layer.locked),
+            LWWSVGObject::Text(ref mut text) => Some(&mut text.layer.locked),
+            LWWSVGObject::Image(ref mut image) => Some(&mut image.layer.locked),
+            LWWSVGObject::Instance(ref mut instance) => Some(&mut instance.layer.locked),
+            LWWSVGObject::Gradient(_) => None
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        match self {
+            LWWSVGObject::Circle(circle) => *circle.layer.locked.value(),
+            LWWSVGObject::Rectangle(rectangle) => *rectangle.layer.locked.value(),
+            LWWSVGObject::Group(group) => *group.layer.locked.value(),
+            LWWSVGObject::Path(path) => *path.layer.locked.value(),
+            LWWSVGObject::Ellipse(ellipse) => *ellipse.layer.locked.value(),
+            LWWSVGObject::Line(line) => *line.layer.locked.value(),
+            LWWSVGObject::Polyline(polyline) => *polyline.layer.locked.value(),
+            LWWSVGObject::Polygon(polygon) => *polygon.layer.locked.value(),
+            LWWSVGObject::Text(text) => *text.layer.locked.value(),
+            LWWSVGObject::Image(image) => *image.layer.locked.value(),
+            LWWSVGObject::Instance(instance) => *instance.layer.locked.value(),
+            LWWSVGObject::Gradient(_) => false
+        }
+    }
+
+    // Drops the tombstones of points, vertices and stops. Deleted
+    // characters of a text stay, as later characters are placed after them
+    // and undoing the deletion brings them back.
+    pub fn compact(&mut self) {
+        match *self {
+            LWWSVGObject::Path(ref mut path) => path.points.compact_all(),
+            LWWSVGObject::Polyline(ref mut polyline) => polyline.points.vertices.compact_all(),
+            LWWSVGObject::Polygon(ref mut polygon) => polygon.points.vertices.compact_all(),
+            LWWSVGObject::Gradient(ref mut gradient) => gradient.stops.stops.compact_all(),
+            _ => {}
+        }
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        match (self, before, after) {
+            (LWWSVGObject::Circle(c), LWWSVGObject::Circle(b), LWWSVGObject::Circle(a)) => c.revert(b, a, stamp),
+            (LWWSVGObject::Rectangle(r), LWWSVGObject::Rectangle(b), LWWSVGObject::Rectangle(a)) => r.revert(b, a, stamp),
+            (LWWSVGObject::Group(g), LWWSVGObject::Group(b), LWWSVGObject::Group(a)) => g.revert(b, a, stamp),
+            (LWWSVGObject::Path(p), LWWSVGObject::Path(b), LWWSVGObject::Path(a)) => p.revert(b, a, stamp),
+            (LWWSVGObject::Ellipse(e), LWWSVGObject::Ellipse(b), LWWSVGObject::Ellipse(a)) => e.revert(b, a, stamp),
+            (LWWSVGObject::Line(l), LWWSVGObject::Line(b), LWWSVGObject::Line(a)) => l.revert(b, a, stamp),
+            (LWWSVGObject::Polyline(p), LWWSVGObject::Polyline(b), LWWSVGObject::Polyline(a)) => p.revert(b, a, stamp),
+            (LWWSVGObject::Polygon(p), LWWSVGObject::Polygon(b), LWWSVGObject::Polygon(a)) => p.revert(b, a, stamp),
+            (LWWSVGObject::Text(t), LWWSVGObject::Text(b), LWWSVGObject::Text(a)) => t.revert(b, a, stamp),
+            (LWWSVGObject::Image(i), LWWSVGObject::Image(b), LWWSVGObject::Image(a)) => i.revert(b, a, stamp),
+            (LWWSVGObject::Instance(i), LWWSVGObject::Instance(b), LWWSVGObject::Instance(a)) => i.revert(b, a, stamp),
+            (LWWSVGObject::Gradient(g), LWWSVGObject::Gradient(b), LWWSVGObject::Gradient(a)) => g.revert(b, a, stamp),
+            _ => false
+        }
+    }
+
     pub fn value(&self) -> SVGObject {
         match *self {
             LWWSVGObject::Circle(ref circle) => SVGObject::Circle(circle.value()),
             LWWSVGObject::Rectangle(ref rectangle) => SVGObject::Rectangle(rectangle.value()),
             LWWSVGObject::Group(ref group) => SVGObject::Group(group.value()),
-            LWWSVGObject::Path(ref path) => SVGObject::Path(path.value())
+            LWWSVGObject::Path(ref path) => SVGObject::Path(path.value()),
+            LWWSVGObject::Ellipse(ref ellipse) => SVGObject::Ellipse(ellipse.value()),
+            LWWSVGObject::Line(ref line) => SVGObject::Line(line.value()),
+            LWWSVGObject::Polyline(ref polyline) => SVGObject::Polyline(polyline.value()),
+            LWWSVGObject::Polygon(ref polygon) => SVGObject::Polygon(polygon.value()),
+            LWWSVGObject::Text(ref text) => SVGObject::Text(text.value()),
+            LWWSVGObject::Image(ref image) => SVGObject::Image(image.value()),
+            LWWSVGObject::Instance(ref instance) => SVGObject::Instance(instance.value()),
+            LWWSVGObject::Gradient(ref gradient) => SVGObject::Gradient(gradient.value())
         }
     }
 }
-
-impl From<SVGObject> for LWWSVGObject {
-    fn from(value: SVGObject) -> Self {
-        match value {
-            SVGObject::Circle(circle) => LWWSVGObject::Circle(circle.into()),
-            SVGObject::Group(group) => LWWSVGObject::Group(group.into()),
-            SVGObject::Path(path) => LWWSVGObject::Path(path.into()),
-            SVGObject::Rectangle(rectangle) => LWWSVGObject::Rectangle(rectangle.into()),
-        }
-    }
-}
\ No newline at end of file
@@ -1,91 +1,496 @@
 use crate::prelude::*;
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGPathPoint {
+    pub command: LWWReg<SVGPathCommandType>,
+    pub pos: LWWReg<Vec2>,
+    pub handle1: LWWReg<Vec2>,
+    pub handle2: LWWReg<Vec2>,
+    pub index: LWWReg<FractionalIndex>,
+}
+
+impl LWWSVGPathPoint {
+    pub fn new(command: &SVGPathCommand, index: FractionalIndex, stamp: &Stamp) -> Self {
+        // Every point keeps a position and both handles whatever its type,
+        // so changing the type back and forth does not lose them.
+        let (pos, handle1, handle2) = match command {
+            SVGPathCommand::Start { pos, .. } | SVGPathCommand::Line { pos, .. } => {
+                (pos.clone(), Vec2 { x: pos.x + 20.0, y: pos.y + 20.0 }, Vec2 { x: pos.x + 20.0, y: pos.y - 20.0 })
+            },
+            SVGPathCommand::Close { .. } => {
+                (Vec2 { x: 0.0, y: 0.0 }, Vec2 { x: 20.0, y: 20.0 }, Vec2 { x: 20.0, y: -20.0 })
+            },
+            SVGPathCommand::Bezier { handle1, handle2, pos, .. } => {
+                (pos.clone(), handle1.clone(), handle2.clone())
+            },
+            SVGPathCommand::BezierQuad { handle, pos, .. } => {
+                (pos.clone(), handle.clone(), Vec2 { x: pos.x + 20.0, y: pos.y - 20.0 })
+            },
+        };
+        Self {
+            command: LWWReg::new_at(command.get_type(), stamp),
+            pos: LWWReg::new_at(pos, stamp),
+            handle1: LWWReg::new_at(handle1, stamp),
+            handle2: LWWReg::new_at(handle2, stamp),
+            index: LWWReg::new_at(index, stamp),
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        [
+            self.command.time,
+            self.pos.time,
+            self.handle1.time,
+            self.handle2.time,
+            self.index.time
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self, id: &str) -> SVGPathCommand {
+        let id = id.to_string();
+        let pos = self.pos.value().clone();
+        match self.command.value() {
+            SVGPathCommandType::START => SVGPathCommand::Start { id, pos },
+            SVGPathCommandType::LINE => SVGPathCommand::Line { id, pos },
+            SVGPathCommandType::CLOSE => SVGPathCommand::Close { id },
+            SVGPathCommandType::BEZIER => SVGPathCommand::Bezier {
+                id,
+                handle1: self.handle1.value().clone(),
+                handle2: self.handle2.value().clone(),
+                pos
+            },
+            SVGPathCommandType::BEZIER_QUAD => SVGPathCommand::BezierQuad {
+                id,
+                handle: self.handle1.value().clone(),
+                pos
+            },
+        }
+    }
+}
+
+impl Mergeable for LWWSVGPathPoint {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            command: self.command.merge(&other.command),
+            pos: self.pos.merge(&other.pos),
+            handle1: self.handle1.merge(&other.handle1),
+            handle2: self.handle2.merge(&other.handle2),
+            index: self.index.merge(&other.index),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LWWSVGPath {
     pub id: LWWReg<NodeID>,
     pub fill: LWWReg<Color>,
-    pub stroke_width: LWWReg<i32>,
+    #[serde(default)]
+    pub fill_gradient: LWWReg<Option<NodeID>>,
+    pub stroke_width: LWWReg<f64>,
     pub stroke: LWWReg<Color>,
-    pub points: LWWReg<Vec<SVGPathCommand>>,
-    pub opacity: LWWReg<f32>
+    #[serde(default)]
+    pub stroke_gradient: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub stroke_style: LWWStrokeStyle,
+    #[serde(default)]
+    pub fill_rule: LWWReg<FillRule>,
+    // Points are keyed by point id and ordered by their fractional index,
+    // so concurrent edits to different points merge independently.
+    pub points: UWMap<String, LWWSVGPathPoint>,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform
 }
 
 impl LWWSVGPath {
+    pub fn new(SVGPath {
+        id,
+        fill,
+        fill_gradient,
+        stroke_width,
+        stroke,
+        stroke_gradient,
+        stroke_style,
+        fill_rule,
+        points,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGPath, stamp: &Stamp) -> Self {
+        let mut path = Self {
+            id: LWWReg::new_at(id, stamp),
+            fill: LWWReg::new_at(fill, stamp),
+            fill_gradient: LWWReg::new_at(fill_gradient, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_gradient: LWWReg::new_at(stroke_gradient, stamp),
+            stroke_style: LWWStrokeStyle::new(stroke_style, stamp),
+            fill_rule: LWWReg::new_at(fill_rule, stamp),
+            points: UWMap::new(),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        };
+        path.set_points(points, stamp);
+        path
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.fill.time,
+            self.fill_gradient.time,
+            self.stroke_width.time,
+            self.stroke.time,
+            self.stroke_gradient.time,
+            self.stroke_style.time(),
+            self.fill_rule.time,
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter()
+            .chain(self.points.iter().map(|(_, point)| point.time()))
+            .max()
+            .unwrap_or_default()
+    }
+
     pub fn value(&self) -> SVGPath {
-        SVGPath { 
-            id: self.id.value().clone(), 
-            fill: self.fill.value().clone(), 
-            stroke_width: self.stroke_width.value().clone(), 
-            stroke: self.stroke.value().clone(), 
-            points: self.points.value().clone(), 
-            opacity: self.opacity.value().clone()
+        SVGPath {
+            id: self.id.value().clone(),
+            fill: self.fill.value().clone(),
+            fill_gradient: self.fill_gradient.value().clone(),
+            stroke_width: self.stroke_width.value().clone(),
+            stroke: self.stroke.value().clone(),
+            stroke_gradient: self.stroke_gradient.value().clone(),
+            stroke_style: self.stroke_style.value(),
+            fill_rule: *self.fill_rule.value(),
+            points: self.ordered_points()
+                .iter()
+                .map(|(point_id, point)| point.value(point_id))
+                .collect(),
+            opacity: self.opacity.value().clone(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
         }
     }
 
-    pub fn set_points(&mut self, points: Vec<SVGPathCommand>) {
-        self.points.set(points);
+    fn ordered_points(&self) -> Vec<(String, LWWSVGPathPoint)> {
+        let mut points = self.points.value().into_iter().collect::<Vec<_>>();
+        // Concurrent inserts may share an index, the point id breaks the tie.
+        points.sort_by(|(id_a, a), (id_b, b)| {
+            (a.index.value(), id_a).cmp(&(b.index.value(), id_b))
+        });
+        points
+    }
+
+    pub fn has_point(&self, point_id: &str) -> bool {
+        self.points.get(&point_id.to_string()).is_some()
     }
-}
 
-impl From<SVGPath> for LWWSVGPath {
-    fn from(SVGPath { 
-        id, 
-        fill, 
-        stroke_width, 
-        stroke, 
-        points, 
-        opacity 
-    }: SVGPath) -> Self {
-        Self { 
-            id: LWWReg::new(id), 
-            fill: LWWReg::new(fill), 
-            stroke_width: LWWReg::new(stroke_width), 
-            stroke: LWWReg::new(stroke), 
-            points: LWWReg::new(points), 
-            opacity: LWWReg::new(opacity) 
+    pub fn set_points(&mut self, points: Vec<SVGPathCommand>, stamp: &Stamp) {
+        for (point_id, _) in self.points.value() {
+            self.points.remove(stamp.replica_id.clone(), point_id);
         }
+        let mut index = FractionalIndex::default();
+        for point in points {
+            let item = LWWSVGPathPoint::new(&point, index.clone(), stamp);
+            self.points.insert(stamp.replica_id.clone(), point.get_id().to_string(), item);
+            index = FractionalIndex::new_after(&index);
+        }
+    }
+
+    pub fn insert_point_after(
+        &mut self, 
+        after_point_id: Option<&str>, 
+        point: SVGPathCommand, 
+        stamp: &Stamp
+    ) -> Result<(), DocError> {
+        if self.has_point(point.get_id()) { return Err(DocError::DuplicateId(point.get_id().to_string())); }
+        let points = self.ordered_points();
+        let position = match after_point_id {
+            Some(after_point_id) => {
+                let Some(i) = points.iter().position(|(id, _)| id == after_point_id) else {
+                    return Err(DocError::NotFound(after_point_id.to_string()));
+                };
+                i + 1
+            },
+            None => 0,
+        };
+        let lower = position.checked_sub(1).and_then(|i| points.get(i)).map(|(_, p)| p.index.value());
+        let upper = points.get(position).map(|(_, p)| p.index.value());
+        let index = index_between(lower, upper);
+        let item = LWWSVGPathPoint::new(&point, index, stamp);
+        self.points.insert(stamp.replica_id.clone(), point.get_id().to_string(), item);
+        Ok(())
+    }
+
+    fn edit_point<F>(&mut self, point_id: &str, stamp: &Stamp, f: F) -> Result<(), DocError>
+        where F: FnOnce(&mut LWWSVGPathPoint) -> Result<(), DocError>
+    {
+        let point_id = point_id.to_string();
+        let Some(point) = self.points.get(&point_id) else { return Err(DocError::NotFound(point_id)); };
+        let mut point = point.clone();
+        f(&mut point)?;
+        self.points.insert(stamp.replica_id.clone(), point_id, point);
+        Ok(())
+    }
+
+    pub fn edit_point_type(&mut self, point_id: &str, command_type: SVGPathCommandType, stamp: &Stamp) -> Result<(), DocError> {
+        self.edit_point(point_id, stamp, |point| {
+            point.command.set(command_type, stamp);
+            Ok(())
+        })
+    }
+
+    pub fn edit_point_pos(&mut self, point_id: &str, new_pos: Vec2, stamp: &Stamp) -> Result<(), DocError> {
+        self.edit_point(point_id, stamp, |point| {
+            if point.command.value() == &SVGPathCommandType::CLOSE {
+                return Err(DocError::WrongKind { id: point_id.to_string(), expected: "positioned point" });
+            }
+            point.pos.set(new_pos, stamp);
+            Ok(())
+        })
+    }
+
+    pub fn edit_point_handle1(&mut self, point_id: &str, new_handle1: Vec2, stamp: &Stamp) -> Result<(), DocError> {
+        self.edit_point(point_id, stamp, |point| {
+            match point.command.value() {
+                SVGPathCommandType::BEZIER | SVGPathCommandType::BEZIER_QUAD => {
+                    point.handle1.set(new_handle1, stamp);
+                    Ok(())
+                },
+                _ => Err(DocError::WrongKind { id: point_id.to_string(), expected: "BEZIER or BEZIER_QUAD point" })
+            }
+        })
+    }
+
+    pub fn edit_point_handle2(&mut self, point_id: &str, new_handle2: Vec2, stamp: &Stamp) -> Result<(), DocError> {
+        self.edit_point(point_id, stamp, |point| {
+            if point.command.value() != &SVGPathCommandType::BEZIER {
+                return Err(DocError::WrongKind { id: point_id.to_string(), expected: "BEZIER point" });
+            }
+            point.handle2.set(new_handle2, stamp);
+            Ok(())
+        })
+    }
+
+    pub fn remove_point(&mut self, point_id: &str, stamp: &Stamp) -> Result<(), DocError> {
+        if !self.has_point(point_id) { return Err(DocError::NotFound(point_id.to_string())); }
+        self.points.remove(stamp.replica_id.clone(), point_id.to_string());
+        Ok(())
+    }
+
+    // Points in path order grouped by subpath, see `SVGSubpath`.
+    fn ordered_subpaths(&self) -> Vec<Vec<(String, LWWSVGPathPoint)>> {
+        let points = self.ordered_points();
+        subpath_ranges(&points, |(_, point)| point.command.value() == &SVGPathCommandType::START)
+            .into_iter()
+            .map(|range| points[range].to_vec())
+            .collect()
+    }
+
+    fn subpath_position(subpaths: &[Vec<(String, LWWSVGPathPoint)>], subpath_id: &str) -> Result<usize, DocError> {
+        subpaths.iter()
+            .position(|subpath| subpath[0].0 == subpath_id)
+            .ok_or_else(|| DocError::NotFound(subpath_id.to_string()))
+    }
+
+    // Moves the subpath's points so it becomes the `index`th subpath, which
+    // changes the paint order of overlapping subpaths.
+    pub fn move_subpath(&mut self, subpath_id: &str, index: usize, stamp: &Stamp) -> Result<(), DocError> {
+        let mut subpaths = self.ordered_subpaths();
+        let moved = subpaths.remove(Self::subpath_position(&subpaths, subpath_id)?);
+        let index = index.min(subpaths.len());
+        let mut lower = index.checked_sub(1)
+            .and_then(|i| subpaths[i].last())
+            .map(|(_, point)| point.index.value().clone());
+        let upper = subpaths.get(index)
+            .and_then(|subpath| subpath.first())
+            .map(|(_, point)| point.index.value().clone());
+        for (point_id, mut point) in moved {
+            let index = index_between(lower.as_ref(), upper.as_ref());
+            point.index.set(index.clone(), stamp);
+            self.points.insert(stamp.replica_id.clone(), point_id, point);
+            lower = Some(index);
+        }
+        Ok(())
+    }
+
+    // Runs the subpath the other way round. The points keep their ids and
+    // places, each one takes over the geometry of its mirror image. CLOSE
+    // points stay where they are.
+    pub fn reverse_subpath(&mut self, subpath_id: &str, stamp: &Stamp) -> Result<(), DocError> {
+        let subpaths = self.ordered_subpaths();
+        let subpath = &subpaths[Self::subpath_position(&subpaths, subpath_id)?];
+        let drawn = subpath.iter()
+            .filter(|(_, point)| point.command.value() != &SVGPathCommandType::CLOSE)
+            .collect::<Vec<_>>();
+        let (Some((_, first)), Some((_, last))) = (drawn.first(), drawn.last()) else { return Ok(()); };
+        let mut reversed = vec![(*first.command.value(), last.pos.value().clone(), None)];
+        // The segment ending at point k now runs from point k back to point
+        // k - 1, so its handles swap.
+        for k in (1..drawn.len()).rev() {
+            let (_, segment) = drawn[k];
+            let (_, previous) = drawn[k - 1];
+            let handles = match segment.command.value() {
+                SVGPathCommandType::BEZIER => Some((segment.handle2.value().clone(), segment.handle1.value().clone())),
+                SVGPathCommandType::BEZIER_QUAD => Some((segment.handle1.value().clone(), segment.handle2.value().clone())),
+                _ => None,
+            };
+            reversed.push((*segment.command.value(), previous.pos.value().clone(), handles));
+        }
+        let point_ids = drawn.iter().map(|(point_id, _)| point_id.clone()).collect::<Vec<_>>();
+        for (point_id, (command, pos, handles)) in point_ids.into_iter().zip(reversed) {
+            self.edit_point(&point_id, stamp, |point| {
+                point.command.set(command, stamp);
+                point.pos.set(pos, stamp);
+                if let Some((handle1, handle2)) = handles {
+                    point.handle1.set(handle1, stamp);
+                    point.handle2.set(handle2, stamp);
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_subpath(&mut self, subpath_id: &str, stamp: &Stamp) -> Result<(), DocError> {
+        let subpaths = self.ordered_subpaths();
+        for (point_id, _) in subpaths[Self::subpath_position(&subpaths, subpath_id)?].iter() {
+            self.points.remove(stamp.replica_id.clone(), point_id.clone());
+        }
+        Ok(())
     }
 }
 
 impl Mergeable for LWWSVGPath {
     fn merge(&self, other: &Self) -> Self {
-        Self { 
-            id: self.id.merge(&other.id), 
-            fill: self.fill.merge(&other.fill), 
-            stroke_width: self.stroke_width.merge(&other.stroke_width), 
-            stroke: self.stroke.merge(&other.stroke), 
-            points: self.points.merge(&other.points), 
-            opacity: self.opacity.merge(&other.opacity) 
+        Self {
+            id: self.id.merge(&other.id),
+            fill: self.fill.merge(&other.fill),
+            fill_gradient: self.fill_gradient.merge(&other.fill_gradient),
+            stroke_width: self.stroke_width.merge(&other.stroke_width),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_gradient: self.stroke_gradient.merge(&other.stroke_gradient),
+            stroke_style: self.stroke_style.merge(&other.stroke_style),
+            fill_rule: self.fill_rule.merge(&other.fill_rule),
+            points: UWMap::merge(&self.points, &other.points),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
         }
     }
 }
 
-impl partially::Partial for LWWSVGPath {
-    type Item = PartialSVGPath;
-
-    fn apply_some(&mut self, partial: Self::Item) -> bool {
+impl LWWSVGPath {
+    pub fn apply_some(&mut self, partial: PartialSVGPath, stamp: &Stamp) -> bool {
         let will_apply_some = partial.fill.is_some()
-            || partial.stroke_width.is_some() 
+            || partial.stroke_width.is_some()
             || partial.stroke.is_some()
             || partial.opacity.is_some()
-            || partial.points.is_some();
+            || partial.points.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty()
+            || partial.fill_rule.is_some();
         if let Some(fill) = partial.fill {
-            self.fill.set(fill);
+            self.fill.set(fill, stamp);
         }
         if let Some(stroke_width) = partial.stroke_width {
-            self.stroke_width.set(stroke_width);
+            self.stroke_width.set(stroke_width, stamp);
         }
         if let Some(stroke) = partial.stroke {
-            self.stroke.set(stroke);
+            self.stroke.set(stroke, stamp);
         }
         if let Some(opacity) = partial.opacity {
-            self.opacity.set(opacity);
+            self.opacity.set(opacity, stamp);
         }
         if let Some(mut points) = partial.points {
-            self.points.set(points.drain(..)
-                .map(|it| SVGPathCommand::from_partial(it))
-                .collect());
+            self.set_points(points.drain(..)
+                .map(SVGPathCommand::from_partial)
+                .collect(), stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient.set(fill_gradient.into(), stamp);
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient.set(stroke_gradient.into(), stamp);
+        }
+        self.stroke_style.apply_some(partial.stroke_style, stamp);
+        if let Some(fill_rule) = partial.fill_rule {
+            self.fill_rule.set(fill_rule, stamp);
         }
         will_apply_some
     }
-}
\ No newline at end of file
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        let mut reverted = [
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.fill_gradient.revert(&before.fill_gradient, &after.fill_gradient, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_gradient.revert(&before.stroke_gradient, &after.stroke_gradient, stamp),
+            self.stroke_style.revert(&before.stroke_style, &after.stroke_style, stamp),
+            self.fill_rule.revert(&before.fill_rule, &after.fill_rule, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted);
+        let before_points = before.points.value();
+        let after_points = after.points.value();
+        let point_ids = before_points.keys()
+            .chain(after_points.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        for point_id in point_ids {
+            let current = self.points.get(&point_id).cloned();
+            match (before_points.get(&point_id), after_points.get(&point_id), current) {
+                // Added by the edit.
+                (None, Some(_), Some(_)) => {
+                    self.points.remove(stamp.replica_id.clone(), point_id);
+                },
+                // Removed by the edit and still gone.
+                (Some(before), None, None) => {
+                    self.points.insert(stamp.replica_id.clone(), point_id, before.clone());
+                },
+                (Some(before), Some(after), Some(mut current)) => {
+                    let point_reverted = [
+                        current.command.revert(&before.command, &after.command, stamp),
+                        current.pos.revert(&before.pos, &after.pos, stamp),
+                        current.handle1.revert(&before.handle1, &after.handle1, stamp),
+                        current.handle2.revert(&before.handle2, &after.handle2, stamp),
+                        current.index.revert(&before.index, &after.index, stamp)
+                    ].into_iter().any(|reverted| reverted);
+                    if !point_reverted { continue; }
+                    self.points.insert(stamp.replica_id.clone(), point_id, current);
+                },
+                _ => continue,
+            }
+            reverted = true;
+        }
+        reverted
+    }
+}
@@ -0,0 +1,332 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGVertex {
+    pub pos: LWWReg<Vec2>,
+    pub index: LWWReg<FractionalIndex>,
+}
+
+impl LWWSVGVertex {
+    pub fn new(pos: Vec2, index: FractionalIndex, stamp: &Stamp) -> Self {
+        Self {
+            pos: LWWReg::new_at(pos, stamp),
+            index: LWWReg::new_at(index, stamp),
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        self.pos.time.max(self.index.time)
+    }
+}
+
+impl Mergeable for LWWSVGVertex {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            pos: self.pos.merge(&other.pos),
+            index: self.index.merge(&other.index),
+        }
+    }
+}
+
+// Vertices of a polyline or polygon, keyed by vertex id and ordered by their
+// fractional index like path points, so concurrent edits to different
+// vertices merge independently.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGVertices {
+    pub vertices: UWMap<String, LWWSVGVertex>,
+}
+
+impl LWWSVGVertices {
+    pub fn new(vertices: Vec<SVGVertex>, stamp: &Stamp) -> Self {
+        let mut res = Self { vertices: UWMap::new() };
+        res.set(vertices, stamp);
+        res
+    }
+
+    pub fn time(&self) -> Option<HLCTimestamp> {
+        self.vertices.iter().map(|(_, vertex)| vertex.time()).max()
+    }
+
+    fn ordered(&self) -> Vec<(String, LWWSVGVertex)> {
+        let mut vertices = self.vertices.value().into_iter().collect::<Vec<_>>();
+        vertices.sort_by(|(id_a, a), (id_b, b)| {
+            (a.index.value(), id_a).cmp(&(b.index.value(), id_b))
+        });
+        vertices
+    }
+
+    pub fn value(&self) -> Vec<SVGVertex> {
+        self.ordered()
+            .into_iter()
+            .map(|(id, vertex)| SVGVertex { id, pos: vertex.pos.value().clone() })
+            .collect()
+    }
+
+    pub fn has(&self, vertex_id: &str) -> bool {
+        self.vertices.get(&vertex_id.to_string()).is_some()
+    }
+
+    pub fn set(&mut self, vertices: Vec<SVGVertex>, stamp: &Stamp) {
+        for (vertex_id, _) in self.vertices.value() {
+            self.vertices.remove(stamp.replica_id.clone(), vertex_id);
+        }
+        let mut index = FractionalIndex::default();
+        for SVGVertex { id, pos } in vertices {
+            self.vertices.insert(stamp.replica_id.clone(), id, LWWSVGVertex::new(pos, index.clone(), stamp));
+            index = FractionalIndex::new_after(&index);
+        }
+    }
+
+    pub fn insert_after(
+        &mut self,
+        after_vertex_id: Option<&str>,
+        vertex: SVGVertex,
+        stamp: &Stamp
+    ) -> Result<(), DocError> {
+        if self.has(&vertex.id) { return Err(DocError::DuplicateId(vertex.id)); }
+        let vertices = self.ordered();
+        let position = match after_vertex_id {
+            Some(after_vertex_id) => {
+                let Some(i) = vertices.iter().position(|(id, _)| id == after_vertex_id) else {
+                    return Err(DocError::NotFound(after_vertex_id.to_string()));
+                };
+                i + 1
+            },
+            None => 0,
+        };
+        let lower = position.checked_sub(1).and_then(|i| vertices.get(i)).map(|(_, v)| v.index.value());
+        let upper = vertices.get(position).map(|(_, v)| v.index.value());
+        let index = index_between(lower, upper);
+        self.vertices.insert(stamp.replica_id.clone(), vertex.id, LWWSVGVertex::new(vertex.pos, index, stamp));
+        Ok(())
+    }
+
+    pub fn edit_pos(&mut self, vertex_id: &str, new_pos: Vec2, stamp: &Stamp) -> Result<(), DocError> {
+        let vertex_id = vertex_id.to_string();
+        let Some(vertex) = self.vertices.get(&vertex_id) else { return Err(DocError::NotFound(vertex_id)); };
+        let mut vertex = vertex.clone();
+        vertex.pos.set(new_pos, stamp);
+        self.vertices.insert(stamp.replica_id.clone(), vertex_id, vertex);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, vertex_id: &str, stamp: &Stamp) -> Result<(), DocError> {
+        if !self.has(vertex_id) { return Err(DocError::NotFound(vertex_id.to_string())); }
+        self.vertices.remove(stamp.replica_id.clone(), vertex_id.to_string());
+        Ok(())
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        let before_vertices = before.vertices.value();
+        let after_vertices = after.vertices.value();
+        let vertex_ids = before_vertices.keys()
+            .chain(after_vertices.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        let mut reverted = false;
+        for vertex_id in vertex_ids {
+            let current = self.vertices.get(&vertex_id).cloned();
+            match (before_vertices.get(&vertex_id), after_vertices.get(&vertex_id), current) {
+                // Added by the edit.
+                (None, Some(_), Some(_)) => {
+                    self.vertices.remove(stamp.replica_id.clone(), vertex_id);
+                },
+                // Removed by the edit and still gone.
+                (Some(before), None, None) => {
+                    self.vertices.insert(stamp.replica_id.clone(), vertex_id, before.clone());
+                },
+                (Some(before), Some(after), Some(mut current)) => {
+                    let vertex_reverted = [
+                        current.pos.revert(&before.pos, &after.pos, stamp),
+                        current.index.revert(&before.index, &after.index, stamp)
+                    ].into_iter().any(|reverted| reverted);
+                    if !vertex_reverted { continue; }
+                    self.vertices.insert(stamp.replica_id.clone(), vertex_id, current);
+                },
+                _ => continue,
+            }
+            reverted = true;
+        }
+        reverted
+    }
+}
+
+impl Mergeable for LWWSVGVertices {
+    fn merge(&self, other: &Self) -> Self {
+        Self { vertices: UWMap::merge(&self.vertices, &other.vertices) }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGPolyline {
+    pub id: LWWReg<NodeID>,
+    pub points: LWWSVGVertices,
+    pub fill: LWWReg<Color>,
+    #[serde(default)]
+    pub fill_gradient: LWWReg<Option<NodeID>>,
+    pub stroke_width: LWWReg<f64>,
+    pub stroke: LWWReg<Color>,
+    #[serde(default)]
+    pub stroke_gradient: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub stroke_style: LWWStrokeStyle,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGPolyline {
+    pub fn new(SVGPolyline {
+        id,
+        points,
+        fill,
+        fill_gradient,
+        stroke_width,
+        stroke,
+        stroke_gradient,
+        stroke_style,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGPolyline, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            points: LWWSVGVertices::new(points, stamp),
+            fill: LWWReg::new_at(fill, stamp),
+            fill_gradient: LWWReg::new_at(fill_gradient, stamp),
+            stroke_width: LWWReg::new_at(stroke_width, stamp),
+            stroke: LWWReg::new_at(stroke, stamp),
+            stroke_gradient: LWWReg::new_at(stroke_gradient, stamp),
+            stroke_style: LWWStrokeStyle::new(stroke_style, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.fill.time,
+            self.fill_gradient.time,
+            self.stroke_width.time,
+            self.stroke.time,
+            self.stroke_gradient.time,
+            self.stroke_style.time(),
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter()
+            .chain(self.points.time())
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGPolyline {
+        SVGPolyline {
+            id: self.id.value().clone(),
+            points: self.points.value(),
+            fill: self.fill.value().clone(),
+            fill_gradient: self.fill_gradient.value().clone(),
+            stroke_width: *self.stroke_width.value(),
+            stroke: self.stroke.value().clone(),
+            stroke_gradient: self.stroke_gradient.value().clone(),
+            stroke_style: self.stroke_style.value(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGPolyline {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            points: self.points.merge(&other.points),
+            fill: self.fill.merge(&other.fill),
+            fill_gradient: self.fill_gradient.merge(&other.fill_gradient),
+            stroke_width: self.stroke_width.merge(&other.stroke_width),
+            stroke: self.stroke.merge(&other.stroke),
+            stroke_gradient: self.stroke_gradient.merge(&other.stroke_gradient),
+            stroke_style: self.stroke_style.merge(&other.stroke_style),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGPolyline {
+    pub fn apply_some(&mut self, partial: PartialSVGPolyline, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.points.is_some()
+            || partial.fill.is_some()
+            || partial.stroke_width.is_some()
+            || partial.stroke.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
+        if let Some(points) = partial.points {
+            self.points.set(SVGVertex::from_positions(points), stamp);
+        }
+        if let Some(fill) = partial.fill {
+            self.fill.set(fill, stamp);
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width.set(stroke_width, stamp);
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke.set(stroke, stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient.set(fill_gradient.into(), stamp);
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient.set(stroke_gradient.into(), stamp);
+        }
+        self.stroke_style.apply_some(partial.stroke_style, stamp);
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.points.revert(&before.points, &after.points, stamp),
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.fill_gradient.revert(&before.fill_gradient, &after.fill_gradient, stamp),
+            self.stroke_width.revert(&before.stroke_width, &after.stroke_width, stamp),
+            self.stroke.revert(&before.stroke, &after.stroke, stamp),
+            self.stroke_gradient.revert(&before.stroke_gradient, &after.stroke_gradient, stamp),
+            self.stroke_style.revert(&before.stroke_style, &after.stroke_style, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
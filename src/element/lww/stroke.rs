@@ -0,0 +1,98 @@
+use crate::prelude::*;
+
+// One register per property, see `StrokeStyle`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWStrokeStyle {
+    pub stroke_dasharray: LWWReg<Vec<f64>>,
+    pub stroke_dashoffset: LWWReg<f64>,
+    pub stroke_linecap: LWWReg<StrokeLinecap>,
+    pub stroke_linejoin: LWWReg<StrokeLinejoin>,
+    pub stroke_miterlimit: LWWReg<f64>,
+}
+
+// The SVG initial values, stamped before any real write.
+impl Default for LWWStrokeStyle {
+    fn default() -> Self {
+        LWWStrokeStyle::new(StrokeStyle::default(), &Stamp::default())
+    }
+}
+
+impl LWWStrokeStyle {
+    pub fn new(StrokeStyle {
+        stroke_dasharray,
+        stroke_dashoffset,
+        stroke_linecap,
+        stroke_linejoin,
+        stroke_miterlimit
+    }: StrokeStyle, stamp: &Stamp) -> Self {
+        Self {
+            stroke_dasharray: LWWReg::new_at(stroke_dasharray, stamp),
+            stroke_dashoffset: LWWReg::new_at(stroke_dashoffset, stamp),
+            stroke_linecap: LWWReg::new_at(stroke_linecap, stamp),
+            stroke_linejoin: LWWReg::new_at(stroke_linejoin, stamp),
+            stroke_miterlimit: LWWReg::new_at(stroke_miterlimit, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        [
+            self.stroke_dasharray.time,
+            self.stroke_dashoffset.time,
+            self.stroke_linecap.time,
+            self.stroke_linejoin.time,
+            self.stroke_miterlimit.time
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> StrokeStyle {
+        StrokeStyle {
+            stroke_dasharray: self.stroke_dasharray.value().clone(),
+            stroke_dashoffset: *self.stroke_dashoffset.value(),
+            stroke_linecap: *self.stroke_linecap.value(),
+            stroke_linejoin: *self.stroke_linejoin.value(),
+            stroke_miterlimit: *self.stroke_miterlimit.value()
+        }
+    }
+
+    pub fn apply_some(&mut self, partial: PartialStrokeStyle, stamp: &Stamp) -> bool {
+        let will_apply_some = !partial.is_empty();
+        if let Some(stroke_dasharray) = partial.stroke_dasharray {
+            self.stroke_dasharray.set(stroke_dasharray, stamp);
+        }
+        if let Some(stroke_dashoffset) = partial.stroke_dashoffset {
+            self.stroke_dashoffset.set(stroke_dashoffset, stamp);
+        }
+        if let Some(stroke_linecap) = partial.stroke_linecap {
+            self.stroke_linecap.set(stroke_linecap, stamp);
+        }
+        if let Some(stroke_linejoin) = partial.stroke_linejoin {
+            self.stroke_linejoin.set(stroke_linejoin, stamp);
+        }
+        if let Some(stroke_miterlimit) = partial.stroke_miterlimit {
+            self.stroke_miterlimit.set(stroke_miterlimit, stamp);
+        }
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.stroke_dasharray.revert(&before.stroke_dasharray, &after.stroke_dasharray, stamp),
+            self.stroke_dashoffset.revert(&before.stroke_dashoffset, &after.stroke_dashoffset, stamp),
+            self.stroke_linecap.revert(&before.stroke_linecap, &after.stroke_linecap, stamp),
+            self.stroke_linejoin.revert(&before.stroke_linejoin, &after.stroke_linejoin, stamp),
+            self.stroke_miterlimit.revert(&before.stroke_miterlimit, &after.stroke_miterlimit, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
+
+impl Mergeable for LWWStrokeStyle {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            stroke_dasharray: self.stroke_dasharray.merge(&other.stroke_dasharray),
+            stroke_dashoffset: self.stroke_dashoffset.merge(&other.stroke_dashoffset),
+            stroke_linecap: self.stroke_linecap.merge(&other.stroke_linecap),
+            stroke_linejoin: self.stroke_linejoin.merge(&other.stroke_linejoin),
+            stroke_miterlimit: self.stroke_miterlimit.merge(&other.stroke_miterlimit)
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWSVGText {
+    pub id: LWWReg<NodeID>,
+    pub pos: LWWReg<Vec2>,
+    pub content: TextSequence,
+    pub font_family: LWWReg<String>,
+    pub font_size: LWWReg<f64>,
+    pub font_weight: LWWReg<i32>,
+    pub align: LWWReg<TextAlign>,
+    pub fill: LWWReg<Color>,
+    #[serde(default)]
+    pub fill_gradient: LWWReg<Option<NodeID>>,
+    pub opacity: LWWReg<f32>,
+    #[serde(default)]
+    pub clip_path: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub mask: LWWReg<Option<NodeID>>,
+    #[serde(default)]
+    pub layer: LWWLayer,
+    #[serde(default)]
+    pub transform: LWWTransform,
+}
+
+impl LWWSVGText {
+    pub fn new(SVGText {
+        id,
+        pos,
+        content,
+        font_family,
+        font_size,
+        font_weight,
+        align,
+        fill,
+        fill_gradient,
+        opacity,
+        clip_path,
+        mask,
+        layer,
+        transform
+    }: SVGText, stamp: &Stamp) -> Self {
+        Self {
+            id: LWWReg::new_at(id, stamp),
+            pos: LWWReg::new_at(pos, stamp),
+            content: TextSequence::new(&content, stamp),
+            font_family: LWWReg::new_at(font_family, stamp),
+            font_size: LWWReg::new_at(font_size, stamp),
+            font_weight: LWWReg::new_at(font_weight, stamp),
+            align: LWWReg::new_at(align, stamp),
+            fill: LWWReg::new_at(fill, stamp),
+            fill_gradient: LWWReg::new_at(fill_gradient, stamp),
+            opacity: LWWReg::new_at(opacity, stamp),
+            clip_path: LWWReg::new_at(clip_path, stamp),
+            mask: LWWReg::new_at(mask, stamp),
+            layer: LWWLayer::new(layer, stamp),
+            transform: LWWTransform::new(transform, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        // Latest write to any of the fields.
+        [
+            self.id.time,
+            self.pos.time,
+            self.content.time().unwrap_or_default(),
+            self.font_family.time,
+            self.font_size.time,
+            self.font_weight.time,
+            self.align.time,
+            self.fill.time,
+            self.fill_gradient.time,
+            self.opacity.time,
+            self.clip_path.time,
+            self.mask.time,
+            self.layer.time(),
+            self.transform.time()
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> SVGText {
+        SVGText {
+            id: self.id.value().clone(),
+            pos: self.pos.value().clone(),
+            content: self.content.value(),
+            font_family: self.font_family.value().clone(),
+            font_size: *self.font_size.value(),
+            font_weight: *self.font_weight.value(),
+            align: *self.align.value(),
+            fill: self.fill.value().clone(),
+            fill_gradient: self.fill_gradient.value().clone(),
+            opacity: *self.opacity.value(),
+            clip_path: self.clip_path.value().clone(),
+            mask: self.mask.value().clone(),
+            layer: self.layer.value(),
+            transform: self.transform.value()
+        }
+    }
+}
+
+impl Mergeable for LWWSVGText {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.merge(&other.id),
+            pos: self.pos.merge(&other.pos),
+            content: self.content.merge(&other.content),
+            font_family: self.font_family.merge(&other.font_family),
+            font_size: self.font_size.merge(&other.font_size),
+            font_weight: self.font_weight.merge(&other.font_weight),
+            align: self.align.merge(&other.align),
+            fill: self.fill.merge(&other.fill),
+            fill_gradient: self.fill_gradient.merge(&other.fill_gradient),
+            opacity: self.opacity.merge(&other.opacity),
+            clip_path: self.clip_path.merge(&other.clip_path),
+            mask: self.mask.merge(&other.mask),
+            layer: self.layer.merge(&other.layer),
+            transform: self.transform.merge(&other.transform)
+        }
+    }
+}
+
+impl LWWSVGText {
+    pub fn apply_some(&mut self, partial: PartialSVGText, stamp: &Stamp) -> bool {
+        let will_apply_some = partial.pos.is_some()
+            || partial.content.is_some()
+            || partial.font_family.is_some()
+            || partial.font_size.is_some()
+            || partial.font_weight.is_some()
+            || partial.align.is_some()
+            || partial.fill.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some();
+        if let Some(pos) = partial.pos {
+            self.pos.set(pos, stamp);
+        }
+        if let Some(content) = partial.content {
+            self.content.set(&content, stamp);
+        }
+        if let Some(font_family) = partial.font_family {
+            self.font_family.set(font_family, stamp);
+        }
+        if let Some(font_size) = partial.font_size {
+            self.font_size.set(font_size, stamp);
+        }
+        if let Some(font_weight) = partial.font_weight {
+            self.font_weight.set(font_weight, stamp);
+        }
+        if let Some(align) = partial.align {
+            self.align.set(align, stamp);
+        }
+        if let Some(fill) = partial.fill {
+            self.fill.set(fill, stamp);
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity.set(opacity, stamp);
+        }
+        if let Some(transform) = partial.transform {
+            self.transform.set(transform, stamp);
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient.set(fill_gradient.into(), stamp);
+        }
+        will_apply_some
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.pos.revert(&before.pos, &after.pos, stamp),
+            self.content.revert(&before.content, &after.content, stamp),
+            self.font_family.revert(&before.font_family, &after.font_family, stamp),
+            self.font_size.revert(&before.font_size, &after.font_size, stamp),
+            self.font_weight.revert(&before.font_weight, &after.font_weight, stamp),
+            self.align.revert(&before.align, &after.align, stamp),
+            self.fill.revert(&before.fill, &after.fill, stamp),
+            self.fill_gradient.revert(&before.fill_gradient, &after.fill_gradient, stamp),
+            self.opacity.revert(&before.opacity, &after.opacity, stamp),
+            self.clip_path.revert(&before.clip_path, &after.clip_path, stamp),
+            self.mask.revert(&before.mask, &after.mask, stamp),
+            self.layer.revert(&before.layer, &after.layer, stamp),
+            self.transform.revert(&before.transform, &after.transform, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
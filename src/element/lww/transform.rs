@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+// One register per component, see `Transform`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LWWTransform {
+    pub translate_x: LWWReg<f64>,
+    pub translate_y: LWWReg<f64>,
+    pub rotate: LWWReg<f64>,
+    pub skew_x: LWWReg<f64>,
+    pub skew_y: LWWReg<f64>,
+    pub scale_x: LWWReg<f64>,
+    pub scale_y: LWWReg<f64>,
+}
+
+// Objects saved before transforms existed load with the identity, stamped
+// before any real write.
+impl Default for LWWTransform {
+    fn default() -> Self {
+        LWWTransform::new(Transform::identity(), &Stamp::default())
+    }
+}
+
+impl LWWTransform {
+    pub fn new(Transform {
+        translate_x,
+        translate_y,
+        rotate,
+        skew_x,
+        skew_y,
+        scale_x,
+        scale_y
+    }: Transform, stamp: &Stamp) -> Self {
+        Self {
+            translate_x: LWWReg::new_at(translate_x, stamp),
+            translate_y: LWWReg::new_at(translate_y, stamp),
+            rotate: LWWReg::new_at(rotate, stamp),
+            skew_x: LWWReg::new_at(skew_x, stamp),
+            skew_y: LWWReg::new_at(skew_y, stamp),
+            scale_x: LWWReg::new_at(scale_x, stamp),
+            scale_y: LWWReg::new_at(scale_y, stamp)
+        }
+    }
+
+    pub fn time(&self) -> HLCTimestamp {
+        [
+            self.translate_x.time,
+            self.translate_y.time,
+            self.rotate.time,
+            self.skew_x.time,
+            self.skew_y.time,
+            self.scale_x.time,
+            self.scale_y.time
+        ].into_iter().max().unwrap_or_default()
+    }
+
+    pub fn value(&self) -> Transform {
+        Transform {
+            translate_x: *self.translate_x.value(),
+            translate_y: *self.translate_y.value(),
+            rotate: *self.rotate.value(),
+            skew_x: *self.skew_x.value(),
+            skew_y: *self.skew_y.value(),
+            scale_x: *self.scale_x.value(),
+            scale_y: *self.scale_y.value()
+        }
+    }
+
+    // Only components that change are written, so an edit that rotates does
+    // not override a concurrent scale.
+    pub fn set(&mut self, transform: Transform, stamp: &Stamp) {
+        let components = [
+            (&mut self.translate_x, transform.translate_x),
+            (&mut self.translate_y, transform.translate_y),
+            (&mut self.rotate, transform.rotate),
+            (&mut self.skew_x, transform.skew_x),
+            (&mut self.skew_y, transform.skew_y),
+            (&mut self.scale_x, transform.scale_x),
+            (&mut self.scale_y, transform.scale_y)
+        ];
+        for (reg, value) in components {
+            if *reg.value() != value {
+                reg.set(value, stamp);
+            }
+        }
+    }
+
+    pub fn revert(&mut self, before: &Self, after: &Self, stamp: &Stamp) -> bool {
+        [
+            self.translate_x.revert(&before.translate_x, &after.translate_x, stamp),
+            self.translate_y.revert(&before.translate_y, &after.translate_y, stamp),
+            self.rotate.revert(&before.rotate, &after.rotate, stamp),
+            self.skew_x.revert(&before.skew_x, &after.skew_x, stamp),
+            self.skew_y.revert(&before.skew_y, &after.skew_y, stamp),
+            self.scale_x.revert(&before.scale_x, &after.scale_x, stamp),
+            self.scale_y.revert(&before.scale_y, &after.scale_y, stamp)
+        ].into_iter().any(|reverted| reverted)
+    }
+}
+
+impl Mergeable for LWWTransform {
+    fn merge(&self, other: &Self) -> Self {
+        Self {
+            translate_x: self.translate_x.merge(&other.translate_x),
+            translate_y: self.translate_y.merge(&other.translate_y),
+            rotate: self.rotate.merge(&other.rotate),
+            skew_x: self.skew_x.merge(&other.skew_x),
+            skew_y: self.skew_y.merge(&other.skew_y),
+            scale_x: self.scale_x.merge(&other.scale_x),
+            scale_y: self.scale_y.merge(&other.scale_y)
+        }
+    }
+}
@@ -1,7 +1,20 @@
 pub mod circle;
+pub mod ellipse;
+pub mod line;
+pub mod polyline;
+pub mod polygon;
 pub mod group;
+pub mod import;
 pub mod path;
 pub mod rectangle;
+pub mod text;
+pub mod image;
+pub mod transform;
+pub mod gradient;
+pub mod instance;
+pub mod stroke;
+pub mod layer;
+pub mod clip;
 pub mod lww;
 use crate::prelude::*;
 
@@ -16,7 +29,24 @@ pub enum SVGObject {
     #[serde(rename = "PATH")]
     Path(SVGPath),
     #[serde(rename = "GROUP")]
-    Group(SVGGroup)
+    Group(SVGGroup),
+    #[serde(rename = "ELLIPSE")]
+    Ellipse(SVGEllipse),
+    #[serde(rename = "LINE")]
+    Line(SVGLine),
+    #[serde(rename = "POLYLINE")]
+    Polyline(SVGPolyline),
+    #[serde(rename = "POLYGON")]
+    Polygon(SVGPolygon),
+    #[serde(rename = "TEXT")]
+    Text(SVGText),
+    #[serde(rename = "IMAGE")]
+    Image(SVGImage),
+    // Only lives in the document's defs, never in the tree.
+    #[serde(rename = "GRADIENT")]
+    Gradient(SVGGradient),
+    #[serde(rename = "INSTANCE")]
+    Instance(SVGInstance)
 }
 
 impl SVGObject {
@@ -25,7 +55,136 @@ impl SVGObject {
             Self::Circle(circle) => &circle.id,
             Self::Rectangle(rect) => &rect.id,
             Self::Group(grp) => &grp.id,
-            Self::Path(pth) => &pth.id
+            Self::Path(pth) => &pth.id,
+            Self::Ellipse(ellipse) => &ellipse.id,
+            Self::Line(line) => &line.id,
+            Self::Polyline(polyline) => &polyline.id,
+            Self::Polygon(polygon) => &polygon.id,
+            Self::Text(text) => &text.id,
+            Self::Image(image) => &image.id,
+            Self::Instance(instance) => &instance.id,
+            Self::Gradient(gradient) => &gradient.id
+        }
+    }
+
+    pub(crate) fn set_id(&mut self, id: NodeID) {
+        match self {
+            Self::Circle(circle) => circle.id = id,
+            Self::Rectangle(rect) => rect.id = id,
+            Self::Group(grp) => grp.id = id,
+            Self::Path(pth) => pth.id = id,
+            Self::Ellipse(ellipse) => ellipse.id = id,
+            Self::Line(line) => line.id = id,
+            Self::Polyline(polyline) => polyline.id = id,
+            Self::Polygon(polygon) => polygon.id = id,
+            Self::Text(text) => text.id = id,
+            Self::Image(image) => image.id = id,
+            Self::Instance(instance) => instance.id = id,
+            Self::Gradient(gradient) => gradient.id = id
+        }
+    }
+
+    // Same names as the serialized type tags.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Circle(_) => "CIRCLE",
+            Self::Rectangle(_) => "RECTANGLE",
+            Self::Group(_) => "GROUP",
+            Self::Path(_) => "PATH",
+            Self::Ellipse(_) => "ELLIPSE",
+            Self::Line(_) => "LINE",
+            Self::Polyline(_) => "POLYLINE",
+            Self::Polygon(_) => "POLYGON",
+            Self::Text(_) => "TEXT",
+            Self::Image(_) => "IMAGE",
+            Self::Instance(_) => "INSTANCE",
+            Self::Gradient(_) => "GRADIENT"
+        }
+    }
+
+    // The clip path and mask sources, in that order.
+    pub(crate) fn clip_refs(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            Self::Circle(circle) => (circle.clip_path.as_deref(), circle.mask.as_deref()),
+            Self::Rectangle(rect) => (rect.clip_path.as_deref(), rect.mask.as_deref()),
+            Self::Group(grp) => (grp.clip_path.as_deref(), grp.mask.as_deref()),
+            Self::Path(pth) => (pth.clip_path.as_deref(), pth.mask.as_deref()),
+            Self::Ellipse(ellipse) => (ellipse.clip_path.as_deref(), ellipse.mask.as_deref()),
+            Self::Line(line) => (line.clip_path.as_deref(), line.mask.as_deref()),
+            Self::Polyline(polyline) => (polyline.clip_path.as_deref(), polyline.mask.as_deref()),
+            Self::Polygon(polygon) => (polygon.clip_path.as_deref(), polygon.mask.as_deref()),
+            Self::Text(text) => (text.clip_path.as_deref(), text.mask.as_deref()),
+            Self::Image(image) => (image.clip_path.as_deref(), image.mask.as_deref()),
+            Self::Instance(instance) => (instance.clip_path.as_deref(), instance.mask.as_deref()),
+            Self::Gradient(_) => (None, None)
+        }
+    }
+
+    pub(crate) fn set_clip_refs(&mut self, clip_path: Option<NodeID>, mask: Option<NodeID>) {
+        match self {
+            Self::Circle(circle) => (circle.clip_path, circle.mask) = (clip_path, mask),
+            Self::Rectangle(rect) => (rect.clip_path, rect.mask) = (clip_path, mask),
+            Self::Group(grp) => (grp.clip_path, grp.mask) = (clip_path, mask),
+            Self::Path(pth) => (pth.clip_path, pth.mask) = (clip_path, mask),
+            Self::Ellipse(ellipse) => (ellipse.clip_path, ellipse.mask) = (clip_path, mask),
+            Self::Line(line) => (line.clip_path, line.mask) = (clip_path, mask),
+            Self::Polyline(polyline) => (polyline.clip_path, polyline.mask) = (clip_path, mask),
+            Self::Polygon(polygon) => (polygon.clip_path, polygon.mask) = (clip_path, mask),
+            Self::Text(text) => (text.clip_path, text.mask) = (clip_path, mask),
+            Self::Image(image) => (image.clip_path, image.mask) = (clip_path, mask),
+            Self::Instance(instance) => (instance.clip_path, instance.mask) = (clip_path, mask),
+            Self::Gradient(_) => {}
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        match self {
+            Self::Circle(circle) => circle.layer.hidden,
+            Self::Rectangle(rect) => rect.layer.hidden,
+            Self::Group(grp) => grp.layer.hidden,
+            Self::Path(pth) => pth.layer.hidden,
+            Self::Ellipse(ellipse) => ellipse.layer.hidden,
+            Self::Line(line) => line.layer.hidden,
+            Self::Polyline(polyline) => polyline.layer.hidden,
+            Self::Polygon(polygon) => polygon.layer.hidden,
+            Self::Text(text) => text.layer.hidden,
+            Self::Image(image) => image.layer.hidden,
+            Self::Instance(instance) => instance.layer.hidden,
+            Self::Gradient(_) => false
+        }
+    }
+
+    pub(crate) fn transform(&self) -> Transform {
+        match self {
+            Self::Circle(circle) => circle.transform,
+            Self::Rectangle(rect) => rect.transform,
+            Self::Group(grp) => grp.transform,
+            Self::Path(pth) => pth.transform,
+            Self::Ellipse(ellipse) => ellipse.transform,
+            Self::Line(line) => line.transform,
+            Self::Polyline(polyline) => polyline.transform,
+            Self::Polygon(polygon) => polygon.transform,
+            Self::Text(text) => text.transform,
+            Self::Image(image) => image.transform,
+            Self::Instance(instance) => instance.transform,
+            Self::Gradient(gradient) => gradient.transform
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        match self {
+            Self::Circle(circle) => circle.write_svg(out, depth),
+            Self::Rectangle(rect) => rect.write_svg(out, depth),
+            Self::Group(grp) => grp.write_svg(out, depth),
+            Self::Path(pth) => pth.write_svg(out, depth),
+            Self::Ellipse(ellipse) => ellipse.write_svg(out, depth),
+            Self::Line(line) => line.write_svg(out, depth),
+            Self::Polyline(polyline) => polyline.write_svg(out, depth),
+            Self::Polygon(polygon) => polygon.write_svg(out, depth),
+            Self::Text(text) => text.write_svg(out, depth),
+            Self::Image(image) => image.write_svg(out, depth),
+            Self::Instance(instance) => instance.write_svg(out, depth),
+            Self::Gradient(gradient) => gradient.write_svg(out, depth)
         }
     }
 }
@@ -33,14 +192,69 @@ impl SVGObject {
 #[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct SVGDocTree {
-    pub children: Vec<SVGObject>
+    pub children: Vec<SVGObject>,
+    #[serde(default)]
+    pub gradients: Vec<SVGGradient>,
+    #[serde(default)]
+    pub symbols: Vec<SVGGroup>
 }
 
 
 impl SVGDocTree {
 
     pub (crate) fn new() -> Self {
-        Self { children: Vec::new() }
+        Self { children: Vec::new(), gradients: Vec::new(), symbols: Vec::new() }
+    }
+
+    // Serializes the tree as a standalone SVG document, children in paint
+    // order. Hidden objects are kept but marked so they aren't drawn, use
+    // `without_hidden` first to leave them out.
+    pub fn export_svg(&self) -> String {
+        let children = resolve_instances(&self.children, &self.symbols);
+        let clips = ClipRefs::resolve(&children);
+        let mut out = String::from("<svg xmlns=\"http://www.w3.org/2000/svg\">\n");
+        if !self.gradients.is_empty() || !self.symbols.is_empty() || !clips.is_empty() {
+            out.push_str("  <defs>\n");
+            for gradient in self.gradients.iter() {
+                gradient.write_svg(&mut out, 2);
+            }
+            for symbol in self.symbols.iter() {
+                symbol.write_svg_symbol(&mut out, 2, &self.symbols);
+            }
+            clips.write_defs(&mut out, 2);
+            out.push_str("  </defs>\n");
+        }
+        for child in clips.visible(&children).iter() {
+            child.write_svg(&mut out, 1);
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    // Hiding a group hides what it holds, and hiding a symbol hides its
+    // instances.
+    pub fn without_hidden(&self) -> SVGDocTree {
+        SVGDocTree {
+            children: Self::visible_objects(&self.children),
+            gradients: self.gradients.clone(),
+            symbols: self.symbols.iter()
+                .filter(|symbol| !symbol.layer.hidden)
+                .map(|symbol| SVGGroup { children: Self::visible_objects(&symbol.children), ..symbol.clone() })
+                .collect()
+        }
+    }
+
+    fn visible_objects(children: &[SVGObject]) -> Vec<SVGObject> {
+        children.iter()
+            .filter(|child| !child.is_hidden())
+            .map(|child| match child {
+                SVGObject::Group(group) => SVGObject::Group(SVGGroup {
+                    children: Self::visible_objects(&group.children),
+                    ..group.clone()
+                }),
+                _ => child.clone()
+            })
+            .collect()
     }
 
     pub(crate) fn find_group_has_object_id<'a>(
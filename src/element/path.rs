@@ -55,7 +55,7 @@ pub enum PartialSVGPathCommand {
 }
 
 impl SVGPathCommand {
-    pub fn get_id<'a>(&'a self) -> &'a str {
+    pub fn get_id(&self) -> &str {
         match self {
             Self::Start { id, .. } => id,
             Self::Line { id, .. } => id,
@@ -65,36 +65,80 @@ impl SVGPathCommand {
         }
     }
 
+    pub fn get_type(&self) -> SVGPathCommandType {
+        match self {
+            Self::Start { .. } => SVGPathCommandType::START,
+            Self::Line { .. } => SVGPathCommandType::LINE,
+            Self::Close { .. } => SVGPathCommandType::CLOSE,
+            Self::Bezier { .. } => SVGPathCommandType::BEZIER,
+            Self::BezierQuad { .. } => SVGPathCommandType::BEZIER_QUAD,
+        }
+    }
+
+    pub fn new(id: String, command_type: SVGPathCommandType, pos: Vec2) -> SVGPathCommand {
+        match command_type {
+            SVGPathCommandType::START => SVGPathCommand::Start { id, pos },
+            SVGPathCommandType::LINE => SVGPathCommand::Line { id, pos },
+            SVGPathCommandType::CLOSE => SVGPathCommand::Close { id },
+            SVGPathCommandType::BEZIER => {
+                let handle1 = Vec2 { x: pos.x + 20.0, y: pos.y + 20.0 };
+                let handle2 = Vec2 { x: pos.x + 20.0, y: pos.y - 20.0 };
+                SVGPathCommand::Bezier { id, handle1, handle2, pos }
+            },
+            SVGPathCommandType::BEZIER_QUAD => {
+                let handle = Vec2 { x: pos.x, y: pos.y + 20.0 };
+                SVGPathCommand::BezierQuad { id, handle, pos }
+            },
+        }
+    }
+
+    // The command as it appears in a path `d` attribute.
+    pub fn to_path_data(&self) -> String {
+        match self {
+            Self::Start { pos, .. } => format!("M {} {}", pos.x, pos.y),
+            Self::Line { pos, .. } => format!("L {} {}", pos.x, pos.y),
+            Self::Close { .. } => "Z".to_string(),
+            Self::Bezier { handle1, handle2, pos, .. } => format!(
+                "C {} {} {} {} {} {}",
+                handle1.x, handle1.y, handle2.x, handle2.y, pos.x, pos.y
+            ),
+            Self::BezierQuad { handle, pos, .. } => format!(
+                "Q {} {} {} {}",
+                handle.x, handle.y, pos.x, pos.y
+            ),
+        }
+    }
+
     pub fn from_partial(command: PartialSVGPathCommand) -> SVGPathCommand {
         let id = gen_str_id();
         match command {
             PartialSVGPathCommand::Start { pos } => SVGPathCommand::Start { 
                 id, 
-                pos: pos.unwrap_or(Vec2 { x: 0, y: 0 })
+                pos: pos.unwrap_or(Vec2 { x: 0.0, y: 0.0 })
             },
             PartialSVGPathCommand::Line { pos } => SVGPathCommand::Line { 
                 id, 
-                pos: pos.unwrap_or(Vec2 { x: 0, y: 0 })
+                pos: pos.unwrap_or(Vec2 { x: 0.0, y: 0.0 })
             },
             PartialSVGPathCommand::Close {  } => SVGPathCommand::Close { 
                 id
             },
             PartialSVGPathCommand::Bezier { handle1, handle2, pos } => SVGPathCommand::Bezier { 
                 id, 
-                handle1: handle1.unwrap_or(Vec2 { x: 5, y: 5 }), 
-                handle2: handle2.unwrap_or(Vec2 { x: 5, y: -5 }), 
-                pos: pos.unwrap_or(Vec2 { x: 0, y: 0 })
+                handle1: handle1.unwrap_or(Vec2 { x: 5.0, y: 5.0 }), 
+                handle2: handle2.unwrap_or(Vec2 { x: 5.0, y: -5.0 }), 
+                pos: pos.unwrap_or(Vec2 { x: 0.0, y: 0.0 })
             },
             PartialSVGPathCommand::BezierQuad { handle, pos } => SVGPathCommand::BezierQuad { 
                 id, 
-                handle: handle.unwrap_or(Vec2 { x: 5, y: 5 }), 
-                pos: pos.unwrap_or(Vec2 { x: 0, y: 0 })
+                handle: handle.unwrap_or(Vec2 { x: 5.0, y: 5.0 }), 
+                pos: pos.unwrap_or(Vec2 { x: 0.0, y: 0.0 })
             },
         }
     }
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub enum SVGPathCommandType {
     START = 0,
@@ -104,16 +148,84 @@ pub enum SVGPathCommandType {
     BEZIER_QUAD = 5,
 }
 
+// Decides which areas enclosed by overlapping subpaths are filled, e.g.
+// `EVENODD` leaves the hole of a donut drawn as two circles empty.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FillRule {
+    #[default]
+    Nonzero,
+    Evenodd
+}
+
+impl FillRule {
+    pub fn attr(&self) -> &'static str {
+        match self {
+            FillRule::Nonzero => "nonzero",
+            FillRule::Evenodd => "evenodd"
+        }
+    }
+}
+
+// A run of points from a START point up to the next one. It takes the id
+// of its START point, which stays the same when the subpath is moved or
+// reversed.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGSubpath {
+    pub id: String,
+    pub points: Vec<SVGPathCommand>,
+    // Ends with a CLOSE point.
+    pub closed: bool
+}
+
+// Same wrapper as `SVGDocTree`, lists cannot cross into JS on their own.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGSubpaths {
+    pub subpaths: Vec<SVGSubpath>
+}
+
+// Splits points in path order into subpaths, given which ones are START
+// points. Points before the first START form a subpath of their own.
+pub(crate) fn subpath_ranges<T>(points: &[T], is_start: impl Fn(&T) -> bool) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        match ranges.last_mut() {
+            Some(range) if !is_start(point) => range.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
 
 #[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct SVGPath {
     pub(crate) id: NodeID,
     pub(crate) fill: Color,
-    pub(crate) stroke_width: i32,
+    #[serde(default)]
+    pub(crate) fill_gradient: Option<NodeID>,
+    pub(crate) stroke_width: f64,
     pub(crate) stroke: Color,
+    #[serde(default)]
+    pub(crate) stroke_gradient: Option<NodeID>,
+    #[serde(flatten)]
+    pub(crate) stroke_style: StrokeStyle,
+    #[serde(default)]
+    pub(crate) fill_rule: FillRule,
     pub(crate) points: Vec<SVGPathCommand>,
-    pub(crate) opacity: f32
+    pub(crate) opacity: f32,
+    #[serde(default)]
+    pub(crate) clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub(crate) mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub(crate) layer: Layer,
+    #[serde(default)]
+    pub(crate) transform: Transform
 }
 
 #[derive(Serialize, Deserialize, Tsify, Clone)]
@@ -122,22 +234,37 @@ pub struct PartialSVGPath {
     #[tsify(optional)]
     pub fill: Option<Color>,
     #[tsify(optional)]
-    pub stroke_width: Option<i32>,
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<f64>,
     #[tsify(optional)]
     pub stroke: Option<Color>,
     #[tsify(optional)]
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[serde(flatten)]
+    pub stroke_style: PartialStrokeStyle,
+    #[tsify(optional)]
+    pub fill_rule: Option<FillRule>,
+    #[tsify(optional)]
     pub opacity: Option<f32>,
     #[tsify(optional)]
+    pub transform: Option<Transform>,
+    #[tsify(optional)]
     pub points: Option<Vec<PartialSVGPathCommand>>
 }
 
 impl PartialSVGPath {
     pub fn empty() -> Self {
         Self { 
-            fill: None, 
+            fill: None,
+            fill_gradient: None,
             stroke_width: None, 
-            stroke: None, 
-            opacity: None, 
+            stroke: None,
+            stroke_gradient: None,
+            stroke_style: PartialStrokeStyle::empty(),
+            fill_rule: None,
+            opacity: None,
+            transform: None,
             points: None
         }
     }
@@ -149,7 +276,12 @@ impl partially::Partial for SVGPath {
         let will_apply_some = partial.fill.is_some()
             || partial.stroke_width.is_some()
             || partial.stroke.is_some()
-            || partial.points.is_some();
+            || partial.points.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty()
+            || partial.fill_rule.is_some();
         if let Some(fill) = partial.fill {
             self.fill = fill.into();
         }
@@ -164,7 +296,20 @@ impl partially::Partial for SVGPath {
         }
         if let Some(mut points) = partial.points {
             self.points = points.drain(..)
-                .map(|it| SVGPathCommand::from_partial(it)).collect();
+                .map(SVGPathCommand::from_partial).collect();
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        self.stroke_style.apply_some(partial.stroke_style);
+        if let Some(fill_rule) = partial.fill_rule {
+            self.fill_rule = fill_rule;
         }
         will_apply_some
     }
@@ -174,20 +319,69 @@ impl SVGPath {
     pub(crate) fn default() -> Self {
         SVGPath {
             id: gen_str_id(),
-            fill: Color::white(), 
-            stroke_width: 2, 
-            stroke: Color::black(), 
+            fill: Color::white(),
+            fill_gradient: None,
+            stroke_width: 2.0, 
+            stroke: Color::black(),
+            stroke_gradient: None,
+            stroke_style: StrokeStyle::default(),
+            fill_rule: FillRule::Nonzero,
             points: vec![],
-            opacity: 1.0
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
         }
     }
 
-    pub(crate) fn find_point_mut<'a>(&'a mut self, point_id: &'a str) -> Option<&'a mut SVGPathCommand> {
-        for point in self.points.iter_mut() {
-            if point.get_id().eq(point_id) {
-                return Some(point);
-            }
+    pub fn path_data(&self) -> String {
+        self.points.iter()
+            .map(|point| point.to_path_data())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn subpaths(&self) -> Vec<SVGSubpath> {
+        subpath_ranges(&self.points, |point| point.get_type() == SVGPathCommandType::START)
+            .into_iter()
+            .map(|range| {
+                let points = self.points[range].to_vec();
+                SVGSubpath {
+                    id: points[0].get_id().to_string(),
+                    closed: points.last().is_some_and(|point| point.get_type() == SVGPathCommandType::CLOSE),
+                    points
+                }
+            })
+            .collect()
+    }
+
+    fn fill_rule_attr(&self) -> String {
+        match self.fill_rule {
+            FillRule::Nonzero => String::new(),
+            fill_rule => format!(" fill-rule=\"{}\"", fill_rule.attr()),
         }
-        return None;
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<path id=\"{}\"{}{}{} d=\"{}\"{}{}{} stroke-width=\"{}\"{} opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.path_data(),
+            self.fill.svg_paint_attrs("fill", &self.fill_gradient),
+            self.fill_rule_attr(),
+            self.stroke.svg_paint_attrs("stroke", &self.stroke_gradient),
+            self.stroke_width,
+            self.stroke_style.svg_attrs(),
+            self.opacity
+        ));
+    }
+
+    pub(crate) fn find_point_mut<'a>(&'a mut self, point_id: &'a str) -> Option<&'a mut SVGPathCommand> {
+        self.points.iter_mut().find(|point| point.get_id().eq(point_id))
     }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+use crate::prelude::*;
+
+// A polyline whose last vertex joins back to the first.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGPolygon {
+    pub id: NodeID,
+    pub points: Vec<SVGVertex>,
+    pub fill: Color,
+    #[serde(default)]
+    pub fill_gradient: Option<NodeID>,
+    pub stroke_width: f64,
+    pub stroke: Color,
+    #[serde(default)]
+    pub stroke_gradient: Option<NodeID>,
+    #[serde(flatten)]
+    pub stroke_style: StrokeStyle,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGPolygon {
+    // Replaces every vertex, use the vertex methods to edit single ones.
+    #[tsify(optional)]
+    pub points: Option<Vec<Vec2>>,
+    #[tsify(optional)]
+    pub fill: Option<Color>,
+    #[tsify(optional)]
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<f64>,
+    #[tsify(optional)]
+    pub stroke: Option<Color>,
+    #[tsify(optional)]
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[serde(flatten)]
+    pub stroke_style: PartialStrokeStyle,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGPolygon {
+    pub fn empty() -> Self {
+        PartialSVGPolygon {
+            points: None,
+            fill: None,
+            fill_gradient: None,
+            stroke_width: None,
+            stroke: None,
+            stroke_gradient: None,
+            stroke_style: PartialStrokeStyle::empty(),
+            opacity: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGPolygon {
+    type Item = PartialSVGPolygon;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.points.is_some() || partial.fill.is_some()
+            || partial.stroke_width.is_some() || partial.stroke.is_some()
+            || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
+        if let Some(points) = partial.points {
+            self.points = SVGVertex::from_positions(points);
+        }
+        if let Some(fill) = partial.fill {
+            self.fill = fill;
+        }
+        if let Some(stroke_width) = partial.stroke_width {
+            self.stroke_width = stroke_width;
+        }
+        if let Some(stroke) = partial.stroke {
+            self.stroke = stroke;
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        self.stroke_style.apply_some(partial.stroke_style);
+        will_apply_some
+    }
+}
+
+impl SVGPolygon {
+    pub(crate) fn default() -> Self {
+        SVGPolygon {
+            id: gen_str_id(),
+            points: vec![],
+            fill: Color::white(),
+            fill_gradient: None,
+            stroke_width: 2.0,
+            stroke: Color::black(),
+            stroke_gradient: None,
+            stroke_style: StrokeStyle::default(),
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<polygon id=\"{}\"{}{}{} points=\"{}\"{}{} stroke-width=\"{}\"{} opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            SVGVertex::points_attr(&self.points),
+            self.fill.svg_paint_attrs("fill", &self.fill_gradient),
+            self.stroke.svg_paint_attrs("stroke", &self.stroke_gradient),
+            self.stroke_width,
+            self.stroke_style.svg_attrs(),
+            self.opacity
+        ));
+    }
+}
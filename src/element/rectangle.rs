@@ -5,12 +5,26 @@ use crate::prelude::*;
 pub struct SVGRectangle {
     pub id: String,
     pub pos: Vec2,
-    pub height: i32,
-    pub width: i32,
+    pub height: f64,
+    pub width: f64,
     pub fill: Color,
-    pub stroke_width: i32,
+    #[serde(default)]
+    pub fill_gradient: Option<NodeID>,
+    pub stroke_width: f64,
     pub stroke: Color,
-    pub opacity: f32
+    #[serde(default)]
+    pub stroke_gradient: Option<NodeID>,
+    #[serde(flatten)]
+    pub stroke_style: StrokeStyle,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform
 }
 
 #[derive(Serialize, Deserialize, Tsify, Clone)]
@@ -19,17 +33,25 @@ pub struct PartialSVGRectangle {
     #[tsify(optional)]
     pub pos: Option<Vec2>,
     #[tsify(optional)]
-    pub height: Option<i32>,
+    pub height: Option<f64>,
     #[tsify(optional)]
-    pub width: Option<i32>,
+    pub width: Option<f64>,
     #[tsify(optional)]
     pub fill: Option<Color>,
     #[tsify(optional)]
-    pub stroke_width: Option<i32>,
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub stroke_width: Option<f64>,
     #[tsify(optional)]
     pub stroke: Option<Color>,
     #[tsify(optional)]
-    pub opacity: Option<f32>
+    pub stroke_gradient: Option<JSNullable<NodeID>>,
+    #[serde(flatten)]
+    pub stroke_style: PartialStrokeStyle,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
 }
 
 impl PartialSVGRectangle {
@@ -38,10 +60,14 @@ impl PartialSVGRectangle {
             pos: None, 
             height: None, 
             width: None, 
-            fill: None, 
+            fill: None,
+            fill_gradient: None,
             stroke_width: None, 
-            stroke: None, 
-            opacity: None
+            stroke: None,
+            stroke_gradient: None,
+            stroke_style: PartialStrokeStyle::empty(),
+            opacity: None,
+            transform: None
         }
     }
 }
@@ -51,7 +77,11 @@ impl partially::Partial for SVGRectangle {
     fn apply_some(&mut self, partial: Self::Item) -> bool {
         let will_apply_some = partial.pos.is_some() || partial.height.is_some()
             || partial.width.is_some() || partial.fill.is_some()
-            || partial.stroke_width.is_some() || partial.stroke.is_some() || partial.opacity.is_some();
+            || partial.stroke_width.is_some() || partial.stroke.is_some() || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some()
+            || partial.stroke_gradient.is_some()
+            || !partial.stroke_style.is_empty();
         if let Some(pos) = partial.pos {
             self.pos = pos.into();
         }
@@ -73,6 +103,16 @@ impl partially::Partial for SVGRectangle {
         if let Some(opacity) = partial.opacity {
             self.opacity = opacity.into();
         }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        if let Some(stroke_gradient) = partial.stroke_gradient {
+            self.stroke_gradient = stroke_gradient.into();
+        }
+        self.stroke_style.apply_some(partial.stroke_style);
         will_apply_some
     }
 }
@@ -83,25 +123,32 @@ impl SVGRectangle {
         let id = gen_str_id();
         SVGRectangle { 
             id, 
-            pos: Vec2 { x: 0, y: 0 }, 
-            height: 5, 
-            width: 10, 
-            fill: Color::white(), 
-            stroke_width: 2, 
+            pos: Vec2 { x: 0.0, y: 0.0 }, 
+            height: 5.0, 
+            width: 10.0, 
+            fill: Color::white(),
+            fill_gradient: None,
+            stroke_width: 2.0, 
             stroke: Color::black(),
-            opacity: 1.0
+            stroke_gradient: None,
+            stroke_style: StrokeStyle::default(),
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
         }
     }
 
-    pub fn set_pos(&mut self, x: i32, y: i32) {
+    pub fn set_pos(&mut self, x: f64, y: f64) {
         self.pos = Vec2 { x, y };
     }
 
-    pub fn set_height(&mut self, height: i32) {
+    pub fn set_height(&mut self, height: f64) {
         self.height = height;
     }
 
-    pub fn set_width(&mut self, width: i32) {
+    pub fn set_width(&mut self, width: f64) {
         self.width = width;
     }
 
@@ -109,11 +156,31 @@ impl SVGRectangle {
         self.fill = Color(red, green, blue, opacity);
     }
 
-    pub fn set_stroke_width(&mut self, stroke_width: i32) {
+    pub fn set_stroke_width(&mut self, stroke_width: f64) {
         self.stroke_width = stroke_width;
     }
 
     pub fn set_stroke(&mut self, red: i32, green: i32, blue: i32, opacity: f32) {
         self.stroke = Color(red, green, blue, opacity);
     }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<rect id=\"{}\"{}{}{} x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{} stroke-width=\"{}\"{} opacity=\"{}\"/>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.pos.x,
+            self.pos.y,
+            self.width,
+            self.height,
+            self.fill.svg_paint_attrs("fill", &self.fill_gradient),
+            self.stroke.svg_paint_attrs("stroke", &self.stroke_gradient),
+            self.stroke_width,
+            self.stroke_style.svg_attrs(),
+            self.opacity
+        ));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,168 @@
+use crate::prelude::*;
+
+// Shape at the open ends of a stroke and of every dash.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StrokeLinecap {
+    #[default]
+    Butt,
+    Round,
+    Square
+}
+
+impl StrokeLinecap {
+    pub fn attr(&self) -> &'static str {
+        match self {
+            StrokeLinecap::Butt => "butt",
+            StrokeLinecap::Round => "round",
+            StrokeLinecap::Square => "square"
+        }
+    }
+}
+
+// Shape at the corners of a stroke. Miter joins sharper than the miter
+// limit are drawn beveled.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum StrokeLinejoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel
+}
+
+impl StrokeLinejoin {
+    pub fn attr(&self) -> &'static str {
+        match self {
+            StrokeLinejoin::Miter => "miter",
+            StrokeLinejoin::Round => "round",
+            StrokeLinejoin::Bevel => "bevel"
+        }
+    }
+}
+
+pub const DEFAULT_MITERLIMIT: f64 = 4.0;
+
+// Serde default for shapes from before stroke styling.
+pub(crate) fn default_miterlimit() -> f64 {
+    DEFAULT_MITERLIMIT
+}
+
+// Dashes, caps, joins and miter limit of a shape's stroke. Shapes flatten
+// it into their own fields.
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StrokeStyle {
+    #[serde(default)]
+    pub stroke_dasharray: Vec<f64>,
+    #[serde(default)]
+    pub stroke_dashoffset: f64,
+    #[serde(default)]
+    pub stroke_linecap: StrokeLinecap,
+    #[serde(default)]
+    pub stroke_linejoin: StrokeLinejoin,
+    #[serde(default = "default_miterlimit")]
+    pub stroke_miterlimit: f64,
+}
+
+// The SVG initial values.
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            stroke_dasharray: Vec::new(),
+            stroke_dashoffset: 0.0,
+            stroke_linecap: StrokeLinecap::Butt,
+            stroke_linejoin: StrokeLinejoin::Miter,
+            stroke_miterlimit: DEFAULT_MITERLIMIT
+        }
+    }
+}
+
+impl StrokeStyle {
+    // Values that match the SVG initial ones are left out.
+    pub(crate) fn svg_attrs(&self) -> String {
+        let mut attrs = String::new();
+        if !self.stroke_dasharray.is_empty() {
+            attrs.push_str(&format!(" stroke-dasharray=\"{}\"", dasharray_attr(&self.stroke_dasharray)));
+        }
+        if self.stroke_dashoffset != 0.0 {
+            attrs.push_str(&format!(" stroke-dashoffset=\"{}\"", self.stroke_dashoffset));
+        }
+        if self.stroke_linecap != StrokeLinecap::default() {
+            attrs.push_str(&format!(" stroke-linecap=\"{}\"", self.stroke_linecap.attr()));
+        }
+        if self.stroke_linejoin != StrokeLinejoin::default() {
+            attrs.push_str(&format!(" stroke-linejoin=\"{}\"", self.stroke_linejoin.attr()));
+        }
+        if self.stroke_miterlimit != DEFAULT_MITERLIMIT {
+            attrs.push_str(&format!(" stroke-miterlimit=\"{}\"", self.stroke_miterlimit));
+        }
+        attrs
+    }
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialStrokeStyle {
+    #[tsify(optional)]
+    pub stroke_dasharray: Option<Vec<f64>>,
+    #[tsify(optional)]
+    pub stroke_dashoffset: Option<f64>,
+    #[tsify(optional)]
+    pub stroke_linecap: Option<StrokeLinecap>,
+    #[tsify(optional)]
+    pub stroke_linejoin: Option<StrokeLinejoin>,
+    #[tsify(optional)]
+    pub stroke_miterlimit: Option<f64>,
+}
+
+impl PartialStrokeStyle {
+    pub fn empty() -> Self {
+        PartialStrokeStyle {
+            stroke_dasharray: None,
+            stroke_dashoffset: None,
+            stroke_linecap: None,
+            stroke_linejoin: None,
+            stroke_miterlimit: None
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stroke_dasharray.is_none()
+            && self.stroke_dashoffset.is_none()
+            && self.stroke_linecap.is_none()
+            && self.stroke_linejoin.is_none()
+            && self.stroke_miterlimit.is_none()
+    }
+}
+
+impl partially::Partial for StrokeStyle {
+    type Item = PartialStrokeStyle;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = !partial.is_empty();
+        if let Some(stroke_dasharray) = partial.stroke_dasharray {
+            self.stroke_dasharray = stroke_dasharray;
+        }
+        if let Some(stroke_dashoffset) = partial.stroke_dashoffset {
+            self.stroke_dashoffset = stroke_dashoffset;
+        }
+        if let Some(stroke_linecap) = partial.stroke_linecap {
+            self.stroke_linecap = stroke_linecap;
+        }
+        if let Some(stroke_linejoin) = partial.stroke_linejoin {
+            self.stroke_linejoin = stroke_linejoin;
+        }
+        if let Some(stroke_miterlimit) = partial.stroke_miterlimit {
+            self.stroke_miterlimit = stroke_miterlimit;
+        }
+        will_apply_some
+    }
+}
+
+// `stroke-dasharray="5,3"` style list, `none` when empty.
+pub(crate) fn dasharray_attr(dasharray: &[f64]) -> String {
+    if dasharray.is_empty() { return "none".to_string(); }
+    dasharray.iter().map(|dash| dash.to_string()).collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,173 @@
+use crate::prelude::*;
+
+// Written out as the `text-anchor` of the text.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TextAlign {
+    Start,
+    Middle,
+    End
+}
+
+impl TextAlign {
+    pub fn text_anchor(&self) -> &'static str {
+        match self {
+            TextAlign::Start => "start",
+            TextAlign::Middle => "middle",
+            TextAlign::End => "end"
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SVGText {
+    pub id: NodeID,
+    pub pos: Vec2,
+    pub content: String,
+    pub font_family: String,
+    pub font_size: f64,
+    pub font_weight: i32,
+    pub align: TextAlign,
+    pub fill: Color,
+    #[serde(default)]
+    pub fill_gradient: Option<NodeID>,
+    pub opacity: f32,
+    #[serde(default)]
+    pub clip_path: Option<NodeID>,
+    #[serde(default)]
+    pub mask: Option<NodeID>,
+    #[serde(flatten)]
+    pub layer: Layer,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+// Setting `content` replaces the whole text, use `insert_text` and
+// `delete_text` for edits that merge with concurrent typing.
+#[derive(Serialize, Deserialize, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct PartialSVGText {
+    #[tsify(optional)]
+    pub pos: Option<Vec2>,
+    #[tsify(optional)]
+    pub content: Option<String>,
+    #[tsify(optional)]
+    pub font_family: Option<String>,
+    #[tsify(optional)]
+    pub font_size: Option<f64>,
+    #[tsify(optional)]
+    pub font_weight: Option<i32>,
+    #[tsify(optional)]
+    pub align: Option<TextAlign>,
+    #[tsify(optional)]
+    pub fill: Option<Color>,
+    #[tsify(optional)]
+    pub fill_gradient: Option<JSNullable<NodeID>>,
+    #[tsify(optional)]
+    pub opacity: Option<f32>,
+    #[tsify(optional)]
+    pub transform: Option<Transform>
+}
+
+impl PartialSVGText {
+    pub fn empty() -> Self {
+        PartialSVGText {
+            pos: None,
+            content: None,
+            font_family: None,
+            font_size: None,
+            font_weight: None,
+            align: None,
+            fill: None,
+            fill_gradient: None,
+            opacity: None,
+            transform: None
+        }
+    }
+}
+
+impl partially::Partial for SVGText {
+    type Item = PartialSVGText;
+    fn apply_some(&mut self, partial: Self::Item) -> bool {
+        let will_apply_some = partial.pos.is_some() || partial.content.is_some()
+            || partial.font_family.is_some() || partial.font_size.is_some()
+            || partial.font_weight.is_some() || partial.align.is_some()
+            || partial.fill.is_some() || partial.opacity.is_some()
+            || partial.transform.is_some()
+            || partial.fill_gradient.is_some();
+        if let Some(pos) = partial.pos {
+            self.pos = pos;
+        }
+        if let Some(content) = partial.content {
+            self.content = content;
+        }
+        if let Some(font_family) = partial.font_family {
+            self.font_family = font_family;
+        }
+        if let Some(font_size) = partial.font_size {
+            self.font_size = font_size;
+        }
+        if let Some(font_weight) = partial.font_weight {
+            self.font_weight = font_weight;
+        }
+        if let Some(align) = partial.align {
+            self.align = align;
+        }
+        if let Some(fill) = partial.fill {
+            self.fill = fill;
+        }
+        if let Some(opacity) = partial.opacity {
+            self.opacity = opacity;
+        }
+        if let Some(transform) = partial.transform {
+            self.transform = transform;
+        }
+        if let Some(fill_gradient) = partial.fill_gradient {
+            self.fill_gradient = fill_gradient.into();
+        }
+        will_apply_some
+    }
+}
+
+impl SVGText {
+    pub(crate) fn default() -> Self {
+        SVGText {
+            id: gen_str_id(),
+            pos: Vec2 { x: 0.0, y: 0.0 },
+            content: String::new(),
+            font_family: "sans-serif".to_string(),
+            font_size: 16.0,
+            font_weight: 400,
+            align: TextAlign::Start,
+            fill: Color::black(),
+            fill_gradient: None,
+            opacity: 1.0,
+            clip_path: None,
+            mask: None,
+            layer: Layer::default(),
+            transform: Transform::identity()
+        }
+    }
+
+    pub(crate) fn write_svg(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}<text id=\"{}\"{}{}{} x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" font-weight=\"{}\" text-anchor=\"{}\"{} opacity=\"{}\">{}</text>\n",
+            "  ".repeat(depth),
+            escape_xml_attr(&self.id),
+            self.transform.svg_attr(),
+            clip_attrs(&self.id, &self.clip_path, &self.mask),
+            self.layer.svg_attr(),
+            self.pos.x,
+            self.pos.y,
+            escape_xml_attr(&self.font_family),
+            self.font_size,
+            self.font_weight,
+            self.align.text_anchor(),
+            self.fill.svg_paint_attrs("fill", &self.fill_gradient),
+            self.opacity,
+            escape_xml_attr(&self.content)
+        ));
+    }
+}
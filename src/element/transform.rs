@@ -0,0 +1,191 @@
+use crate::prelude::*;
+
+// Affine matrix laid out as in SVG's `matrix(a b c d e f)`, mapping (x, y)
+// to (a x + c y + e, b x + d y + f).
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Matrix {
+    pub fn identity() -> Self {
+        Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 }
+    }
+
+    pub fn translate(x: f64, y: f64) -> Self {
+        Matrix { e: x, f: y, ..Matrix::identity() }
+    }
+
+    pub fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Matrix { a: cos, b: sin, c: -sin, d: cos, e: 0.0, f: 0.0 }
+    }
+
+    pub fn scale(x: f64, y: f64) -> Self {
+        Matrix { a: x, d: y, ..Matrix::identity() }
+    }
+
+    pub fn skew_x(degrees: f64) -> Self {
+        Matrix { c: degrees.to_radians().tan(), ..Matrix::identity() }
+    }
+
+    pub fn skew_y(degrees: f64) -> Self {
+        Matrix { b: degrees.to_radians().tan(), ..Matrix::identity() }
+    }
+
+    // `self` applied after `other`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    // None when the matrix flattens everything onto a line or a point.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 { return None; }
+        Some(Matrix {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            e: (self.c * self.f - self.d * self.e) / det,
+            f: (self.b * self.e - self.a * self.f) / det,
+        })
+    }
+}
+
+// Kept as components rather than a matrix so that concurrent edits to, say,
+// the rotation and the scale both survive a merge. Applied in the order the
+// SVG attribute lists them: scale first, translate last.
+#[derive(Serialize, Deserialize, Tsify, Clone, Copy, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Transform {
+    pub translate_x: f64,
+    pub translate_y: f64,
+    // Degrees, clockwise in SVG's y-down coordinates.
+    pub rotate: f64,
+    pub skew_x: f64,
+    pub skew_y: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            translate_x: 0.0,
+            translate_y: 0.0,
+            rotate: 0.0,
+            skew_x: 0.0,
+            skew_y: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::identity()
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        Matrix::translate(self.translate_x, self.translate_y)
+            .multiply(&Matrix::rotate(self.rotate))
+            .multiply(&Matrix::skew_x(self.skew_x))
+            .multiply(&Matrix::skew_y(self.skew_y))
+            .multiply(&Matrix::scale(self.scale_x, self.scale_y))
+    }
+
+    // Splits a matrix into translate, rotate, skewX and scale. Any matrix
+    // can be written that way, so `skew_y` is always zero.
+    pub fn from_matrix(m: &Matrix) -> Self {
+        let scale_x = m.a.hypot(m.b);
+        let det = m.a * m.d - m.b * m.c;
+        if scale_x == 0.0 || det == 0.0 {
+            return Transform { translate_x: m.e, translate_y: m.f, scale_x, scale_y: 0.0, ..Transform::identity() };
+        }
+        Transform {
+            translate_x: m.e,
+            translate_y: m.f,
+            rotate: m.b.atan2(m.a).to_degrees(),
+            skew_x: ((m.a * m.c + m.b * m.d) / det).atan().to_degrees(),
+            skew_y: 0.0,
+            scale_x,
+            scale_y: det / scale_x
+        }
+    }
+
+    // Rotates around `pivot`, given in the object's own coordinates like
+    // its `pos`. The rotation is applied on top of the existing transform,
+    // so only the translation and the angle change.
+    pub fn rotated_around(&self, degrees: f64, pivot: Vec2) -> Self {
+        let (px, py) = self.matrix().apply(pivot.x, pivot.y);
+        let around = Matrix::translate(px, py)
+            .multiply(&Matrix::rotate(degrees))
+            .multiply(&Matrix::translate(-px, -py));
+        let (translate_x, translate_y) = around.apply(self.translate_x, self.translate_y);
+        Transform { translate_x, translate_y, rotate: self.rotate + degrees, ..*self }
+    }
+
+    // Scales around `pivot`, given in the object's own coordinates, along
+    // the object's own axes. Only the translation and the scale change.
+    pub fn scaled_around(&self, scale_x: f64, scale_y: f64, pivot: Vec2) -> Self {
+        let scaled = Transform { scale_x: self.scale_x * scale_x, scale_y: self.scale_y * scale_y, ..*self };
+        // Keep the pivot where it was.
+        let (x, y) = self.matrix().apply(pivot.x, pivot.y);
+        let (moved_x, moved_y) = scaled.matrix().apply(pivot.x, pivot.y);
+        Transform {
+            translate_x: scaled.translate_x + x - moved_x,
+            translate_y: scaled.translate_y + y - moved_y,
+            ..scaled
+        }
+    }
+
+    // The ` transform="..."` attribute, empty for the identity.
+    pub fn svg_attr(&self) -> String {
+        self.svg_attr_named("transform")
+    }
+
+    // Gradients take the same list as `gradientTransform`.
+    pub(crate) fn svg_attr_named(&self, name: &str) -> String {
+        let mut parts = Vec::new();
+        if self.translate_x != 0.0 || self.translate_y != 0.0 {
+            parts.push(format!("translate({} {})", self.translate_x, self.translate_y));
+        }
+        if self.rotate != 0.0 {
+            parts.push(format!("rotate({})", self.rotate));
+        }
+        if self.skew_x != 0.0 {
+            parts.push(format!("skewX({})", self.skew_x));
+        }
+        if self.skew_y != 0.0 {
+            parts.push(format!("skewY({})", self.skew_y));
+        }
+        if self.scale_x != 1.0 || self.scale_y != 1.0 {
+            parts.push(format!("scale({} {})", self.scale_x, self.scale_y));
+        }
+        if parts.is_empty() { return String::new(); }
+        format!(" {}=\"{}\"", name, parts.join(" "))
+    }
+}
@@ -22,31 +22,58 @@ impl SVGDoc {
         self.tree.get_group(group_id)
     }
 
-    pub fn add_group(&mut self, group_id: Option<String>, partial_group: PartialSVGGroup) {
+    pub fn add_group(&mut self, group_id: Option<String>, partial_group: PartialSVGGroup) -> Result<String, DocError> {
         self.tree.add_group(group_id, partial_group)
     }
 
+    pub fn add_group_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        new_group_id: String, 
+        partial_group: PartialSVGGroup
+    ) -> Result<String, DocError> {
+        self.tree.add_group_with_id(group_id, new_group_id, partial_group)
+    }
+
     pub fn get_circle(&self, circle_id: String) -> Option<SVGCircle>{
         self.tree.get_circle(circle_id)
     }
 
-    pub fn add_circle(&mut self, group_id: Option<String>, partial_circle: PartialSVGCircle) {
-        self.tree.add_circle(group_id, partial_circle);
+    pub fn add_circle(&mut self, group_id: Option<String>, partial_circle: PartialSVGCircle) -> Result<String, DocError> {
+        self.tree.add_circle(group_id, partial_circle)
+    }
+
+    pub fn add_circle_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        circle_id: String, 
+        partial_circle: PartialSVGCircle
+    ) -> Result<String, DocError> {
+        self.tree.add_circle_with_id(group_id, circle_id, partial_circle)
     }
 
-    pub fn edit_circle(&mut self, circle_id: String, edits: PartialSVGCircle) {
-        self.tree.edit_circle(circle_id, edits);
+    pub fn edit_circle(&mut self, circle_id: String, edits: PartialSVGCircle) -> Result<(), DocError> {
+        self.tree.edit_circle(circle_id, edits)
     }
 
     pub fn get_rectangle(&self, rectangle_id: String) -> Option<SVGRectangle> {
         self.tree.get_rectangle(rectangle_id)
     }
 
-    pub fn add_rectangle(&mut self, group_id: Option<String>, partial_rectangle: PartialSVGRectangle) {
+    pub fn add_rectangle(&mut self, group_id: Option<String>, partial_rectangle: PartialSVGRectangle) -> Result<String, DocError> {
         self.tree.add_rectangle(group_id, partial_rectangle)
     }
 
-    pub fn edit_rectangle(&mut self, rectangle_id: String, edits: PartialSVGRectangle) {
+    pub fn add_rectangle_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        rectangle_id: String, 
+        partial_rectangle: PartialSVGRectangle
+    ) -> Result<String, DocError> {
+        self.tree.add_rectangle_with_id(group_id, rectangle_id, partial_rectangle)
+    }
+
+    pub fn edit_rectangle(&mut self, rectangle_id: String, edits: PartialSVGRectangle) -> Result<(), DocError> {
         self.tree.edit_rectangle(rectangle_id, edits)
     }
 
@@ -54,19 +81,280 @@ impl SVGDoc {
         self.tree.get_path(path_id)
     }
 
-    pub fn add_path(&mut self, group_id: Option<String>, partial_path: PartialSVGPath) {
+    pub fn add_path(&mut self, group_id: Option<String>, partial_path: PartialSVGPath) -> Result<String, DocError> {
         self.tree.add_path(group_id, partial_path)
     }
 
-    pub fn edit_path(&mut self, path_id: String, partial_path: PartialSVGPath) {
+    pub fn add_path_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        path_id: String, 
+        partial_path: PartialSVGPath
+    ) -> Result<String, DocError> {
+        self.tree.add_path_with_id(group_id, path_id, partial_path)
+    }
+
+    pub fn edit_path(&mut self, path_id: String, partial_path: PartialSVGPath) -> Result<(), DocError> {
         self.tree.edit_path(path_id, partial_path)
     }
 
+    pub fn get_ellipse(&self, ellipse_id: String) -> Option<SVGEllipse> {
+        self.tree.get_ellipse(ellipse_id)
+    }
+
+    pub fn add_ellipse(&mut self, group_id: Option<String>, partial_ellipse: PartialSVGEllipse) -> Result<String, DocError> {
+        self.tree.add_ellipse(group_id, partial_ellipse)
+    }
+
+    pub fn add_ellipse_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        ellipse_id: String, 
+        partial_ellipse: PartialSVGEllipse
+    ) -> Result<String, DocError> {
+        self.tree.add_ellipse_with_id(group_id, ellipse_id, partial_ellipse)
+    }
+
+    pub fn edit_ellipse(&mut self, ellipse_id: String, edits: PartialSVGEllipse) -> Result<(), DocError> {
+        self.tree.edit_ellipse(ellipse_id, edits)
+    }
+
+    pub fn get_line(&self, line_id: String) -> Option<SVGLine> {
+        self.tree.get_line(line_id)
+    }
+
+    pub fn add_line(&mut self, group_id: Option<String>, partial_line: PartialSVGLine) -> Result<String, DocError> {
+        self.tree.add_line(group_id, partial_line)
+    }
+
+    pub fn add_line_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        line_id: String, 
+        partial_line: PartialSVGLine
+    ) -> Result<String, DocError> {
+        self.tree.add_line_with_id(group_id, line_id, partial_line)
+    }
+
+    pub fn edit_line(&mut self, line_id: String, edits: PartialSVGLine) -> Result<(), DocError> {
+        self.tree.edit_line(line_id, edits)
+    }
+
+    pub fn get_polyline(&self, polyline_id: String) -> Option<SVGPolyline> {
+        self.tree.get_polyline(polyline_id)
+    }
+
+    pub fn add_polyline(&mut self, group_id: Option<String>, partial_polyline: PartialSVGPolyline) -> Result<String, DocError> {
+        self.tree.add_polyline(group_id, partial_polyline)
+    }
+
+    pub fn add_polyline_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        polyline_id: String, 
+        partial_polyline: PartialSVGPolyline
+    ) -> Result<String, DocError> {
+        self.tree.add_polyline_with_id(group_id, polyline_id, partial_polyline)
+    }
+
+    pub fn edit_polyline(&mut self, polyline_id: String, edits: PartialSVGPolyline) -> Result<(), DocError> {
+        self.tree.edit_polyline(polyline_id, edits)
+    }
+
+    pub fn get_polygon(&self, polygon_id: String) -> Option<SVGPolygon> {
+        self.tree.get_polygon(polygon_id)
+    }
+
+    pub fn add_polygon(&mut self, group_id: Option<String>, partial_polygon: PartialSVGPolygon) -> Result<String, DocError> {
+        self.tree.add_polygon(group_id, partial_polygon)
+    }
+
+    pub fn add_polygon_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        polygon_id: String, 
+        partial_polygon: PartialSVGPolygon
+    ) -> Result<String, DocError> {
+        self.tree.add_polygon_with_id(group_id, polygon_id, partial_polygon)
+    }
+
+    pub fn edit_polygon(&mut self, polygon_id: String, edits: PartialSVGPolygon) -> Result<(), DocError> {
+        self.tree.edit_polygon(polygon_id, edits)
+    }
+
+    pub fn get_text(&self, text_id: String) -> Option<SVGText> {
+        self.tree.get_text(text_id)
+    }
+
+    pub fn add_text(&mut self, group_id: Option<String>, partial_text: PartialSVGText) -> Result<String, DocError> {
+        self.tree.add_text(group_id, partial_text)
+    }
+
+    pub fn add_text_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        text_id: String, 
+        partial_text: PartialSVGText
+    ) -> Result<String, DocError> {
+        self.tree.add_text_with_id(group_id, text_id, partial_text)
+    }
+
+    pub fn edit_text(&mut self, text_id: String, edits: PartialSVGText) -> Result<(), DocError> {
+        self.tree.edit_text(text_id, edits)
+    }
+
+    // `index` and `length` count UTF-16 code units, like JS strings do.
+    pub fn insert_text(&mut self, text_id: String, index: usize, text: String) -> Result<(), DocError> {
+        let index = self.char_index(&text_id, index)?;
+        self.tree.insert_text(text_id, index, text)
+    }
+
+    pub fn delete_text(&mut self, text_id: String, index: usize, length: usize) -> Result<(), DocError> {
+        let start = self.char_index(&text_id, index)?;
+        let end = self.char_index(&text_id, index.saturating_add(length))?;
+        self.tree.delete_text(text_id, start, end - start)
+    }
+
+    pub fn get_image(&self, image_id: String) -> Option<SVGImage> {
+        self.tree.get_image(image_id)
+    }
+
+    pub fn add_image(&mut self, group_id: Option<String>, partial_image: PartialSVGImage) -> Result<String, DocError> {
+        self.tree.add_image(group_id, partial_image)
+    }
+
+    pub fn add_image_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        image_id: String, 
+        partial_image: PartialSVGImage
+    ) -> Result<String, DocError> {
+        self.tree.add_image_with_id(group_id, image_id, partial_image)
+    }
+
+    pub fn edit_image(&mut self, image_id: String, edits: PartialSVGImage) -> Result<(), DocError> {
+        self.tree.edit_image(image_id, edits)
+    }
+
+    pub fn get_gradient(&self, gradient_id: String) -> Option<SVGGradient> {
+        self.tree.get_gradient(gradient_id)
+    }
+
+    pub fn add_gradient(&mut self, partial_gradient: PartialSVGGradient) -> Result<String, DocError> {
+        self.tree.add_gradient(partial_gradient)
+    }
+
+    pub fn add_gradient_with_id(
+        &mut self, 
+        gradient_id: String, 
+        partial_gradient: PartialSVGGradient
+    ) -> Result<String, DocError> {
+        self.tree.add_gradient_with_id(gradient_id, partial_gradient)
+    }
+
+    pub fn edit_gradient(&mut self, gradient_id: String, edits: PartialSVGGradient) -> Result<(), DocError> {
+        self.tree.edit_gradient(gradient_id, edits)
+    }
+
+    pub fn add_gradient_stop(&mut self, gradient_id: String, offset: f64, color: Color) -> Result<String, DocError> {
+        self.tree.add_gradient_stop(gradient_id, offset, color)
+    }
+
+    pub fn add_gradient_stop_with_id(
+        &mut self, 
+        gradient_id: String, 
+        stop_id: String, 
+        offset: f64, 
+        color: Color
+    ) -> Result<String, DocError> {
+        self.tree.add_gradient_stop_with_id(gradient_id, stop_id, offset, color)
+    }
+
+    pub fn edit_gradient_stop(
+        &mut self, 
+        gradient_id: String, 
+        stop_id: String, 
+        edits: PartialSVGGradientStop
+    ) -> Result<(), DocError> {
+        self.tree.edit_gradient_stop(gradient_id, stop_id, edits)
+    }
+
+    pub fn remove_gradient_stop(&mut self, gradient_id: String, stop_id: String) -> Result<(), DocError> {
+        self.tree.remove_gradient_stop(gradient_id, stop_id)
+    }
+
+    pub fn add_asset(&mut self, data: Vec<u8>, mime_type: String) -> String {
+        self.tree.add_asset(data, mime_type)
+    }
+
+    pub fn get_asset(&self, hash: String) -> Option<Vec<u8>> {
+        self.tree.get_asset(&hash).map(|asset| asset.data)
+    }
+
+    pub fn get_asset_url(&self, hash: String) -> Option<String> {
+        self.tree.get_asset(&hash).map(|asset| asset.data_url())
+    }
+
+    pub fn missing_assets(&self) -> Vec<String> {
+        self.tree.missing_assets()
+    }
+
+    pub fn broadcast_assets(&self, hashes: Vec<String>) -> String {
+        self.tree.broadcast_assets(hashes)
+    }
+
+    pub fn rotate_object(&mut self, object_id: String, degrees: f64, pivot: Vec2) -> Result<(), DocError> {
+        self.tree.rotate_object(object_id, degrees, pivot)
+    }
+
+    pub fn scale_object(&mut self, object_id: String, scale_x: f64, scale_y: f64, pivot: Vec2) -> Result<(), DocError> {
+        self.tree.scale_object(object_id, scale_x, scale_y, pivot)
+    }
+
+    pub fn world_transform(&self, object_id: String) -> Result<Matrix, DocError> {
+        self.tree.world_transform(object_id)
+    }
+
+    pub fn add_vertex(&mut self, object_id: String, pos: Vec2) -> Result<String, DocError> {
+        self.tree.add_vertex(object_id, pos)
+    }
+
+    pub fn add_vertex_with_id(&mut self, object_id: String, vertex_id: String, pos: Vec2) -> Result<String, DocError> {
+        self.tree.add_vertex_with_id(object_id, vertex_id, pos)
+    }
+
+    pub fn insert_vertex(
+        &mut self, 
+        object_id: String, 
+        after_vertex_id: Option<String>, 
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.tree.insert_vertex(object_id, after_vertex_id, pos)
+    }
+
+    pub fn insert_vertex_with_id(
+        &mut self, 
+        object_id: String, 
+        after_vertex_id: Option<String>, 
+        vertex_id: String, 
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.tree.insert_vertex_with_id(object_id, after_vertex_id, vertex_id, pos)
+    }
+
+    pub fn edit_vertex_pos(&mut self, object_id: String, vertex_id: String, new_pos: Vec2) -> Result<(), DocError> {
+        self.tree.edit_vertex_pos(object_id, vertex_id, new_pos)
+    }
+
+    pub fn remove_vertex(&mut self, object_id: String, vertex_id: String) -> Result<(), DocError> {
+        self.tree.remove_vertex(object_id, vertex_id)
+    }
+
     pub fn edit_group(
         &mut self,
         group_id: String,
         partial_group: PartialSVGGroup
-    ) {
+    ) -> Result<(), DocError> {
         console_log!("Editing group: {:?}", partial_group.fill);
         self.tree.edit_group(group_id, partial_group)
     }
@@ -76,7 +364,7 @@ impl SVGDoc {
         path_id: String, 
         point_id: String, 
         command_type: SVGPathCommandType, 
-    ) {
+    ) -> Result<(), DocError> {
         self.tree.edit_path_point_type(path_id, point_id, command_type)
     }
 
@@ -85,7 +373,7 @@ impl SVGDoc {
         path_id: String, 
         point_id: String, 
         new_pos: Vec2
-    ) {
+    ) -> Result<(), DocError> {
         self.tree.edit_path_point_pos(path_id, point_id, new_pos)
     }
 
@@ -94,7 +382,7 @@ impl SVGDoc {
         path_id: String, 
         point_id: String, 
         new_handle1: Vec2
-    ) {
+    ) -> Result<(), DocError> {
         self.tree.edit_path_point_handle1(path_id, point_id, new_handle1)
     }
 
@@ -103,7 +391,7 @@ impl SVGDoc {
         path_id: String, 
         point_id: String, 
         new_handle2: Vec2
-    ) {
+    ) -> Result<(), DocError> {
         self.tree.edit_path_point_handle2(path_id, point_id, new_handle2)
     }
 
@@ -112,42 +400,170 @@ impl SVGDoc {
         path_id: String, 
         command: SVGPathCommandType, 
         pos: Vec2
-    ) {
+    ) -> Result<String, DocError> {
         self.tree.add_point_to_path(path_id, command, pos)
     }
 
+    pub fn add_point_to_path_with_id(
+        &mut self, 
+        path_id: String, 
+        point_id: String, 
+        command: SVGPathCommandType, 
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.tree.add_point_to_path_with_id(path_id, point_id, command, pos)
+    }
+
+    pub fn insert_point_into_path(
+        &mut self, 
+        path_id: String, 
+        after_point_id: Option<String>, 
+        command: SVGPathCommandType, 
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.tree.insert_point_into_path(path_id, after_point_id, command, pos)
+    }
+
+    pub fn insert_point_into_path_with_id(
+        &mut self, 
+        path_id: String, 
+        after_point_id: Option<String>, 
+        point_id: String, 
+        command: SVGPathCommandType, 
+        pos: Vec2
+    ) -> Result<String, DocError> {
+        self.tree.insert_point_into_path_with_id(path_id, after_point_id, point_id, command, pos)
+    }
+
     pub fn move_object_to_group(
         &mut self, 
         object_id: String, 
         group_id: String, 
         index: usize
-    ) {
+    ) -> Result<(), DocError> {
         // self.tree.move_object_to_group(object_id, group_id, index)
         self.tree.move_object(Some(group_id), object_id, Some(index))
     }
 
-    pub fn move_object_to_root(&mut self, object_id: String, index: usize) {
+    pub fn move_object_to_root(&mut self, object_id: String, index: usize) -> Result<(), DocError> {
         // self.tree.move_object_to_root(object_id, index)
         self.tree.move_object(None, object_id, Some(index))
     }
 
-    pub fn remove_object(&mut self, object_id: String) {
-        self.tree.remove_object(object_id)
+    pub fn remove_object(&mut self, object_id: String, policy: DeletePolicy) -> Result<(), DocError> {
+        self.tree.remove_object(object_id, policy)
+    }
+
+    pub fn remove_group(&mut self, group_id: String, policy: DeletePolicy) -> Result<(), DocError> {
+        self.tree.remove_group(group_id, policy)
     }
 
     pub fn remove_path_point(
         &mut self, 
         path_id: String,
         point_id: String
-    ) {
+    ) -> Result<(), DocError> {
         self.tree.remove_path_point(path_id, point_id)
     }
 
-    pub fn save(&self) -> Option<String> {
-        Some(self.tree.save())
+    pub fn get_path_subpaths(&self, path_id: String) -> Result<SVGSubpaths, DocError> {
+        self.tree.get_path_subpaths(path_id).map(|subpaths| SVGSubpaths { subpaths })
+    }
+
+    pub fn move_subpath(&mut self, path_id: String, subpath_id: String, index: usize) -> Result<(), DocError> {
+        self.tree.move_subpath(path_id, subpath_id, index)
+    }
+
+    pub fn reverse_subpath(&mut self, path_id: String, subpath_id: String) -> Result<(), DocError> {
+        self.tree.reverse_subpath(path_id, subpath_id)
+    }
+
+    pub fn remove_subpath(&mut self, path_id: String, subpath_id: String) -> Result<(), DocError> {
+        self.tree.remove_subpath(path_id, subpath_id)
+    }
+
+    pub fn set_clip_path(&mut self, object_id: String, source_id: Option<String>) -> Result<(), DocError> {
+        self.tree.set_clip_path(object_id, source_id)
+    }
+
+    pub fn set_mask(&mut self, object_id: String, source_id: Option<String>) -> Result<(), DocError> {
+        self.tree.set_mask(object_id, source_id)
+    }
+
+    pub fn set_name(&mut self, object_id: String, name: String) -> Result<(), DocError> {
+        self.tree.set_name(object_id, name)
+    }
+
+    pub fn set_hidden(&mut self, object_id: String, hidden: bool) -> Result<(), DocError> {
+        self.tree.set_hidden(object_id, hidden)
+    }
+
+    pub fn set_locked(&mut self, object_id: String, locked: bool) -> Result<(), DocError> {
+        self.tree.set_locked(object_id, locked)
+    }
+
+    pub fn get_symbol(&self, symbol_id: String) -> Option<SVGGroup> {
+        self.tree.get_symbol(symbol_id)
+    }
+
+    pub fn add_symbol(&mut self, partial_group: PartialSVGGroup) -> Result<String, DocError> {
+        self.tree.add_symbol(partial_group)
+    }
+
+    pub fn add_symbol_with_id(&mut self, symbol_id: String, partial_group: PartialSVGGroup) -> Result<String, DocError> {
+        self.tree.add_symbol_with_id(symbol_id, partial_group)
+    }
+
+    pub fn get_instance(&self, instance_id: String) -> Option<SVGInstance> {
+        self.tree.get_instance(instance_id)
     }
 
-    pub fn load(&mut self, data: String) {
+    pub fn add_instance(&mut self, group_id: Option<String>, partial_instance: PartialSVGInstance) -> Result<String, DocError> {
+        self.tree.add_instance(group_id, partial_instance)
+    }
+
+    pub fn add_instance_with_id(
+        &mut self, 
+        group_id: Option<String>, 
+        instance_id: String, 
+        partial_instance: PartialSVGInstance
+    ) -> Result<String, DocError> {
+        self.tree.add_instance_with_id(group_id, instance_id, partial_instance)
+    }
+
+    pub fn edit_instance(&mut self, instance_id: String, edits: PartialSVGInstance) -> Result<(), DocError> {
+        self.tree.edit_instance(instance_id, edits)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.tree.undo()
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.tree.redo()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.tree.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.tree.can_redo()
+    }
+
+    pub fn begin_step(&mut self) {
+        self.tree.begin_step()
+    }
+
+    pub fn end_step(&mut self) {
+        self.tree.end_step()
+    }
+
+    pub fn save(&self) -> Result<String, DocError> {
+        self.tree.save()
+    }
+
+    pub fn load(&mut self, data: String) -> Result<(), DocError> {
         self.tree.load(data)
     }
 
@@ -155,16 +571,94 @@ impl SVGDoc {
         self.tree.broadcast()
     }
 
-    pub fn merge(&mut self, oplog: String) {
-        self.tree.merge(oplog);
+    pub fn broadcast_delta(&mut self) -> String {
+        self.tree.broadcast_delta()
+    }
+
+    pub fn broadcast_since(&self, version: String) -> Result<String, DocError> {
+        self.tree.broadcast_since(version)
+    }
+
+    pub fn version(&self) -> String {
+        self.tree.version()
+    }
+
+    pub fn ack(&mut self) -> String {
+        self.tree.ack()
+    }
+
+    pub fn receive_ack(&mut self, data: String) -> Result<(), DocError> {
+        self.tree.receive_ack(data)
+    }
+
+    pub fn compact(&mut self) -> Result<(), DocError> {
+        self.tree.compact()
+    }
+
+    pub fn merge(&mut self, oplog: String) -> Result<(), DocError> {
+        self.tree.merge(oplog)
+    }
+
+    // `Vec<u8>` and `&[u8]` cross into JS as `Uint8Array`.
+    pub fn save_binary(&self) -> Result<Vec<u8>, DocError> {
+        self.tree.save_binary()
+    }
+
+    pub fn load_binary(&mut self, data: &[u8]) -> Result<(), DocError> {
+        self.tree.load_binary(data)
+    }
+
+    pub fn broadcast_binary(&mut self) -> Result<Vec<u8>, DocError> {
+        self.tree.broadcast_binary()
+    }
+
+    pub fn broadcast_delta_binary(&mut self) -> Result<Vec<u8>, DocError> {
+        self.tree.broadcast_delta_binary()
+    }
+
+    pub fn merge_binary(&mut self, data: &[u8]) -> Result<(), DocError> {
+        self.tree.merge_binary(data)
     }
 
     pub fn children(&self) -> SVGDocTree {
         self.tree.tree()
     }
 
+    pub fn visible_children(&self) -> SVGDocTree {
+        self.tree.visible_tree()
+    }
+
+    pub fn import_svg(&mut self, svg_text: String, parent_group_id: Option<String>) -> Result<Vec<String>, DocError> {
+        self.tree.import_svg(&svg_text, parent_group_id)
+    }
+
+    pub fn export_svg(&self) -> String {
+        self.tree.export_svg()
+    }
+
+    pub fn export_visible_svg(&self) -> String {
+        self.tree.export_visible_svg()
+    }
+
     pub fn repr(&self) -> String {
         "NO_REPR".to_string()
         // self.tree.repr()
     }
 }
+
+impl SVGDoc {
+    // Turns a UTF-16 offset into the text content into a character index.
+    // An offset inside a surrogate pair goes past the pair. Missing texts
+    // are left for the document to report.
+    fn char_index(&self, text_id: &str, offset: usize) -> Result<usize, DocError> {
+        let Some(text) = self.tree.get_text(text_id.to_string()) else { return Ok(offset); };
+        let (mut units, mut chars) = (0, 0);
+        for c in text.content.chars() {
+            if units >= offset { break; }
+            units += c.len_utf16();
+            chars += 1;
+        }
+        if offset > units { return Err(DocError::OutOfRange { index: offset, len: units }); }
+        Ok(chars)
+    }
+}
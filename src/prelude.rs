@@ -17,23 +17,54 @@ pub use web_sys::*;
 pub use crate::*;
 pub(crate) use crate::crdt::core::*;
 pub(crate) use crate::crdt::core2::*;
+pub use crate::crdt::core2::DeletePolicy;
+pub(crate) use crate::crdt::undo::*;
 pub use crate::crdt::vtime::*;
 pub use crate::crdt::uw_map::*;
+pub use crate::crdt::sequence::*;
+pub use crate::crdt::assets::*;
 pub use crate::crdt::lww_reg::*;
+pub use crate::crdt::hlc::*;
+pub use crate::crdt::error::*;
 pub use serde::de::DeserializeOwned;
 pub use crate::{
     utility::*,
     element::{ 
         path::*,
+        import::*,
         *,
         lww::{
-            *,
+            LWWSVGObject,
+            index_between,
             circle::*,
             rectangle::*,
             path::*,
-            group::*
+            group::*,
+            ellipse::*,
+            line::*,
+            polyline::*,
+            polygon::*,
+            text::*,
+            image::*,
+            transform::*,
+            gradient::*,
+            instance::*,
+            stroke::*,
+            layer::*
         },
         circle::*,
+        ellipse::*,
+        line::*,
+        polyline::*,
+        polygon::*,
+        text::*,
+        image::*,
+        transform::*,
+        gradient::*,
+        instance::*,
+        stroke::*,
+        layer::*,
+        clip::*,
         rectangle::*,
         group::*
     },
@@ -33,8 +33,8 @@ pub fn gen_str_id() -> String {
 #[derive(Tsify, Serialize, Deserialize, Clone, Debug)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct Vec2 {
-    pub x: i32,
-    pub y: i32,
+    pub x: f64,
+    pub y: f64,
 }
 
 #[derive(Serialize, Deserialize, Tsify, Clone, Debug)]
@@ -54,6 +54,45 @@ impl Color {
     pub fn black() -> Color {
         Color(0, 0, 0, 1.0)
     }
+
+    // Writes ` fill="rgb(..)"` style attributes. The alpha goes into its own
+    // `*-opacity` attribute since SVG 1.1 has no `rgba()` paint. With a
+    // gradient the color is only the fallback for a missing one, and the
+    // alpha is left out as it would fade the gradient too.
+    pub(crate) fn svg_paint_attrs(&self, name: &str, gradient: &Option<NodeID>) -> String {
+        let Color(red, green, blue, alpha) = self;
+        if let Some(gradient) = gradient {
+            let fallback = match *alpha > 0.0 {
+                true => format!("rgb({}, {}, {})", red, green, blue),
+                false => "none".to_string(),
+            };
+            return format!(" {}=\"{} {}\"", name, gradient_url(gradient), fallback);
+        }
+        let mut attrs = format!(" {}=\"rgb({}, {}, {})\"", name, red, green, blue);
+        if *alpha < 1.0 {
+            attrs.push_str(&format!(" {}-opacity=\"{}\"", name, alpha));
+        }
+        attrs
+    }
+}
+
+pub(crate) fn gradient_url(gradient_id: &str) -> String {
+    format!("url(#{})", escape_xml_attr(gradient_id))
+}
+
+// Escapes text for use inside a double quoted XML attribute.
+pub(crate) fn escape_xml_attr(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 
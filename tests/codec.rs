@@ -0,0 +1,93 @@
+use inktor_crdt::prelude::*;
+use inktor_crdt::crdt::codec::{from_bytes, to_bytes, BINARY_FORMAT_VERSION};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Shape {
+    Empty,
+    Named(String),
+    Point { x: i32, y: i32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Sample {
+    small: u8,
+    negative: i64,
+    wide: u128,
+    ratio: f32,
+    label: Option<String>,
+    shapes: Vec<Shape>,
+    table: HashMap<String, i64>,
+}
+
+#[test]
+fn test_roundtrip() {
+    let sample = Sample {
+        small: 200,
+        negative: -123_456,
+        wide: u128::MAX,
+        ratio: 0.25,
+        label: Some("replica".to_string()),
+        shapes: vec![Shape::Empty, Shape::Named("replica".to_string()), Shape::Point { x: -1, y: 2 }],
+        table: HashMap::from([("replica".to_string(), i64::MIN)]),
+    };
+    let bytes = to_bytes(&sample).unwrap();
+    assert_eq!(from_bytes::<Sample>(&bytes).unwrap(), sample);
+}
+
+#[test]
+fn test_strings_are_interned() {
+    let once = to_bytes(&vec!["replica-id".to_string()]).unwrap();
+    let thrice = to_bytes(&vec!["replica-id".to_string(); 3]).unwrap();
+    // Repeats cost a tag and an index byte each.
+    assert_eq!(thrice.len(), once.len() + 4);
+}
+
+#[derive(Serialize)]
+struct Before {
+    kept: i64,
+    dropped: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct After {
+    kept: i64,
+    #[serde(default)]
+    added: Option<Shape>,
+}
+
+#[test]
+fn test_fields_are_named() {
+    // Fields are matched by name, so types can gain and lose fields.
+    let bytes = to_bytes(&Before { kept: -1, dropped: "gone".to_string() }).unwrap();
+    assert_eq!(from_bytes::<After>(&bytes).unwrap(), After { kept: -1, added: None });
+    // Reading fewer entries than were written is an error, not a misread.
+    let bytes = to_bytes(&((1u8, 2u8, 3u8), 4u8)).unwrap();
+    assert!(matches!(from_bytes::<((u8, u8), u8)>(&bytes), Err(DocError::DecodeError(_))));
+}
+
+#[test]
+fn test_rejects_bad_data() {
+    let mut bytes = to_bytes(&(1u32, "id".to_string())).unwrap();
+    assert!(matches!(from_bytes::<(u32, String)>(&bytes[..bytes.len() - 1]), Err(DocError::DecodeError(_))));
+    assert!(matches!(from_bytes::<(u32, String)>(b"{}"), Err(DocError::DecodeError(_))));
+    bytes[4] = (BINARY_FORMAT_VERSION + 1) as u8;
+    assert_eq!(
+        from_bytes::<(u32, String)>(&bytes).err(),
+        Some(DocError::VersionMismatch { expected: BINARY_FORMAT_VERSION, found: BINARY_FORMAT_VERSION + 1 })
+    );
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Flattened {
+    id: String,
+    #[serde(flatten)]
+    rest: HashMap<String, i64>,
+}
+
+#[test]
+fn test_maps_of_unknown_length() {
+    // Flattened structs are written without knowing their length up front.
+    let value = Flattened { id: "a".to_string(), rest: HashMap::from([("b".to_string(), 300), ("c".to_string(), -2)]) };
+    let bytes = to_bytes(&value).unwrap();
+    assert_eq!(from_bytes::<Flattened>(&bytes).unwrap(), value);
+}
@@ -3,23 +3,31 @@ use inktor_crdt::prelude::*;
 fn empty_circle() -> PartialSVGCircle {
     PartialSVGCircle {
         fill: None,
+        fill_gradient: None,
         opacity: None,
         pos: None,
         radius: None,
         stroke: None,
-        stroke_width: None
+        stroke_gradient: None,
+        stroke_style: PartialStrokeStyle::empty(),
+        stroke_width: None,
+        transform: None
     }
 }
 
 fn empty_rectangle() -> PartialSVGRectangle {
     PartialSVGRectangle {
         fill: None,
+        fill_gradient: None,
         pos: None,
         height: None,
         width: None,
         stroke_width: None,
         stroke: None,
-        opacity: None
+        stroke_gradient: None,
+        stroke_style: PartialStrokeStyle::empty(),
+        opacity: None,
+        transform: None
     }
 }
 
@@ -27,7 +35,7 @@ fn empty_rectangle() -> PartialSVGRectangle {
 fn test_create_circle() {
     let mut doc = SVGDoc::new("1".to_string());
     let circle = empty_circle();
-    doc.add_circle(None, circle);
+    doc.add_circle(None, circle).unwrap();
     assert!(doc.children().children.iter().all(|o| {
         match o {
             SVGObject::Circle(_) => true,
@@ -41,19 +49,19 @@ fn test_create_circle() {
 fn test_edit_circle() {
     let mut doc = SVGDoc::new("1".to_string());
     let circle = empty_circle();
-    doc.add_circle(None, circle);
+    doc.add_circle(None, circle).unwrap();
     assert_eq!(doc.children().children.len(), 1);
     let circle_id = doc.children().children[0].get_id().to_string();
     let mut circle_edits = empty_circle();
-    circle_edits.pos = Some(Vec2 { x: 100, y: 50 });
-    doc.edit_circle(circle_id, circle_edits);
+    circle_edits.pos = Some(Vec2 { x: 100.0, y: 50.0 });
+    doc.edit_circle(circle_id, circle_edits).unwrap();
     assert!(doc.children().children.iter().all(|o| {
         match o {
             SVGObject::Circle(c) => {
-                if !c.pos.x == 100 {
+                if c.pos.x != 100.0 {
                     return false;
                 }
-                if !c.pos.y == 50 {
+                if c.pos.y != 50.0 {
                     return false;
                 }
                 true
@@ -68,7 +76,7 @@ fn test_edit_circle() {
 fn test_remove_circle() {
     let mut doc = SVGDoc::new("1".to_string());
     let circle = empty_circle();
-    doc.add_circle(None, circle);
+    doc.add_circle(None, circle).unwrap();
     assert!(doc.children().children.iter().all(|o| {
         match o {
             SVGObject::Circle(_) => true,
@@ -82,7 +90,7 @@ fn test_remove_circle() {
         _ => panic!("Child should be a circle")
     };
 
-    doc.remove_object(id);
+    doc.remove_object(id, DeletePolicy::DELETE_SUBTREE).unwrap();
     assert_eq!(doc.children().children.len(), 0, "Assert circle does not exist");
 }
 
@@ -91,7 +99,7 @@ fn test_create_rectangle() {
     let mut doc = SVGDoc::new("1".to_string());
     let rect = empty_rectangle();
 
-    doc.add_rectangle(None, rect);
+    doc.add_rectangle(None, rect).unwrap();
     assert!(doc.children().children.iter().all(|o| {
         match o {
             SVGObject::Rectangle(_) => true,
@@ -106,12 +114,12 @@ fn test_edit_rectangle() {
     let mut doc = SVGDoc::new("1".to_string());
     let rect = empty_rectangle();
 
-    doc.add_rectangle(None, rect);
+    doc.add_rectangle(None, rect).unwrap();
 
     let rect_id = doc.children().children[0].get_id().to_string();
     let mut rect_edits = empty_rectangle();
     rect_edits.opacity = Some(0.5);
-    doc.edit_rectangle(rect_id, rect_edits);
+    doc.edit_rectangle(rect_id, rect_edits).unwrap();
     assert!(doc.children().children.iter().all(|o| {
         match o {
             SVGObject::Rectangle(o) => {
@@ -127,12 +135,12 @@ fn test_edit_rectangle() {
 #[test]
 fn test_move_ancestor_into_grandchild_failed() {
     let mut doc = SVGDoc::new("1".to_string());
-    doc.add_group(None, PartialSVGGroup::empty());
+    doc.add_group(None, PartialSVGGroup::empty()).unwrap();
     let group1_id = match &doc.children().children[0] {
         SVGObject::Group(g) => g.id.clone(),
         _ => panic!("First should be group")
     };
-    doc.add_group(Some(group1_id.clone()), PartialSVGGroup::empty());
+    doc.add_group(Some(group1_id.clone()), PartialSVGGroup::empty()).unwrap();
     let children = doc.children();
     let group2_id = match children.children.get(0) {
         Some(SVGObject::Group(g)) => {
@@ -146,7 +154,10 @@ fn test_move_ancestor_into_grandchild_failed() {
     assert_eq!(doc.children().children.len(), 1);
 
     // Operation must fail. With nothing changed in the tree.
-    doc.move_object_to_group(group1_id.clone(), group2_id.clone(), 0);
+    assert_eq!(
+        doc.move_object_to_group(group1_id.clone(), group2_id.clone(), 0),
+        Err(DocError::CycleRejected { object_id: group1_id.clone(), group_id: group2_id.clone() })
+    );
 
     assert_eq!(doc.children().children.len(), 1);
 
@@ -167,4 +178,19 @@ fn test_move_ancestor_into_grandchild_failed() {
 
     assert_eq!(group1_id, exp_group1_id);
     assert_eq!(group2_id, exp_group2_id);
+}
+
+#[test]
+fn test_text_offsets_are_utf16() {
+    let mut doc = SVGDoc::new("1".to_string());
+    let text_id = doc.add_text(None, PartialSVGText {
+        content: Some("a😀b".to_string()),
+        ..PartialSVGText::empty()
+    }).unwrap();
+    // The emoji is two code units in JS.
+    doc.insert_text(text_id.clone(), 3, "c".to_string()).unwrap();
+    assert_eq!(doc.get_text(text_id.clone()).unwrap().content, "a😀cb");
+    doc.delete_text(text_id.clone(), 1, 2).unwrap();
+    assert_eq!(doc.get_text(text_id.clone()).unwrap().content, "acb");
+    assert!(matches!(doc.insert_text(text_id, 4, "d".to_string()), Err(DocError::OutOfRange { index: 4, len: 3 })));
 }
\ No newline at end of file
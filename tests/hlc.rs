@@ -0,0 +1,29 @@
+use inktor_crdt::prelude::*;
+
+#[test]
+fn test_now_is_monotonic() {
+    let mut clock = HybridLogicalClock::new();
+    let mut prev = clock.now();
+    for _ in 0..1000 {
+        let next = clock.now();
+        assert!(next > prev);
+        prev = next;
+    }
+}
+
+#[test]
+fn test_update_moves_past_remote() {
+    let mut clock = HybridLogicalClock::new();
+    let hour = 3_600_000_000_000;
+    let remote = HybridLogicalClock::pack(epoch_now_nanos() + hour, 7);
+    clock.update(remote);
+    assert!(clock.timestamp() > remote);
+    assert!(clock.now() > remote);
+}
+
+#[test]
+fn test_pack_unpack() {
+    let ts = HybridLogicalClock::pack(123_456_789, 42);
+    assert_eq!(HybridLogicalClock::unpack(ts), (123_456_789, 42));
+    assert!(HybridLogicalClock::pack(2, 0) > HybridLogicalClock::pack(1, u32::MAX));
+}
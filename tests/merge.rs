@@ -3,10 +3,10 @@ use inktor_crdt::prelude::*;
 #[test]
 fn test_merge() {
     let mut doc1 = SVGDoc::new("1".to_string());
-    doc1.add_circle(None, PartialSVGCircle::empty());
-    let Some(merge_str) = doc1.save() else { return; };
+    doc1.add_circle(None, PartialSVGCircle::empty()).unwrap();
+    let merge_str = doc1.save().unwrap();
     let mut doc2 = SVGDoc::new("2".to_string());
-    doc2.merge(merge_str);
+    doc2.merge(merge_str).unwrap();
     let children1 =  doc1.children();
     let children2 = doc2.children();
     let Some(SVGObject::Circle(expected_circle)) = children1.children.first() else { 
@@ -3,22 +3,30 @@ use inktor_crdt::prelude::*;
 pub fn empty_circle() -> PartialSVGCircle {
     PartialSVGCircle {
         fill: None,
+        fill_gradient: None,
         opacity: None,
         pos: None,
         radius: None,
         stroke: None,
-        stroke_width: None
+        stroke_gradient: None,
+        stroke_style: PartialStrokeStyle::empty(),
+        stroke_width: None,
+        transform: None
     }
 }
 
 pub fn empty_rectangle() -> PartialSVGRectangle {
     PartialSVGRectangle {
         fill: None,
+        fill_gradient: None,
         pos: None,
         height: None,
         width: None,
         stroke_width: None,
         stroke: None,
-        opacity: None
+        stroke_gradient: None,
+        stroke_style: PartialStrokeStyle::empty(),
+        opacity: None,
+        transform: None
     }
 }
\ No newline at end of file
@@ -83,4 +83,66 @@ fn test_all_keys_exist() {
     assert_eq!(mm.get("kd").map(|v| v.value()), Some(&"vd"));
     assert_eq!(mm.get("ke").map(|v| v.value()), Some(&"ve"));
     assert_eq!(mm.get("kf").map(|v| v.value()), Some(&"vf"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_delta_since() {
+    let r1 = "r1";
+    let r2 = "r2";
+    let mut map1 = UWMap::<&str, LWWReg<&str>>::new();
+    map1.insert(r1.to_string(), "ka", LWWReg::new("va"));
+    map1.insert(r1.to_string(), "kb", LWWReg::new("vb"));
+    let mut map2 = map1.clone();
+
+    map1.insert(r1.to_string(), "kb", LWWReg::new("vb2"));
+    map1.remove(r1.to_string(), "ka");
+    map1.insert(r1.to_string(), "kc", LWWReg::new("vc"));
+
+    let delta = map1.delta_since(&map2.version());
+    assert_eq!(delta.keys(), HashSet::from(["ka", "kb", "kc"]));
+
+    map2.insert(r2.to_string(), "kd", LWWReg::new("vd"));
+    let delta = map1.delta_since(&map1.version());
+    assert_eq!(delta.keys().len(), 0);
+
+    let merged = UWMap::merge(&map2, &map1.delta_since(&map2.version()));
+    let mm = merged.value();
+    assert_eq!(mm.get("ka").map(|v| v.value()), None);
+    assert_eq!(mm.get("kb").map(|v| v.value()), Some(&"vb2"));
+    assert_eq!(mm.get("kc").map(|v| v.value()), Some(&"vc"));
+    assert_eq!(mm.get("kd").map(|v| v.value()), Some(&"vd"));
+}
+
+#[test]
+fn test_lww_reg_tie_breaks_by_replica() {
+    let a = LWWReg::new_at("a", &Stamp::new(10, "r1".to_string()));
+    let b = LWWReg::new_at("b", &Stamp::new(10, "r2".to_string()));
+    assert_eq!(LWWReg::merge(&a, &b).value(), &"b");
+    assert_eq!(LWWReg::merge(&b, &a).value(), &"b");
+
+    let mut map1 = UWMap::<&str, LWWReg<&str>>::new();
+    let mut map2 = UWMap::<&str, LWWReg<&str>>::new();
+    map1.insert("r1".to_string(), "key", a);
+    map2.insert("r2".to_string(), "key", b);
+    let m12 = UWMap::merge(&map1, &map2);
+    let m21 = UWMap::merge(&map2, &map1);
+    assert_eq!(m12.get(&"key").map(|v| v.value()), Some(&"b"));
+    assert_eq!(m21.get(&"key").map(|v| v.value()), Some(&"b"));
+}
+
+#[test]
+fn test_compact_drops_acknowledged_tombstones() {
+    let r1 = "r1";
+    let mut map = UWMap::<&str, LWWReg<&str>>::new();
+    map.insert(r1.to_string(), "a", LWWReg::new("thing"));
+    map.insert(r1.to_string(), "b", LWWReg::new("thing"));
+    map.remove(r1.to_string(), "a");
+    map.remove(r1.to_string(), "b");
+    let mut acked = map.version();
+    acked.remove("b");
+
+    map.compact(&acked);
+    assert!(map.vtime(&"a").is_none());
+    assert!(map.vtime(&"b").is_some());
+    assert!(map.value().is_empty());
+}